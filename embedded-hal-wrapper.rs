@@ -33,14 +33,48 @@
 
 #![no_std]
 
+#[cfg(feature = "std")]
+extern crate std;
+
 use drv_i2c_api::{I2cDevice, ResponseCode};
 use drv_i2c_types::{Controller, PortIndex, Mux, Segment};
 use userlib::TaskId;
 use embedded_hal::i2c::{ErrorKind, ErrorType, NoAcknowledgeSource, Operation};
 
+/// Largest payload this crate moves in one request, or in one transaction
+///
+/// A crate policy, not a server limit: the server leases each request's
+/// buffer separately. It is sized for the longest read the crate issues,
+/// a 255-byte SMBus/PMBus block plus its count byte, and reused as the
+/// budget for a whole transaction so a batch can be checked before any of
+/// it runs.
+pub const MAX_TRANSFER_LEN: usize = 256;
+
+/// See [`HubrisI2c::max_10bit_write_len`]
+const MAX_10BIT_WRITE_LEN: usize = 256;
+
+/// Default for [`HubrisI2c::with_max_transaction_ops`]
+///
+/// Far more operations than any real register protocol uses in one go.
+pub const DEFAULT_MAX_TRANSACTION_OPS: usize = 64;
+
 /// Embedded-HAL I2C wrapper for Hubris IPC-based I2C
-pub struct HubrisI2c {
+///
+/// Requests go to the server through `B`, [`IpcBackend`] unless replaced
+/// with [`with_backend`](Self::with_backend).
+///
+/// The handle is `Send` but not `Sync`: [`last_operation`](Self::last_operation)
+/// is kept in a `Cell` so that recording it doesn't need `&mut self`.
+pub struct HubrisI2c<B = IpcBackend> {
     device: I2cDevice,
+    backend: B,
+    byte_order: Option<ByteOrder>,
+    name: Option<&'static str>,
+    last_op: core::cell::Cell<Option<LastOp>>,
+    max_transaction_ops: usize,
+    priority: Option<u8>,
+    #[cfg(feature = "watchdog")]
+    op_timeout: Option<core::time::Duration>,
 }
 
 impl HubrisI2c {
@@ -68,6 +102,14 @@ impl HubrisI2c {
                 segment,
                 device_address,
             ),
+            backend: IpcBackend,
+            byte_order: None,
+            name: None,
+            last_op: core::cell::Cell::new(None),
+            max_transaction_ops: DEFAULT_MAX_TRANSACTION_OPS,
+            priority: None,
+            #[cfg(feature = "watchdog")]
+            op_timeout: None,
         }
     }
 
@@ -83,6 +125,222 @@ impl HubrisI2c {
         Self::new(i2c_server_task, controller, port, None, device_address)
     }
 
+    /// Largest total payload, in bytes, accepted for one transaction
+    pub const fn max_transfer_len() -> usize {
+        MAX_TRANSFER_LEN
+    }
+
+    /// Largest block [`read_block16`](HubrisI2c::read_block16) supports
+    ///
+    /// What is left of [`MAX_TRANSFER_LEN`] after the two length bytes.
+    pub const fn max_block16_len() -> usize {
+        MAX_TRANSFER_LEN - 2
+    }
+
+    /// Largest data payload of a 10-bit write
+    ///
+    /// 256 bytes, the capacity the 10-bit path has always had. The low
+    /// address byte is staged ahead of the data in a buffer of its own;
+    /// one write is one server request, so this is independent of
+    /// [`MAX_TRANSFER_LEN`]. Longer writes fail up front with
+    /// `TooMuchData`, reporting both sizes in [`ErrorDetail::TooLong`].
+    pub const fn max_10bit_write_len() -> usize {
+        MAX_10BIT_WRITE_LEN
+    }
+
+    /// Check a transaction's total size before issuing any of it
+    ///
+    /// Sums the read and write lengths of `operations` and fails with
+    /// `TooMuchData` if they exceed [`max_transfer_len`](Self::max_transfer_len),
+    /// reporting both in [`ErrorDetail::TooLong`]. The operations are
+    /// separate server requests, so this is a deliberate budget for the
+    /// batch rather than a single transfer's size.
+    /// `transaction` calls this first, so an oversized batch fails cleanly
+    /// instead of partway through with the device half-updated.
+    pub fn validate_transaction(operations: &[Operation<'_>]) -> Result<(), HubrisI2cError> {
+        let total: usize = operations
+            .iter()
+            .map(|operation| match operation {
+                Operation::Read(buffer) => buffer.len(),
+                Operation::Write(data) => data.len(),
+            })
+            .sum();
+
+        if total > MAX_TRANSFER_LEN {
+            return Err(HubrisI2cError {
+                response_code: ResponseCode::TooMuchData,
+                operation: "transaction_size",
+                bus: None,
+                detail: Some(ErrorDetail::TooLong {
+                    limit: MAX_TRANSFER_LEN,
+                    attempted: total,
+                }),
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl<B: I2cBackend> HubrisI2c<B> {
+    /// Send this handle's requests through `backend` instead
+    ///
+    /// Routing and configuration are kept. Host tests use this to put a
+    /// fake server behind a real `HubrisI2c`.
+    pub fn with_backend<B2: I2cBackend>(self, backend: B2) -> HubrisI2c<B2> {
+        HubrisI2c {
+            device: self.device,
+            backend,
+            byte_order: self.byte_order,
+            name: self.name,
+            last_op: self.last_op,
+            max_transaction_ops: self.max_transaction_ops,
+            priority: self.priority,
+            #[cfg(feature = "watchdog")]
+            op_timeout: self.op_timeout,
+        }
+    }
+
+    /// Configure the byte order of the device's multi-byte registers
+    ///
+    /// Once set, [`read_register`](Self::read_register) converts values
+    /// from the device order to host order. Without it values are returned
+    /// as raw host-order reinterpretations of the bytes on the wire.
+    pub fn with_byte_order(mut self, byte_order: ByteOrder) -> Self {
+        self.byte_order = Some(byte_order);
+        self
+    }
+
+    /// Byte order configured with [`with_byte_order`](Self::with_byte_order)
+    pub fn byte_order(&self) -> Option<ByteOrder> {
+        self.byte_order
+    }
+
+    /// Name the bus for diagnostics, e.g. `"front_sensors"`
+    ///
+    /// The name is carried in every error this wrapper returns and shown
+    /// in its `Display` output, so logs from several buses can be told
+    /// apart without decoding controller and port numbers.
+    pub fn with_name(mut self, name: &'static str) -> Self {
+        self.name = Some(name);
+        self
+    }
+
+    /// Name configured with [`with_name`](Self::with_name)
+    pub fn name(&self) -> Option<&'static str> {
+        self.name
+    }
+
+    /// Cap the number of operations one `transaction` may contain
+    ///
+    /// Each operation is a separate server request, so a runaway list from
+    /// a buggy caller would hold the bus and starve other tasks. Longer
+    /// transactions fail with `BadArg` before anything is issued. Defaults
+    /// to [`DEFAULT_MAX_TRANSACTION_OPS`].
+    pub fn with_max_transaction_ops(mut self, max: usize) -> Self {
+        self.max_transaction_ops = max;
+        self
+    }
+
+    /// Limit configured with [`with_max_transaction_ops`](Self::with_max_transaction_ops)
+    pub fn max_transaction_ops(&self) -> usize {
+        self.max_transaction_ops
+    }
+
+    /// Most recent request this handle sent to the server
+    ///
+    /// Recorded on the one path every request takes, just before it goes
+    /// out, so after a hang this names the request that never returned
+    /// whichever method issued it: the `I2c` trait impls (7- and 10-bit),
+    /// the inherent helpers, and the [`RegisterOptimizedI2c`] fast paths
+    /// alike. A call made of several requests shows up as its last one.
+    pub fn last_operation(&self) -> Option<LastOp> {
+        self.last_op.get()
+    }
+
+    fn record_op(&self, kind: LastOpKind, first_byte: Option<u8>, len: usize) {
+        self.last_op.set(Some(LastOp {
+            kind,
+            first_byte,
+            len,
+        }));
+    }
+
+    // Every server request is made through one of these, so per-request
+    // behavior only has to be added in one place
+
+    /// Issue `request` with the current priority hint, bounded by the
+    /// timeout set with `set_op_timeout`
+    fn issue<T>(
+        &self,
+        request: impl FnOnce(&B) -> Result<T, ResponseCode>,
+    ) -> Result<T, ResponseCode> {
+        let request = |backend: &B| match self.priority {
+            Some(priority) => backend.with_priority(priority, request),
+            None => request(backend),
+        };
+
+        #[cfg(feature = "watchdog")]
+        if let Some(timeout) = self.op_timeout {
+            return self.backend.with_timeout(timeout, request);
+        }
+
+        request(&self.backend)
+    }
+
+    fn request_write(&self, device: &I2cDevice, bytes: &[u8]) -> Result<(), ResponseCode> {
+        self.record_op(LastOpKind::Write, bytes.first().copied(), bytes.len());
+        self.issue(|backend| backend.write(device, bytes))
+    }
+
+    fn request_read_into(
+        &self,
+        device: &I2cDevice,
+        buffer: &mut [u8],
+    ) -> Result<usize, ResponseCode> {
+        self.record_op(LastOpKind::Read, None, buffer.len());
+        self.issue(|backend| backend.read_into(device, buffer))
+    }
+
+    fn record_write_read(&self, reg: &[u8], read_len: usize) {
+        self.record_op(LastOpKind::WriteRead, reg.first().copied(), reg.len() + read_len);
+    }
+
+    fn request_read_reg_into<R>(
+        &self,
+        device: &I2cDevice,
+        reg: R,
+        buffer: &mut [u8],
+    ) -> Result<usize, ResponseCode>
+    where
+        R: zerocopy::IntoBytes + zerocopy::Immutable,
+    {
+        self.record_write_read(reg.as_bytes(), buffer.len());
+        self.issue(|backend| backend.read_reg_into(device, reg, buffer))
+    }
+
+    fn request_read_reg<R, V>(&self, device: &I2cDevice, reg: R) -> Result<V, ResponseCode>
+    where
+        R: zerocopy::IntoBytes + zerocopy::Immutable,
+        V: zerocopy::IntoBytes + zerocopy::FromBytes,
+    {
+        self.record_write_read(reg.as_bytes(), core::mem::size_of::<V>());
+        self.issue(|backend| backend.read_reg(device, reg))
+    }
+
+    fn request_read_block<R>(
+        &self,
+        device: &I2cDevice,
+        reg: R,
+        buffer: &mut [u8],
+    ) -> Result<usize, ResponseCode>
+    where
+        R: zerocopy::IntoBytes + zerocopy::Immutable,
+    {
+        self.record_write_read(reg.as_bytes(), buffer.len());
+        self.issue(|backend| backend.read_block(device, reg, buffer))
+    }
+
     /// Get reference to underlying Hubris device for advanced operations
     ///
     /// This allows access to Hubris-specific optimized operations like
@@ -100,17 +358,70 @@ impl HubrisI2c {
     ///
     /// This bypasses the embedded-hal interface for optimal performance
     /// when reading typed register values.
+    ///
+    /// If a byte order is configured, `V` is converted from device to host
+    /// order by reversing its bytes, which is only meaningful for scalar
+    /// values (`u16`, `i32`, ...), not structs.
     pub fn read_register<R, V>(&self, reg: R) -> Result<V, HubrisI2cError>
     where
         R: zerocopy::IntoBytes + zerocopy::Immutable,
         V: zerocopy::IntoBytes + zerocopy::FromBytes,
     {
-        self.device
-            .read_reg(reg)
+        let mut value = self
+            .request_read_reg(&self.device, reg)
             .map_err(|response_code| HubrisI2cError {
                 response_code,
                 operation: "optimized_register_read",
-            })
+                bus: self.name,
+                detail: None,
+            })?;
+
+        if let Some(byte_order) = self.byte_order {
+            byte_order.to_host(&mut value);
+        }
+
+        Ok(value)
+    }
+
+    /// Typed register read of a big-endian value
+    ///
+    /// Like [`read_register`](Self::read_register), but always converts
+    /// `V` from big-endian to host order, whatever byte order is
+    /// configured. Only meaningful for scalar values.
+    pub fn read_register_be<R, V>(&self, reg: R) -> Result<V, HubrisI2cError>
+    where
+        R: zerocopy::IntoBytes + zerocopy::Immutable,
+        V: zerocopy::IntoBytes + zerocopy::FromBytes,
+    {
+        self.read_register_ordered(reg, ByteOrder::Big)
+    }
+
+    /// Typed register read of a little-endian value, see
+    /// [`read_register_be`](Self::read_register_be)
+    pub fn read_register_le<R, V>(&self, reg: R) -> Result<V, HubrisI2cError>
+    where
+        R: zerocopy::IntoBytes + zerocopy::Immutable,
+        V: zerocopy::IntoBytes + zerocopy::FromBytes,
+    {
+        self.read_register_ordered(reg, ByteOrder::Little)
+    }
+
+    fn read_register_ordered<R, V>(&self, reg: R, byte_order: ByteOrder) -> Result<V, HubrisI2cError>
+    where
+        R: zerocopy::IntoBytes + zerocopy::Immutable,
+        V: zerocopy::IntoBytes + zerocopy::FromBytes,
+    {
+        let mut value = self
+            .request_read_reg(&self.device, reg)
+            .map_err(|response_code| HubrisI2cError {
+                response_code,
+                operation: "optimized_register_read",
+                bus: self.name,
+                detail: None,
+            })?;
+
+        byte_order.to_host(&mut value);
+        Ok(value)
     }
 
     /// Perform SMBus block read using Hubris API
@@ -125,849 +436,10106 @@ impl HubrisI2c {
     where
         R: zerocopy::IntoBytes + zerocopy::Immutable,
     {
-        self.device
-            .read_block(reg, buffer)
+        self.request_read_block(&self.device, reg, buffer)
             .map_err(|response_code| HubrisI2cError {
                 response_code,
                 operation: "smbus_block_read",
+                bus: self.name,
+                detail: None,
             })
     }
-}
 
-/// Error type that maps Hubris ResponseCode to embedded-hal errors
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct HubrisI2cError {
-    pub response_code: ResponseCode,
-    pub operation: &'static str,
-}
+    /// Read a block with a 16-bit big-endian length prefix
+    ///
+    /// For vendor protocols whose blocks outgrow SMBus's one-byte count:
+    /// the device answers `command` with a two-byte big-endian length
+    /// followed by that many data bytes, which are copied into `buffer`.
+    ///
+    /// The prefix and data arrive in one transfer, so blocks of more than
+    /// [`max_block16_len`](HubrisI2c::max_block16_len) data bytes are not
+    /// supported: a reported length beyond that fails with `TooMuchData`,
+    /// with both sizes in [`ErrorDetail::TooLong`], whatever the size of
+    /// `buffer`. Within the limit, the same policy as
+    /// [`smbus_read_block`](Self::smbus_read_block) applies: a block
+    /// longer than `buffer` is truncated to it, and the number of bytes
+    /// stored is returned.
+    pub fn read_block16(&self, command: u8, buffer: &mut [u8]) -> Result<usize, HubrisI2cError> {
+        let wire_len = (buffer.len() + 2).min(HubrisI2c::max_transfer_len());
+        let mut wire = [0u8; MAX_TRANSFER_LEN];
+        self.request_read_reg_into(&self.device, command, &mut wire[..wire_len])
+            .map_err(|response_code| HubrisI2cError {
+                response_code,
+                operation: "read_block16",
+                bus: self.name,
+                detail: None,
+            })?;
 
-impl embedded_hal::i2c::Error for HubrisI2cError {
-    fn kind(&self) -> ErrorKind {
-        match self.response_code {
-            ResponseCode::Success => ErrorKind::Other, // Should not happen
+        let len = usize::from(u16::from_be_bytes([wire[0], wire[1]]));
+        if len > HubrisI2c::max_block16_len() {
+            return Err(HubrisI2cError {
+                response_code: ResponseCode::TooMuchData,
+                operation: "read_block16_length",
+                bus: self.name,
+                detail: Some(ErrorDetail::TooLong {
+                    limit: HubrisI2c::max_block16_len(),
+                    attempted: len,
+                }),
+            });
+        }
 
-            // Address/Data NACK errors
-            ResponseCode::AddressNackSentEarly | ResponseCode::AddressNackSentLate => {
-                ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address)
-            }
-            ResponseCode::DataNackSent => {
-                ErrorKind::NoAcknowledge(NoAcknowledgeSource::Data)
-            }
+        let count = len.min(buffer.len());
+        buffer[..count].copy_from_slice(&wire[2..count + 2]);
+        Ok(count)
+    }
 
-            // Bus condition errors
-            ResponseCode::BusError => ErrorKind::Bus,
-            ResponseCode::ArbitrationLost => ErrorKind::ArbitrationLoss,
+    /// Optional features supported by the connected I2C server
+    ///
+    /// Reported by the backend; for [`IpcBackend`] that is the build-time
+    /// description of the stock server in [`Capabilities::SERVER`], since
+    /// `drv-i2c-api` has no capability query.
+    pub fn capabilities(&self) -> Capabilities {
+        self.backend.capabilities()
+    }
 
-            // All other errors map to Other
-            _ => ErrorKind::Other,
+    /// Fail fast if the server lacks any of the `required` capabilities
+    ///
+    /// Returns `OperationNotSupported` tagged with `operation` so callers
+    /// get a clear error instead of sending a request the server can't honor.
+    /// The missing feature is recorded for
+    /// [`HubrisI2cError::unsupported_feature`].
+    pub fn require(
+        &self,
+        required: Capabilities,
+        operation: &'static str,
+    ) -> Result<(), HubrisI2cError> {
+        if self.capabilities().contains(required) {
+            Ok(())
+        } else {
+            Err(HubrisI2cError {
+                response_code: ResponseCode::OperationNotSupported,
+                operation,
+                bus: self.name,
+                detail: Some(ErrorDetail::Unsupported(UnsupportedFeature::for_capabilities(
+                    required,
+                ))),
+            })
         }
     }
-}
 
-impl core::fmt::Display for HubrisI2cError {
-    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        write!(
-            f,
-            "I2C {} operation failed: {:?}",
-            self.operation, self.response_code
-        )
-    }
-}
+    /// Clock out a target that is holding SDA low
+    ///
+    /// Needs a server with [`Capabilities::BUS_RECOVERY`]; against any
+    /// other this fails fast with `OperationNotSupported` and nothing is
+    /// sent.
+    pub fn recover_bus(&self) -> Result<(), HubrisI2cError> {
+        self.require(Capabilities::BUS_RECOVERY, "recover_bus")?;
 
-impl HubrisI2cError {
-    /// Add operation context to error
-    pub fn with_operation(mut self, operation: &'static str) -> Self {
-        self.operation = operation;
-        self
+        self.backend
+            .recover_bus(&self.device)
+            .map_err(|response_code| HubrisI2cError {
+                response_code,
+                operation: "recover_bus",
+                bus: self.name,
+                detail: None,
+            })
     }
 
-    /// Check if error indicates device not present
-    pub fn is_device_not_found(&self) -> bool {
-        matches!(
-            self.response_code,
-            ResponseCode::AddressNackSentEarly
-                | ResponseCode::AddressNackSentLate
-                | ResponseCode::NoDevice
-        )
+    /// Issue an address-only write: START, address + W, STOP
+    ///
+    /// This is the canonical presence check. The server is sent an
+    /// explicit zero-length write lease, so the request can't be optimized
+    /// away and the result is exactly the address ACK/NACK.
+    pub fn write_address_only(&self) -> Result<(), HubrisI2cError> {
+        self.request_write(&self.device, &[])
+            .map_err(|response_code| HubrisI2cError {
+                response_code,
+                operation: "address_only_write",
+                bus: self.name,
+                detail: None,
+            })
     }
 
-    /// Check if error indicates temporary bus condition
-    pub fn is_temporary(&self) -> bool {
-        matches!(
-            self.response_code,
-            ResponseCode::BusLocked | ResponseCode::BusTimeout | ResponseCode::ArbitrationLost
-        )
+    /// Emit a bare STOP to close out a dangling transaction
+    ///
+    /// Lighter than a bus recovery: it gives the bus a clean boundary after
+    /// an interrupted transaction without clocking out a stuck target.
+    /// Needs a server with [`Capabilities::FORCE_STOP`]. `drv-i2c-api` has
+    /// no STOP-only request (the stock server ends every transaction with
+    /// STOP itself and resets the controller when one fails), so against
+    /// it this fails fast with `OperationNotSupported` and nothing is sent.
+    pub fn force_stop(&self) -> Result<(), HubrisI2cError> {
+        self.require(Capabilities::FORCE_STOP, "force_stop")?;
+
+        self.backend
+            .force_stop(&self.device)
+            .map_err(|response_code| HubrisI2cError {
+                response_code,
+                operation: "force_stop",
+                bus: self.name,
+                detail: None,
+            })
     }
 
-    /// Get suggested retry delay for temporary errors
-    pub fn retry_delay(&self) -> Option<core::time::Duration> {
-        match self.response_code {
-            ResponseCode::BusLocked => Some(core::time::Duration::from_millis(10)),
-            ResponseCode::BusTimeout => Some(core::time::Duration::from_millis(100)),
-            ResponseCode::ArbitrationLost => Some(core::time::Duration::from_millis(1)),
-            _ => None,
+    /// Check whether the device acknowledges its address
+    ///
+    /// Uses an address-only write, so no data is clocked out of the device.
+    /// Returns `Ok(false)` on an address NACK and `Err` only for genuine
+    /// bus or server failures, so scans can tell "absent" from "broken".
+    pub fn probe(&self) -> Result<bool, HubrisI2cError> {
+        match self.write_address_only() {
+            Ok(()) => Ok(true),
+            Err(err) => {
+                let err = err.with_operation("probe");
+                if err.is_device_not_found() {
+                    Ok(false)
+                } else {
+                    Err(err)
+                }
+            }
         }
     }
-}
 
-/// Address wrapper for 7-bit addressing
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct SevenBitAddr(pub u8);
+    /// Set the register pointer once for streaming reads of a FIFO register
+    ///
+    /// The returned [`FifoReader`] performs bare reads, saving the pointer
+    /// write on every sample. The pointer lives in the device, so another
+    /// task talking to the same device between samples can move it; hold
+    /// exclusive access to the device for the lifetime of the reader.
+    pub fn fifo_reader(&self, reg: u8) -> Result<FifoReader<'_, B>, HubrisI2cError> {
+        self.request_write(&self.device, &[reg])
+            .map_err(|response_code| HubrisI2cError {
+                response_code,
+                operation: "fifo_set_pointer",
+                bus: self.name,
+                detail: None,
+            })?;
 
-impl SevenBitAddr {
-    /// Create new 7-bit address
-    pub const fn new(addr: u8) -> Self {
-        SevenBitAddr(addr)
+        Ok(FifoReader { wrapper: self })
     }
 
-    /// Get the address value
-    pub fn get(self) -> u8 {
-        self.0
-    }
+    /// Read an 8-bit register and test a single bit
+    ///
+    /// Returns whether bit `bit` (0 = LSB) of `reg` is set, for status
+    /// flags like data-ready, fault or busy.
+    pub fn read_bit(&self, reg: u8, bit: u8) -> Result<bool, HubrisI2cError> {
+        debug_assert!(bit < 8, "bit index out of range for 8-bit register");
 
-    /// Validate 7-bit address range
-    pub fn try_new(addr: u8) -> Result<Self, InvalidAddress> {
-        if addr > 0x7F {
-            Err(InvalidAddress::SevenBitRange(addr))
-        } else if addr < 0x08 || addr > 0x77 {
-            Err(InvalidAddress::Reserved(addr))
-        } else {
-            Ok(SevenBitAddr(addr))
-        }
-    }
-}
+        let value: u8 = self
+            .request_read_reg(&self.device, reg)
+            .map_err(|response_code| HubrisI2cError {
+                response_code,
+                operation: "read_bit",
+                bus: self.name,
+                detail: None,
+            })?;
 
-/// Address wrapper for 10-bit addressing
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct TenBitAddr(pub u16);
+        Ok(value & (1 << bit) != 0)
+    }
 
-impl TenBitAddr {
-    /// Create new 10-bit address
-    pub const fn new(addr: u16) -> Self {
-        TenBitAddr(addr)
+    /// Read an 8-bit register and decode it with `f`
+    ///
+    /// Folds the usual read-then-decode into one call, so I/O errors and
+    /// decoding share a single `?`, e.g.
+    /// `i2c.read_register_map(MODE, Mode::from_bits)?`. For decoders that
+    /// can themselves fail, return a `Result` or `Option` from `f`.
+    pub fn read_register_map<T>(
+        &self,
+        reg: u8,
+        f: impl FnOnce(u8) -> T,
+    ) -> Result<T, HubrisI2cError> {
+        self.request_read_reg(&self.device, reg)
+            .map(f)
+            .map_err(|response_code| HubrisI2cError {
+                response_code,
+                operation: "read_register_map",
+                bus: self.name,
+                detail: None,
+            })
     }
 
-    /// Get the address value
-    pub fn get(self) -> u16 {
-        self.0
+    /// Write `reg` then read into `buffer` as one combined server request
+    ///
+    /// On the wire: START, address+W, `reg`, repeated START, address+R,
+    /// `buffer.len()` bytes, STOP. This is what the trait `write_read`
+    /// uses for any single-byte write, on the guess that the byte is a
+    /// register pointer.
+    pub fn write_read_optimized(&self, reg: u8, buffer: &mut [u8]) -> Result<(), HubrisI2cError> {
+        self.request_read_reg_into(&self.device, reg, buffer)
+            .map(|_| ()) // Discard byte count
+            .map_err(|response_code| HubrisI2cError {
+                response_code,
+                operation: "write_read_reg",
+                bus: self.name,
+                detail: None,
+            })
     }
 
-    /// Validate 10-bit address range
-    pub fn try_new(addr: u16) -> Result<Self, InvalidAddress> {
-        if addr > 0x3FF {
-            Err(InvalidAddress::TenBitRange(addr))
-        } else {
-            Ok(TenBitAddr(addr))
-        }
+    /// Write `bytes` then read into `buffer` as two separate server requests
+    ///
+    /// On the wire: START, address+W, `bytes`, STOP, then START,
+    /// address+R, `buffer.len()` bytes, STOP. The trait `write_read` uses
+    /// this for writes of any length other than one; call it directly for
+    /// a device where a single written byte is data rather than a register
+    /// pointer and must be followed by a STOP.
+    pub fn write_read_split(&self, bytes: &[u8], buffer: &mut [u8]) -> Result<(), HubrisI2cError> {
+        self.request_write(&self.device, bytes)
+            .map_err(|response_code| HubrisI2cError {
+                response_code,
+                operation: "write_read_write_phase",
+                bus: self.name,
+                detail: None,
+            })?;
+
+        self.request_read_into(&self.device, buffer)
+            .map(|_| ()) // Discard byte count
+            .map_err(|response_code| HubrisI2cError {
+                response_code,
+                operation: "write_read_read_phase",
+                bus: self.name,
+                detail: None,
+            })
     }
-}
 
-/// Address validation errors
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum InvalidAddress {
-    SevenBitRange(u8),  // Address > 0x7F
-    TenBitRange(u16),   // Address > 0x3FF
-    Reserved(u8),       // Address in reserved range
-}
+    /// Read samples from `data_reg` until the FIFO reports empty or `out` is full
+    ///
+    /// Before each sample, bit `empty_bit` of `status_reg` is checked with
+    /// [`read_bit`](Self::read_bit); once it is set the drain stops, so a
+    /// sample that arrives mid-drain is still collected. Returns the
+    /// number of samples stored at the front of `out`. If `out` fills
+    /// first, samples may remain in the FIFO for the next call.
+    ///
+    /// A failed status check or read stops the drain; its `failed_index`
+    /// is the number of samples already stored, which have left the FIFO
+    /// and are valid in `out[..failed_index]`. Fails with `BadArg`, before
+    /// any bus access, unless `empty_bit` is below 8.
+    pub fn drain_fifo(
+        &self,
+        status_reg: u8,
+        empty_bit: u8,
+        data_reg: u8,
+        out: &mut [u8],
+    ) -> Result<usize, TransactionError> {
+        if empty_bit >= 8 {
+            return Err(TransactionError {
+                failed_index: 0,
+                source: HubrisI2cError {
+                    response_code: ResponseCode::BadArg,
+                    operation: "drain_fifo_bit",
+                    bus: self.name,
+                    detail: None,
+                },
+            });
+        }
 
-impl core::fmt::Display for InvalidAddress {
-    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        match self {
-            InvalidAddress::SevenBitRange(addr) => {
-                write!(f, "Address 0x{:02X} exceeds 7-bit range (0x00-0x7F)", addr)
-            }
-            InvalidAddress::TenBitRange(addr) => {
-                write!(f, "Address 0x{:03X} exceeds 10-bit range (0x000-0x3FF)", addr)
-            }
-            InvalidAddress::Reserved(addr) => {
-                write!(f, "Address 0x{:02X} is in reserved range", addr)
+        let mut count = 0;
+
+        while count < out.len() {
+            let failed = |source| TransactionError {
+                failed_index: count,
+                source,
+            };
+
+            if self
+                .read_bit(status_reg, empty_bit)
+                .map_err(|err| failed(err.with_operation("drain_fifo_status")))?
+            {
+                break;
             }
-        }
-    }
-}
 
-// Address type conversions
-impl From<SevenBitAddr> for u8 {
-    fn from(addr: SevenBitAddr) -> u8 {
-        addr.0
-    }
-}
+            out[count] = self
+                .request_read_reg(&self.device, data_reg)
+                .map_err(|response_code| {
+                    failed(HubrisI2cError {
+                        response_code,
+                        operation: "drain_fifo",
+                        bus: self.name,
+                        detail: None,
+                    })
+                })?;
+            count += 1;
+        }
 
-impl From<u8> for SevenBitAddr {
-    fn from(addr: u8) -> SevenBitAddr {
-        SevenBitAddr(addr)
+        Ok(count)
     }
-}
 
-impl From<TenBitAddr> for u16 {
-    fn from(addr: TenBitAddr) -> u16 {
-        addr.0
+    /// Device on the same bus and segment at a different address
+    fn device_at(&self, address: u8) -> I2cDevice {
+        I2cDevice::new(
+            self.device.task,
+            self.device.controller,
+            self.device.port,
+            self.device.segment,
+            address,
+        )
     }
-}
 
-impl From<u16> for TenBitAddr {
-    fn from(addr: u16) -> TenBitAddr {
-        TenBitAddr(addr)
+    /// Device for `address`'s 10-bit header `11110XX` (`XX` being address
+    /// bits 9..8), and the low address byte that follows the header
+    fn ten_bit_parts(&self, address: TenBitAddr) -> (I2cDevice, u8) {
+        let header = 0x78 | ((address.0 >> 8) & 0x03) as u8;
+        (self.device_at(header), (address.0 & 0xFF) as u8)
     }
-}
-
-// Embedded-HAL trait implementations
-impl ErrorType for HubrisI2c {
-    type Error = HubrisI2cError;
-}
 
-/// Implementation for 7-bit addressing
-impl embedded_hal::i2c::I2c<SevenBitAddr> for HubrisI2c {
-    fn read(&mut self, _address: SevenBitAddr, buffer: &mut [u8]) -> Result<(), Self::Error> {
-        // Note: We ignore the address parameter since Hubris I2cDevice
-        // already contains the device address. This is a limitation of
-        // bridging between embedded-hal's per-operation addressing and
-        // Hubris's device-centric model.
-
-        self.device
-            .read_into(buffer)
-            .map(|_| ()) // Discard byte count
+    /// Write `bytes` to the general-call address (0x00) on this bus
+    ///
+    /// Every device that implements general call receives the write,
+    /// regardless of this wrapper's own device address.
+    pub fn general_call(&self, bytes: &[u8]) -> Result<(), HubrisI2cError> {
+        self.request_write(&self.device_at(SevenBitAddr::GENERAL_CALL.0), bytes)
             .map_err(|response_code| HubrisI2cError {
                 response_code,
-                operation: "read",
+                operation: "general_call",
+                bus: self.name,
+                detail: None,
             })
     }
 
-    fn write(&mut self, _address: SevenBitAddr, bytes: &[u8]) -> Result<(), Self::Error> {
-        self.device
-            .write(bytes)
-            .map_err(|response_code| HubrisI2cError {
-                response_code,
-                operation: "write",
-            })
+    /// Reset every device that supports the SMBus general-call reset
+    ///
+    /// Writes 0x06 to the general-call address. If no device on the bus
+    /// implements general call the write is NACKed; that is usually fine
+    /// to ignore at bring-up, but the result is returned for callers who
+    /// need to know whether anything acknowledged.
+    pub fn smbus_reset_all(&self) -> Result<(), HubrisI2cError> {
+        self.general_call(&[0x06])
+            .map_err(|err| err.with_operation("smbus_reset_all"))
     }
 
-    fn write_read(
-        &mut self,
-        _address: SevenBitAddr,
-        bytes: &[u8],
-        buffer: &mut [u8],
-    ) -> Result<(), Self::Error> {
-        // Try to optimize for common register read patterns
-        if bytes.len() == 1 {
-            // Single byte write likely indicates register read
-            self.device
-                .read_reg_into(bytes[0], buffer)
-                .map(|_| ()) // Discard byte count
-                .map_err(|response_code| HubrisI2cError {
-                    response_code,
-                    operation: "write_read_reg",
-                })
-        } else {
-            // Multi-byte write - fall back to separate operations
-            self.device
-                .write(bytes)
-                .map_err(|response_code| HubrisI2cError {
-                    response_code,
-                    operation: "write_read_write_phase",
+    /// Write the same `bytes` to each address in `addrs`, in order
+    ///
+    /// For programming a bank of identical parts that lack general-call
+    /// support. Stops at the first failure; the error's `failed_index` is
+    /// the position in `addrs` of the device that failed, and every device
+    /// before it has already been written.
+    pub fn write_gang(&self, addrs: &[SevenBitAddr], bytes: &[u8]) -> Result<(), TransactionError> {
+        for (failed_index, address) in addrs.iter().enumerate() {
+            self.request_write(&self.device_at(address.0), bytes)
+                .map_err(|response_code| TransactionError {
+                    failed_index,
+                    source: HubrisI2cError {
+                        response_code,
+                        operation: "write_gang",
+                        bus: self.name,
+                        detail: None,
+                    },
                 })?;
+        }
 
-            self.device
-                .read_into(buffer)
-                .map(|_| ()) // Discard byte count
-                .map_err(|response_code| HubrisI2cError {
+        Ok(())
+    }
+
+    /// Read the SMBus Alert Response Address once
+    ///
+    /// Returns the address of the device that won ALERT# arbitration
+    /// (reported in the upper seven bits of the response byte), or `None`
+    /// if nothing answered because no device is alerting.
+    pub fn smbus_alert_response(&self) -> Result<Option<SevenBitAddr>, HubrisI2cError> {
+        let mut response = [0u8; 1];
+        let ara = self.device_at(SevenBitAddr::ALERT_RESPONSE.0);
+        match self.request_read_into(&ara, &mut response) {
+            Ok(_) => Ok(Some(SevenBitAddr(response[0] >> 1))),
+            Err(response_code) => {
+                let err = HubrisI2cError {
                     response_code,
-                    operation: "write_read_read_phase",
-                })
+                    operation: "smbus_alert_response",
+                    bus: self.name,
+                    detail: None,
+                };
+                if err.is_device_not_found() {
+                    Ok(None)
+                } else {
+                    Err(err)
+                }
+            }
         }
     }
 
-    fn transaction(
-        &mut self,
-        address: SevenBitAddr,
-        operations: &mut [Operation<'_>],
-    ) -> Result<(), Self::Error> {
-        // Process operations sequentially
-        // Note: This doesn't provide true I2C transaction semantics
-        // (repeated START conditions) but is the best we can do with
-        // the current Hubris API
+    /// Collect every device asserting ALERT#
+    ///
+    /// Reads the Alert Response Address until it NACKs, so one interrupt
+    /// yields the full list of devices needing service. A device should
+    /// release ALERT# once it has responded; one that answers a second time
+    /// is stuck, and since it would keep winning arbitration the walk stops
+    /// there rather than spinning. A bus error also ends the walk, keeping
+    /// whatever was collected so far.
+    pub fn collect_alerts(&self) -> heapless::Vec<SevenBitAddr, 16> {
+        let mut alerters = heapless::Vec::new();
 
-        for operation in operations.iter_mut() {
-            match operation {
-                Operation::Read(buffer) => {
-                    self.read(address, buffer).map_err(|mut err| {
-                        err.operation = "transaction_read";
-                        err
-                    })?;
-                }
-                Operation::Write(data) => {
-                    self.write(address, data).map_err(|mut err| {
-                        err.operation = "transaction_write";
-                        err
-                    })?;
-                }
+        while let Ok(Some(address)) = self.smbus_alert_response() {
+            if alerters.contains(&address) || alerters.push(address).is_err() {
+                break;
             }
         }
 
+        alerters
+    }
+
+    /// 7-bit address this wrapper was configured with
+    pub fn address(&self) -> SevenBitAddr {
+        SevenBitAddr(self.device.address)
+    }
+
+    /// Controller this wrapper was configured with
+    pub fn controller(&self) -> Controller {
+        self.device.controller
+    }
+
+    /// Port this wrapper was configured with
+    pub fn port(&self) -> PortIndex {
+        self.device.port
+    }
+
+    /// Multiplexer and segment this wrapper was configured with, if any
+    pub fn segment(&self) -> Option<(Mux, Segment)> {
+        self.device.segment
+    }
+
+    /// Read several independent 8-bit registers, isolating failures
+    ///
+    /// Each register is read on its own and its outcome stored in the
+    /// matching slot of `out`, so one NACKed register doesn't hide the
+    /// others. Fails up front with `BadArg` if `out` and `regs` differ in
+    /// length.
+    pub fn read_registers_isolated(
+        &self,
+        regs: &[u8],
+        out: &mut [Result<u8, HubrisI2cError>],
+    ) -> Result<(), HubrisI2cError> {
+        if regs.len() != out.len() {
+            return Err(HubrisI2cError {
+                response_code: ResponseCode::BadArg,
+                operation: "read_registers_isolated_length",
+                bus: self.name,
+                detail: None,
+            });
+        }
+
+        for (&reg, slot) in regs.iter().zip(out.iter_mut()) {
+            *slot = self
+                .request_read_reg(&self.device, reg)
+                .map_err(|response_code| HubrisI2cError {
+                    response_code,
+                    operation: "read_registers_isolated",
+                    bus: self.name,
+                    detail: None,
+                });
+        }
+
         Ok(())
     }
-}
 
-/// Implementation for 10-bit addressing
-impl embedded_hal::i2c::I2c<TenBitAddr> for HubrisI2c {
-    fn read(&mut self, address: TenBitAddr, buffer: &mut [u8]) -> Result<(), Self::Error> {
-        // 10-bit addressing requires special handling
-        // The current Hubris API doesn't directly support 10-bit addressing,
-        // so we need to handle the 10-bit protocol manually
+    /// Read scattered 8-bit registers, such as 0x00, 0x05 and 0x10, in order
+    ///
+    /// Each register in `regs` is read with its own combined write-read
+    /// into the matching slot of `out`. Unlike
+    /// [`read_registers_isolated`](Self::read_registers_isolated) this
+    /// stops at the first failure, for when the set is only useful whole.
+    /// Fails up front with `BadArg` if `out` and `regs` differ in length.
+    pub fn read_register_set(&self, regs: &[u8], out: &mut [u8]) -> Result<(), HubrisI2cError> {
+        if regs.len() != out.len() {
+            return Err(HubrisI2cError {
+                response_code: ResponseCode::BadArg,
+                operation: "read_register_set_length",
+                bus: self.name,
+                detail: None,
+            });
+        }
 
-        // 10-bit read sequence:
-        // 1. Send 11110XX0 (where XX are upper 2 bits of address)
-        // 2. Send lower 8 bits of address
-        // 3. Send repeated START
-        // 4. Send 11110XX1 (read bit set)
-        // 5. Read data
+        for (&reg, slot) in regs.iter().zip(out.iter_mut()) {
+            *slot = self
+                .request_read_reg(&self.device, reg)
+                .map_err(|response_code| HubrisI2cError {
+                    response_code,
+                    operation: "read_register_set",
+                    bus: self.name,
+                    detail: None,
+                })?;
+        }
 
-        let addr_high = 0xF0 | ((address.0 >> 7) & 0x06) as u8; // 11110XX0
-        let addr_low = (address.0 & 0xFF) as u8;
+        Ok(())
+    }
 
-        // Use write_read to perform the 10-bit addressing sequence
-        let write_data = [addr_high, addr_low];
+    /// Write an 8-bit register and read it back to confirm the write took
+    ///
+    /// For critical configuration. A read-back that differs from `value`
+    /// fails with [`VerifyError::Mismatch`], which carries the expected
+    /// and actual values and converts to a `BadResponse`
+    /// [`HubrisI2cError`] for callers that just propagate with `?`.
+    ///
+    /// Only suitable for registers that read back what was written. For
+    /// registers with write-only, self-clearing or status bits, use
+    /// [`write_register_verified_masked`](Self::write_register_verified_masked)
+    /// to compare just the bits that persist.
+    pub fn write_register_verified(&self, reg: u8, value: u8) -> Result<(), VerifyError> {
+        self.write_register_verified_masked(reg, value, 0xFF)
+    }
 
-        // This is a limitation: we're approximating 10-bit addressing
-        // A full implementation would need server support for 10-bit protocol
-        self.device
-            .write(&write_data)
-            .map_err(|response_code| HubrisI2cError {
-                response_code,
-                operation: "10bit_address_setup",
+    /// Write an 8-bit register and confirm the bits in `mask` read back
+    ///
+    /// All of `value` is written; only the bits set in `mask` are compared
+    /// on read-back. See [`write_register_verified`](Self::write_register_verified).
+    pub fn write_register_verified_masked(
+        &self,
+        reg: u8,
+        value: u8,
+        mask: u8,
+    ) -> Result<(), VerifyError> {
+        self.request_write(&self.device, &[reg, value])
+            .map_err(|response_code| {
+                VerifyError::Bus(HubrisI2cError {
+                    response_code,
+                    operation: "write_register_verified_write",
+                    bus: self.name,
+                    detail: None,
+                })
             })?;
 
-        self.device
-            .read_into(buffer)
-            .map(|_| ())
-            .map_err(|response_code| HubrisI2cError {
+        let actual: u8 = self.request_read_reg(&self.device, reg).map_err(|response_code| {
+            VerifyError::Bus(HubrisI2cError {
                 response_code,
-                operation: "10bit_read",
+                operation: "write_register_verified_read",
+                bus: self.name,
+                detail: None,
             })
-    }
+        })?;
 
-    fn write(&mut self, address: TenBitAddr, bytes: &[u8]) -> Result<(), Self::Error> {
-        // 10-bit write sequence:
-        // 1. Send 11110XX0 (where XX are upper 2 bits of address)
-        // 2. Send lower 8 bits of address
-        // 3. Send data bytes
+        if actual & mask != value & mask {
+            return Err(VerifyError::Mismatch {
+                reg,
+                expected: value,
+                actual,
+                mask,
+                bus: self.name,
+            });
+        }
 
-        let addr_high = 0xF0 | ((address.0 >> 7) & 0x06) as u8;
-        let addr_low = (address.0 & 0xFF) as u8;
+        Ok(())
+    }
 
-        // Prepare combined write data: address + data
-        let mut write_data = heapless::Vec::<u8, 258>::new(); // Max: 2 addr + 256 data
-        write_data.push(addr_high).map_err(|_| HubrisI2cError {
-            response_code: ResponseCode::BadResponse,
-            operation: "10bit_write_buffer_overflow",
-        })?;
-        write_data.push(addr_low).map_err(|_| HubrisI2cError {
-            response_code: ResponseCode::BadResponse,
-            operation: "10bit_write_buffer_overflow",
-        })?;
+    /// Read each register in `regs` into a map of register to value
+    ///
+    /// For configuration audits: compare the result against a golden map
+    /// with [`diff_registers`]. Listing a register twice reads it twice
+    /// and keeps the later value. Fails with `TooMuchData` if `regs` holds
+    /// more distinct registers than the map can store; `N` must be a
+    /// power of two, as for any `FnvIndexMap`.
+    pub fn snapshot_registers<const N: usize>(
+        &self,
+        regs: &[u8],
+    ) -> Result<heapless::FnvIndexMap<u8, u8, N>, HubrisI2cError> {
+        let mut snapshot = heapless::FnvIndexMap::new();
 
-        for &byte in bytes {
-            write_data.push(byte).map_err(|_| HubrisI2cError {
-                response_code: ResponseCode::BadResponse,
-                operation: "10bit_write_buffer_overflow",
+        for &reg in regs {
+            let value = self
+                .request_read_reg(&self.device, reg)
+                .map_err(|response_code| HubrisI2cError {
+                    response_code,
+                    operation: "snapshot_registers",
+                    bus: self.name,
+                    detail: None,
+                })?;
+
+            snapshot.insert(reg, value).map_err(|_| HubrisI2cError {
+                response_code: ResponseCode::TooMuchData,
+                operation: "snapshot_registers",
+                bus: self.name,
+                detail: None,
             })?;
         }
 
-        self.device
-            .write(&write_data)
-            .map_err(|response_code| HubrisI2cError {
+        Ok(snapshot)
+    }
+
+    /// Write `bytes`, treating a NACK as an expected outcome
+    ///
+    /// Returns `Ok(true)` if the write was acknowledged, `Ok(false)` on a
+    /// clean address or data NACK, and `Err` only for genuine bus or server
+    /// failures. Suited to optional devices and broadcast writes where
+    /// nobody answering is normal.
+    pub fn write_tolerant(&self, bytes: &[u8]) -> Result<bool, HubrisI2cError> {
+        match self.request_write(&self.device, bytes) {
+            Ok(()) => Ok(true),
+            Err(ResponseCode::AddressNackSentEarly)
+            | Err(ResponseCode::AddressNackSentLate)
+            | Err(ResponseCode::DataNackSent)
+            | Err(ResponseCode::NoDevice) => Ok(false),
+            Err(response_code) => Err(HubrisI2cError {
                 response_code,
-                operation: "10bit_write",
-            })
+                operation: "write_tolerant",
+                bus: self.name,
+                detail: None,
+            }),
+        }
     }
 
-    fn write_read(
-        &mut self,
-        address: TenBitAddr,
-        bytes: &[u8],
-        buffer: &mut [u8],
-    ) -> Result<(), Self::Error> {
-        // For 10-bit devices, we need to do separate write and read
-        // since the addressing is more complex
-        self.write(address, bytes)?;
-        self.read(address, buffer)
+    /// Borrow a view that can only read from the device
+    ///
+    /// Register reads are still available since their write phase only
+    /// sets the register pointer. Hand this to code that must not be able
+    /// to modify device state.
+    pub fn read_only(&self) -> ReadOnlyI2c<'_, B> {
+        ReadOnlyI2c { wrapper: self }
     }
 
-    fn transaction(
+    /// Borrow a view that can only write to the device
+    pub fn write_only(&self) -> WriteOnlyI2c<'_, B> {
+        WriteOnlyI2c { wrapper: self }
+    }
+
+    /// Verify the part's ID register, then apply its configuration
+    ///
+    /// Reads `whoami_reg` and, only if it equals `expected`, writes each
+    /// `(register, value)` pair in `config` in order. Returns `Ok(false)`
+    /// without writing anything if the device is absent (address NACK) or
+    /// reports a different ID, so a wrong or missing part is never
+    /// misconfigured.
+    ///
+    /// The server serializes individual requests but not this sequence;
+    /// taking `&mut self` keeps this handle from interleaving its own
+    /// operations, but other tasks sharing the device must coordinate.
+    pub fn init_if_present(
         &mut self,
-        address: TenBitAddr,
-        operations: &mut [Operation<'_>],
-    ) -> Result<(), Self::Error> {
-        // Similar to 7-bit but with 10-bit addressing
-        for operation in operations.iter_mut() {
-            match operation {
-                Operation::Read(buffer) => {
-                    self.read(address, buffer)?;
-                }
-                Operation::Write(data) => {
-                    self.write(address, data)?;
+        whoami_reg: u8,
+        expected: u8,
+        config: &[(u8, u8)],
+    ) -> Result<bool, HubrisI2cError> {
+        let id: u8 = match self.request_read_reg(&self.device, whoami_reg) {
+            Ok(id) => id,
+            Err(response_code) => {
+                let err = HubrisI2cError {
+                    response_code,
+                    operation: "init_whoami",
+                    bus: self.name,
+                    detail: None,
+                };
+                if err.is_device_not_found() {
+                    return Ok(false);
                 }
+                return Err(err);
             }
+        };
+
+        if id != expected {
+            return Ok(false);
         }
-        Ok(())
-    }
-}
 
-/// Optimized wrapper for register-heavy devices
-pub struct RegisterOptimizedI2c {
-    wrapper: HubrisI2c,
-}
+        for &(reg, value) in config {
+            self.request_write(&self.device, &[reg, value])
+                .map_err(|response_code| HubrisI2cError {
+                    response_code,
+                    operation: "init_config_write",
+                    bus: self.name,
+                    detail: None,
+                })?;
+        }
 
-impl RegisterOptimizedI2c {
-    /// Create new register-optimized wrapper
-    pub fn new(wrapper: HubrisI2c) -> Self {
-        Self { wrapper }
+        Ok(true)
     }
 
-    /// Direct register access using Hubris optimized calls
-    pub fn read_register<T>(&self, reg: u8) -> Result<T, HubrisI2cError>
-    where
-        T: zerocopy::FromBytes + zerocopy::IntoBytes,
-    {
-        self.wrapper.device.read_reg(reg).map_err(|response_code| {
-            HubrisI2cError {
-                response_code,
-                operation: "optimized_register_read",
-            }
-        })
+    /// Re-point this handle at a restarted I2C server task
+    ///
+    /// After the server faults and restarts, its old `TaskId` generation is
+    /// stale and every operation fails. This rebuilds the underlying device
+    /// with `new_server`, preserving controller, port, segment, address and
+    /// wrapper configuration.
+    pub fn reattach(&mut self, new_server: TaskId) {
+        self.device = I2cDevice::new(
+            new_server,
+            self.device.controller,
+            self.device.port,
+            self.device.segment,
+            self.device.address,
+        );
     }
 
-    /// Block read using Hubris SMBus support
-    pub fn read_block(&self, reg: u8, buffer: &mut [u8]) -> Result<usize, HubrisI2cError> {
-        self.wrapper.device.read_block(reg, buffer).map_err(
-            |response_code| HubrisI2cError {
+    /// SMBus Read Byte: send `command`, read exactly one data byte
+    ///
+    /// On the wire this is the same as an 8-bit register read, but the
+    /// framing is fixed to the SMBus definition so a PEC wrapper knows the
+    /// covered bytes are address, command, address and one data byte.
+    pub fn smbus_read_byte(&self, command: u8) -> Result<u8, HubrisI2cError> {
+        let mut byte = [0u8; 1];
+        self.request_read_reg_into(&self.device, command, &mut byte)
+            .map_err(|response_code| HubrisI2cError {
                 response_code,
-                operation: "optimized_block_read",
-            },
-        )
-    }
-}
-
-impl ErrorType for RegisterOptimizedI2c {
-    type Error = HubrisI2cError;
-}
+                operation: "smbus_read_byte",
+                bus: self.name,
+                detail: None,
+            })?;
 
-impl embedded_hal::i2c::I2c<SevenBitAddr> for RegisterOptimizedI2c {
-    fn read(&mut self, address: SevenBitAddr, buffer: &mut [u8]) -> Result<(), Self::Error> {
-        self.wrapper.read(address, buffer)
+        Ok(byte[0])
     }
 
-    fn write(&mut self, address: SevenBitAddr, bytes: &[u8]) -> Result<(), Self::Error> {
-        self.wrapper.write(address, bytes)
+    /// SMBus Write Byte: send `command` followed by one data byte
+    pub fn smbus_write_byte(&self, command: u8, value: u8) -> Result<(), HubrisI2cError> {
+        self.request_write(&self.device, &[command, value])
+            .map_err(|response_code| HubrisI2cError {
+                response_code,
+                operation: "smbus_write_byte",
+                bus: self.name,
+                detail: None,
+            })
     }
 
-    fn write_read(
-        &mut self,
-        address: SevenBitAddr,
-        bytes: &[u8],
-        buffer: &mut [u8],
-    ) -> Result<(), Self::Error> {
-        // Always use optimized register read for single-byte writes
-        if bytes.len() == 1 {
-            self.wrapper
-                .device
-                .read_reg_into(bytes[0], buffer)
-                .map(|_| ())
-                .map_err(|response_code| HubrisI2cError {
-                    response_code,
-                    operation: "optimized_write_read",
-                })
-        } else {
-            self.wrapper.write_read(address, bytes, buffer)
-        }
+    /// SMBus Read Word: send `command`, read a little-endian 16-bit word
+    ///
+    /// Unlike [`read_register`](Self::read_register), the byte order is
+    /// pinned to SMBus (little-endian) regardless of device configuration.
+    pub fn smbus_read_word(&self, command: u8) -> Result<u16, HubrisI2cError> {
+        let mut word = [0u8; 2];
+        self.request_read_reg_into(&self.device, command, &mut word)
+            .map_err(|response_code| HubrisI2cError {
+                response_code,
+                operation: "smbus_read_word",
+                bus: self.name,
+                detail: None,
+            })?;
+
+        Ok(u16::from_le_bytes(word))
     }
 
-    fn transaction(
+    /// SMBus Write Word: send `command` followed by `value`, low byte first
+    pub fn smbus_write_word(&self, command: u8, value: u16) -> Result<(), HubrisI2cError> {
+        let [low, high] = value.to_le_bytes();
+        self.request_write(&self.device, &[command, low, high])
+            .map_err(|response_code| HubrisI2cError {
+                response_code,
+                operation: "smbus_write_word",
+                bus: self.name,
+                detail: None,
+            })
+    }
+
+    /// Bound how long each operation may wait for the server to reply
+    ///
+    /// With a timeout in place every request is issued through
+    /// [`I2cBackend::with_timeout`], so a wedged server yields `BusTimeout`
+    /// rather than blocking the calling task forever. A Hubris `send` can't
+    /// be abandoned from the client side, so the bound has to be enforced
+    /// by the server: setting `Some(_)` fails with `OperationNotSupported`
+    /// unless it advertises [`Capabilities::TIMEOUT`], so a caller never
+    /// believes it is protected when it isn't. `None` always succeeds.
+    #[cfg(feature = "watchdog")]
+    pub fn set_op_timeout(
         &mut self,
-        address: SevenBitAddr,
-        operations: &mut [Operation<'_>],
-    ) -> Result<(), Self::Error> {
-        // Try to optimize common transaction patterns
-        if operations.len() == 2 {
-            if let (Operation::Write(write_data), Operation::Read(read_buffer)) =
-                (&operations[0], &mut operations[1])
-            {
-                if write_data.len() == 1 {
-                    // Optimize write(1 byte) + read(n bytes) as register read
-                    return self
-                        .wrapper
-                        .device
-                        .read_reg_into(write_data[0], read_buffer)
-                        .map(|_| ())
-                        .map_err(|response_code| HubrisI2cError {
-                            response_code,
-                            operation: "optimized_transaction",
-                        });
-                }
-            }
+        timeout: Option<core::time::Duration>,
+    ) -> Result<(), HubrisI2cError> {
+        if timeout.is_some() {
+            self.require(Capabilities::TIMEOUT, "set_op_timeout")?;
         }
-
-        // Fall back to default implementation
-        self.wrapper.transaction(address, operations)
+        self.op_timeout = timeout;
+        Ok(())
     }
-}
 
-/// Wrapper that automatically retries on temporary errors
-pub struct RetryingI2c<I2C> {
-    inner: I2C,
-    max_retries: u8,
-}
+    /// Timeout configured with [`set_op_timeout`](Self::set_op_timeout)
+    #[cfg(feature = "watchdog")]
+    pub fn op_timeout(&self) -> Option<core::time::Duration> {
+        self.op_timeout
+    }
 
-impl<I2C> RetryingI2c<I2C> {
-    /// Create new retrying wrapper
-    ///
-    /// # Arguments
+    /// Heuristically check whether the register pointer auto-increments
     ///
-    /// * `inner` - The underlying I2C implementation
-    /// * `max_retries` - Maximum number of retry attempts
-    pub fn new(inner: I2C, max_retries: u8) -> Self {
-        Self { inner, max_retries }
+    /// Reads two bytes starting at `reg`: a device that doesn't
+    /// auto-increment returns the same register twice. Returns `true` if
+    /// the bytes differ. This can only prove auto-increment, never rule it
+    /// out: adjacent registers that happen to hold equal values (e.g. both
+    /// zero after reset) read as "not incrementing". Pick a `reg` whose
+    /// neighbor is known to differ, such as an ID register followed by a
+    /// revision register, and prefer the datasheet where it's explicit.
+    pub fn probe_auto_increment(&self, reg: u8) -> Result<bool, HubrisI2cError> {
+        let mut bytes = [0u8; 2];
+        self.request_read_reg_into(&self.device, reg, &mut bytes)
+            .map_err(|response_code| HubrisI2cError {
+                response_code,
+                operation: "probe_auto_increment",
+                bus: self.name,
+                detail: None,
+            })?;
+
+        Ok(bytes[0] != bytes[1])
     }
 
-    /// Execute operation with automatic retry on temporary errors
-    fn retry_operation<F, R>(&mut self, mut operation: F) -> Result<R, I2C::Error>
-    where
-        F: FnMut(&mut I2C) -> Result<R, I2C::Error>,
-        I2C::Error: embedded_hal::i2c::Error,
-        I2C: embedded_hal::i2c::I2c<SevenBitAddr>,
-    {
-        let mut last_error = None;
+    /// Run a transaction, reporting which operation failed
+    ///
+    /// Like the embedded-hal `transaction`, operations are issued as
+    /// separate server requests, so a failure part-way leaves the device
+    /// with the earlier operations applied. The returned
+    /// [`TransactionError`] names the failing operation's index so the
+    /// caller can attempt device-specific recovery. Empty operations are
+    /// skipped, as in `transaction`.
+    pub fn transaction_detailed(
+        &mut self,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), TransactionError> {
+        let address = self.address();
 
-        for attempt in 0..=self.max_retries {
-            match operation(&mut self.inner) {
-                Ok(result) => return Ok(result),
-                Err(error) => {
-                    // Check if error is retryable
-                    match error.kind() {
-                        ErrorKind::ArbitrationLoss | ErrorKind::Other => {
-                            if attempt < self.max_retries {
-                                // Wait before retry (exponential backoff)
-                                userlib::sys::sleep_for(userlib::time::Duration::from_millis(
-                                    10 * (attempt + 1) as u64,
-                                ));
-                                last_error = Some(error);
-                                continue;
-                            }
-                        }
-                        _ => {
-                            // Non-retryable error
-                            return Err(error);
-                        }
-                    }
+        // Nothing has been issued if validation fails, so report index 0
+        self.check_transaction(operations).map_err(|source| TransactionError {
+            failed_index: 0,
+            source,
+        })?;
 
-                    last_error = Some(error);
-                }
-            }
+        for (index, operation) in operations
+            .iter_mut()
+            .enumerate()
+            .filter(|(_, operation)| !is_empty_operation(operation))
+        {
+            let result = match operation {
+                Operation::Read(buffer) => self.read(address, buffer),
+                Operation::Write(data) => self.write(address, data),
+            };
+
+            result.map_err(|source| TransactionError {
+                failed_index: index,
+                source,
+            })?;
         }
 
-        Err(last_error.unwrap())
+        Ok(())
     }
-}
 
-impl<I2C> ErrorType for RetryingI2c<I2C>
-where
-    I2C: ErrorType,
-{
-    type Error = I2C::Error;
-}
+    /// Single-shot measurement: trigger a conversion, wait, read the result
+    ///
+    /// Writes `trigger`, waits `conversion_time` on `delay` (this varies
+    /// with the configured resolution, so it's up to the caller), then
+    /// reads `result_reg` into `buffer`.
+    pub fn trigger_and_read<D: DelayProvider>(
+        &self,
+        trigger: &[u8],
+        conversion_time: core::time::Duration,
+        result_reg: u8,
+        buffer: &mut [u8],
+        delay: &mut D,
+    ) -> Result<(), HubrisI2cError> {
+        self.request_write(&self.device, trigger)
+            .map_err(|response_code| HubrisI2cError {
+                response_code,
+                operation: "conversion_trigger",
+                bus: self.name,
+                detail: None,
+            })?;
 
-impl<I2C> embedded_hal::i2c::I2c<SevenBitAddr> for RetryingI2c<I2C>
-where
-    I2C: embedded_hal::i2c::I2c<SevenBitAddr>,
-    I2C::Error: embedded_hal::i2c::Error,
-{
-    fn read(&mut self, address: SevenBitAddr, buffer: &mut [u8]) -> Result<(), Self::Error> {
-        self.retry_operation(|i2c| i2c.read(address, buffer))
+        delay.delay(conversion_time);
+
+        self.request_read_reg_into(&self.device, result_reg, buffer)
+            .map(|_| ())
+            .map_err(|response_code| HubrisI2cError {
+                response_code,
+                operation: "conversion_result_read",
+                bus: self.name,
+                detail: None,
+            })
     }
 
-    fn write(&mut self, address: SevenBitAddr, bytes: &[u8]) -> Result<(), Self::Error> {
-        self.retry_operation(|i2c| i2c.write(address, bytes))
+    /// Retarget this handle at a different multiplexer segment
+    pub fn set_segment(&mut self, segment: Option<(Mux, Segment)>) {
+        self.device.segment = segment;
     }
 
-    fn write_read(
+    /// Run `f` with this handle temporarily retargeted at `segment`
+    ///
+    /// The original segment is restored afterwards, whatever `f` returns.
+    pub fn with_segment<R>(
         &mut self,
-        address: SevenBitAddr,
-        bytes: &[u8],
-        buffer: &mut [u8],
-    ) -> Result<(), Self::Error> {
-        self.retry_operation(|i2c| i2c.write_read(address, bytes, buffer))
+        segment: Option<(Mux, Segment)>,
+        f: impl FnOnce(&mut Self) -> R,
+    ) -> R {
+        let original = self.device.segment;
+        self.device.segment = segment;
+        let result = f(self);
+        self.device.segment = original;
+        result
     }
 
-    fn transaction(
+    /// Run `f` once per segment in `segments`, retargeting this handle each time
+    ///
+    /// For a bank of identical devices isolated behind muxes: each call
+    /// runs as [`with_segment`](Self::with_segment) would, so the original
+    /// segment is restored afterwards. With `stop_on_error` the walk ends
+    /// at the first failure; otherwise every segment is still visited.
+    /// Either way the error reports the first failure, with `failed_index`
+    /// the position in `segments` of the segment that failed.
+    pub fn for_each_segment(
         &mut self,
-        address: SevenBitAddr,
-        operations: &mut [Operation<'_>],
-    ) -> Result<(), Self::Error> {
-        self.retry_operation(|i2c| i2c.transaction(address, operations))
-    }
-}
+        segments: &[(Mux, Segment)],
+        stop_on_error: bool,
+        mut f: impl FnMut(&mut Self) -> Result<(), HubrisI2cError>,
+    ) -> Result<(), TransactionError> {
+        let mut first_failure = None;
 
-#[cfg(feature = "testing")]
-pub mod mock {
-    //! Mock I2C implementation for testing embedded-hal device drivers
+        for (index, &segment) in segments.iter().enumerate() {
+            if let Err(source) = self.with_segment(Some(segment), &mut f) {
+                first_failure.get_or_insert(TransactionError {
+                    failed_index: index,
+                    source,
+                });
+                if stop_on_error {
+                    break;
+                }
+            }
+        }
 
-    use super::*;
-    use heapless::Vec;
+        match first_failure {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
 
-    /// Mock I2C implementation for testing
-    pub struct MockI2c {
-        expected_operations: Vec<MockOperation, 32>,
-        operation_index: usize,
+    /// Freeze this handle's routing for safety-critical devices
+    ///
+    /// The returned [`SealedI2c`] always talks to the original controller,
+    /// port, segment and address.
+    pub fn sealed(self) -> SealedI2c<B> {
+        SealedI2c { wrapper: self }
     }
 
-    #[derive(Debug, Clone)]
-    pub enum MockOperation {
-        Read {
-            address: SevenBitAddr,
-            response: Vec<u8, 256>,
-        },
-        Write {
-            address: SevenBitAddr,
-            expected_data: Vec<u8, 256>,
-        },
-        WriteRead {
-            address: SevenBitAddr,
-            expected_write: Vec<u8, 256>,
-            read_response: Vec<u8, 256>,
-        },
+    /// SMBus Block Read with strict length-byte semantics
+    ///
+    /// Sends `command`, then treats the first received byte as the block
+    /// length N and the following N bytes as data. Per the SMBus spec N
+    /// must be 1..=32; anything else is rejected as `BadResponse`. Data is
+    /// copied into `buffer` (truncated if `buffer` is shorter than N) and
+    /// the number of bytes stored is returned.
+    pub fn smbus_read_block(&self, command: u8, buffer: &mut [u8]) -> Result<usize, HubrisI2cError> {
+        self.smbus_read_block_max(command, buffer, 32)
     }
 
-    impl MockI2c {
-        /// Create new mock I2C
-        pub fn new() -> Self {
-            Self {
-                expected_operations: Vec::new(),
-                operation_index: 0,
-            }
+    /// [`smbus_read_block`](Self::smbus_read_block) with a custom length limit
+    ///
+    /// For PMBus extended blocks, which allow up to 255 bytes. The count
+    /// byte and `max_len` data bytes are read in one transfer, checked
+    /// against [`max_transfer_len`](Self::max_transfer_len) up front. As
+    /// with `smbus_read_block`, a valid block longer than `buffer` is
+    /// truncated to it rather than failing.
+    pub fn smbus_read_block_max(
+        &self,
+        command: u8,
+        buffer: &mut [u8],
+        max_len: u8,
+    ) -> Result<usize, HubrisI2cError> {
+        let wire_len = 1 + usize::from(max_len);
+        if wire_len > HubrisI2c::max_transfer_len() {
+            return Err(HubrisI2cError {
+                response_code: ResponseCode::TooMuchData,
+                operation: "smbus_block_read",
+                bus: self.name,
+                detail: Some(ErrorDetail::TooLong {
+                    limit: HubrisI2c::max_transfer_len(),
+                    attempted: wire_len,
+                }),
+            });
         }
 
-        /// Expect a write operation
-        pub fn expect_write(&mut self, address: SevenBitAddr, data: &[u8]) {
-            let mut expected_data = Vec::new();
-            expected_data.extend_from_slice(data).unwrap();
+        let mut raw = [0u8; MAX_TRANSFER_LEN];
+        let raw = &mut raw[..wire_len];
+        self.request_read_reg_into(&self.device, command, raw)
+            .map_err(|response_code| HubrisI2cError {
+                response_code,
+                operation: "smbus_block_read",
+                bus: self.name,
+                detail: None,
+            })?;
 
-            self.expected_operations
-                .push(MockOperation::Write {
-                    address,
-                    expected_data,
-                })
-                .unwrap();
+        let len = raw[0];
+        if len == 0 || len > max_len {
+            return Err(HubrisI2cError {
+                response_code: ResponseCode::BadResponse,
+                operation: "smbus_block_length",
+                bus: self.name,
+                detail: None,
+            });
         }
 
-        /// Expect a read operation
-        pub fn expect_read(&mut self, address: SevenBitAddr, response: &[u8]) {
-            let mut response_data = Vec::new();
-            response_data.extend_from_slice(response).unwrap();
+        let count = (len as usize).min(buffer.len());
+        buffer[..count].copy_from_slice(&raw[1..1 + count]);
+        Ok(count)
+    }
 
-            self.expected_operations
-                .push(MockOperation::Read {
-                    address,
-                    response: response_data,
-                })
-                .unwrap();
+    /// [`validate_transaction`](Self::validate_transaction) plus this
+    /// handle's operation count limit
+    fn check_transaction(&self, operations: &[Operation<'_>]) -> Result<(), HubrisI2cError> {
+        if operations.len() > self.max_transaction_ops {
+            return Err(HubrisI2cError {
+                response_code: ResponseCode::BadArg,
+                operation: "transaction_op_count",
+                bus: self.name,
+                detail: None,
+            });
         }
 
-        /// Expect a write-read operation
-        pub fn expect_write_read(
-            &mut self,
-            address: SevenBitAddr,
-            write_data: &[u8],
-            read_response: &[u8],
-        ) {
-            let mut expected_write = Vec::new();
-            expected_write.extend_from_slice(write_data).unwrap();
+        HubrisI2c::validate_transaction(operations).map_err(|mut err| {
+            err.bus = self.name;
+            err
+        })
+    }
 
-            let mut response = Vec::new();
-            response.extend_from_slice(read_response).unwrap();
+    /// Poll an 8-bit register until `predicate` holds for its value
+    ///
+    /// Reads `reg` every `interval` (waiting on `delay`) and returns the
+    /// first value that satisfies `predicate`. Elapsed time is accounted as
+    /// the sum of the delays, so bus time isn't counted; after `timeout`
+    /// the poll gives up with a `BusTimeout` error carrying
+    /// [`ErrorDetail::Timeout`], which sets it apart from a read that
+    /// failed with `BusTimeout` itself. A zero `interval` polls exactly
+    /// once.
+    pub fn poll_until<D: DelayProvider>(
+        &self,
+        reg: u8,
+        predicate: impl Fn(u8) -> bool,
+        interval: core::time::Duration,
+        timeout: core::time::Duration,
+        delay: &mut D,
+    ) -> Result<u8, HubrisI2cError> {
+        let mut waited = core::time::Duration::ZERO;
 
-            self.expected_operations
-                .push(MockOperation::WriteRead {
-                    address,
-                    expected_write,
-                    read_response: response,
-                })
-                .unwrap();
-        }
+        loop {
+            let value: u8 = self
+                .request_read_reg(&self.device, reg)
+                .map_err(|response_code| HubrisI2cError {
+                    response_code,
+                    operation: "poll_until",
+                    bus: self.name,
+                    detail: None,
+                })?;
 
-        /// Verify all expected operations were performed
-        pub fn verify_complete(&self) {
-            assert_eq!(
-                self.operation_index,
-                self.expected_operations.len(),
-                "Not all expected I2C operations were performed"
-            );
+            if predicate(value) {
+                return Ok(value);
+            }
+
+            if waited >= timeout || interval.is_zero() {
+                return Err(HubrisI2cError {
+                    response_code: ResponseCode::BusTimeout,
+                    operation: "poll_until_timeout",
+                    bus: self.name,
+                    detail: Some(ErrorDetail::Timeout { waited }),
+                });
+            }
+
+            delay.delay(interval);
+            waited += interval;
         }
     }
 
-    /// Mock I2C error type
-    #[derive(Debug)]
-    pub struct MockI2cError {
-        message: &'static str,
+    /// Poll an 8-bit register until its value differs from `baseline`
+    ///
+    /// The change-detection counterpart of [`poll_until`](Self::poll_until),
+    /// with the same interval and timeout behavior.
+    pub fn poll_until_change<D: DelayProvider>(
+        &self,
+        reg: u8,
+        baseline: u8,
+        interval: core::time::Duration,
+        timeout: core::time::Duration,
+        delay: &mut D,
+    ) -> Result<u8, HubrisI2cError> {
+        self.poll_until(reg, |value| value != baseline, interval, timeout, delay)
+            .map_err(|err| match err.detail {
+                Some(ErrorDetail::Timeout { .. }) => {
+                    err.with_operation("poll_until_change_timeout")
+                }
+                _ => err.with_operation("poll_until_change"),
+            })
     }
 
-    impl embedded_hal::i2c::Error for MockI2cError {
-        fn kind(&self) -> ErrorKind {
-            ErrorKind::Other
-        }
-    }
+    /// Read a payload from `reg` and verify the device's trailing CRC bytes
+    ///
+    /// The device sends `buffer.len()` payload bytes interleaved with CRC
+    /// bytes per `crc`; only the payload is copied into `buffer`. A CRC
+    /// mismatch fails with `BadResponse`. An empty payload, or one that
+    /// doesn't divide into whole words, fails with `BadArg`; one whose
+    /// length on the wire exceeds [`MAX_TRANSFER_LEN`] fails with
+    /// `TooMuchData`.
+    pub fn read_with_crc(
+        &self,
+        reg: u8,
+        buffer: &mut [u8],
+        crc: CrcSpec,
+    ) -> Result<(), HubrisI2cError> {
+        let chunk = crc.chunk_len(buffer.len()).ok_or(HubrisI2cError {
+            response_code: ResponseCode::BadArg,
+            operation: "read_with_crc_length",
+            bus: self.name,
+            detail: None,
+        })?;
 
-    impl core::fmt::Display for MockI2cError {
-        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-            write!(f, "Mock I2C error: {}", self.message)
+        let wire_len = buffer.len() + buffer.len() / chunk;
+        if wire_len > MAX_TRANSFER_LEN {
+            return Err(HubrisI2cError {
+                response_code: ResponseCode::TooMuchData,
+                operation: "read_with_crc_length",
+                bus: self.name,
+                detail: Some(ErrorDetail::TooLong {
+                    limit: MAX_TRANSFER_LEN,
+                    attempted: wire_len,
+                }),
+            });
         }
-    }
 
-    impl ErrorType for MockI2c {
-        type Error = MockI2cError;
-    }
+        let mut wire = [0u8; MAX_TRANSFER_LEN];
+        self.request_read_reg_into(&self.device, reg, &mut wire[..wire_len])
+            .map_err(|response_code| HubrisI2cError {
+                response_code,
+                operation: "read_with_crc",
+                bus: self.name,
+                detail: None,
+            })?;
 
-    impl embedded_hal::i2c::I2c<SevenBitAddr> for MockI2c {
-        fn read(&mut self, address: SevenBitAddr, buffer: &mut [u8]) -> Result<(), Self::Error> {
-            if self.operation_index >= self.expected_operations.len() {
-                return Err(MockI2cError {
-                    message: "Unexpected read operation",
+        for (payload, framed) in buffer
+            .chunks_mut(chunk)
+            .zip(wire[..wire_len].chunks(chunk + 1))
+        {
+            let (data, checksum) = framed.split_at(chunk);
+            if crc.checksum(data) != checksum[0] {
+                return Err(HubrisI2cError {
+                    response_code: ResponseCode::BadResponse,
+                    operation: "read_with_crc_mismatch",
+                    bus: self.name,
+                    detail: None,
                 });
             }
+            payload.copy_from_slice(data);
+        }
 
-            match &self.expected_operations[self.operation_index] {
-                MockOperation::Read {
-                    address: expected_addr,
-                    response,
-                } => {
-                    if *expected_addr != address {
-                        return Err(MockI2cError {
-                            message: "Read address mismatch",
-                        });
-                    }
+        Ok(())
+    }
 
-                    if buffer.len() != response.len() {
-                        return Err(MockI2cError {
-                            message: "Read buffer size mismatch",
-                        });
-                    }
+    /// Write `command` followed by `data` with CRC bytes computed per `crc`
+    ///
+    /// The write-direction counterpart of
+    /// [`read_with_crc`](Self::read_with_crc): a CRC byte is inserted after
+    /// each word of `data` (or after all of it, for
+    /// [`CrcCoverage::PerBlock`]) and the frame is sent as one write. The
+    /// command bytes are not covered, matching Sensirion parts, whose
+    /// commands carry no CRC. An empty `data`, or one that doesn't divide
+    /// into whole words, fails with `BadArg`; a frame longer than
+    /// [`max_transfer_len`](Self::max_transfer_len) fails with
+    /// `TooMuchData`.
+    pub fn write_with_crc(
+        &self,
+        command: &[u8],
+        data: &[u8],
+        crc: CrcSpec,
+    ) -> Result<(), HubrisI2cError> {
+        let chunk = crc.chunk_len(data.len()).ok_or(HubrisI2cError {
+            response_code: ResponseCode::BadArg,
+            operation: "write_with_crc_length",
+            bus: self.name,
+            detail: None,
+        })?;
 
-                    buffer.copy_from_slice(response);
-                    self.operation_index += 1;
-                    Ok(())
-                }
-                _ => Err(MockI2cError {
-                    message: "Expected read operation",
+        let frame_len = command.len() + data.len() + data.len() / chunk;
+        let limit = HubrisI2c::max_transfer_len();
+        if frame_len > limit {
+            return Err(HubrisI2cError {
+                response_code: ResponseCode::TooMuchData,
+                operation: "write_with_crc_length",
+                bus: self.name,
+                detail: Some(ErrorDetail::TooLong {
+                    limit,
+                    attempted: frame_len,
                 }),
-            }
+            });
         }
 
-        fn write(&mut self, address: SevenBitAddr, bytes: &[u8]) -> Result<(), Self::Error> {
-            if self.operation_index >= self.expected_operations.len() {
-                return Err(MockI2cError {
-                    message: "Unexpected write operation",
-                });
-            }
+        let mut frame = heapless::Vec::<u8, MAX_TRANSFER_LEN>::new();
+        // Can't overflow: the length was checked above
+        let _ = frame.extend_from_slice(command);
+        for word in data.chunks(chunk) {
+            let _ = frame.extend_from_slice(word);
+            let _ = frame.push(crc.checksum(word));
+        }
 
-            match &self.expected_operations[self.operation_index] {
-                MockOperation::Write {
-                    address: expected_addr,
-                    expected_data,
-                } => {
-                    if *expected_addr != address {
-                        return Err(MockI2cError {
-                            message: "Write address mismatch",
-                        });
-                    }
+        self.request_write(&self.device, &frame)
+            .map_err(|response_code| HubrisI2cError {
+                response_code,
+                operation: "write_with_crc",
+                bus: self.name,
+                detail: None,
+            })
+    }
 
-                    if bytes != expected_data.as_slice() {
-                        return Err(MockI2cError {
-                            message: "Write data mismatch",
-                        });
-                    }
+    /// 10-bit write followed by a read, choosing how the read is addressed
+    ///
+    /// Below, `XX` are address bits 9..8 and `A7..A0` the low address
+    /// byte. The header `11110XX` is sent to the server as a 7-bit
+    /// address, so it goes out with its R/W bit as shown.
+    ///
+    /// With `repeated_start` false, this is the 10-bit `write` followed by
+    /// the 10-bit `read`, two server requests with a STOP between them:
+    ///
+    /// ```text
+    /// S 11110XX0 A7..A0 data... P  S 11110XX0 A7..A0 Sr 11110XX1 data... P
+    /// ```
+    ///
+    /// The read addresses the device in full again, which suits devices
+    /// that keep their register pointer across a STOP.
+    ///
+    /// With `repeated_start` true, the low address byte and `bytes` go out
+    /// as the write half of a single server write-then-read request, so
+    /// the device stays addressed and only the header is repeated:
+    ///
+    /// ```text
+    /// S 11110XX0 A7..A0 data... Sr 11110XX1 data... P
+    /// ```
+    ///
+    /// The server's combined request takes a fixed-size register value,
+    /// so this mode carries at most two bytes of `bytes`, enough for an
+    /// 8- or 16-bit register pointer. Longer writes fail with
+    /// `OperationNotSupported` without touching the bus.
+    pub fn write_read_10bit(
+        &mut self,
+        address: TenBitAddr,
+        bytes: &[u8],
+        buffer: &mut [u8],
+        repeated_start: bool,
+    ) -> Result<(), HubrisI2cError> {
+        self.require(Capabilities::TEN_BIT, "10bit_addressing")?;
 
-                    self.operation_index += 1;
-                    Ok(())
-                }
-                _ => Err(MockI2cError {
-                    message: "Expected write operation",
-                }),
-            }
+        if !repeated_start {
+            self.write(address, bytes)?;
+            return self.read(address, buffer);
         }
 
-        fn write_read(
-            &mut self,
-            address: SevenBitAddr,
-            bytes: &[u8],
-            buffer: &mut [u8],
-        ) -> Result<(), Self::Error> {
-            if self.operation_index >= self.expected_operations.len() {
-                return Err(MockI2cError {
-                    message: "Unexpected write_read operation",
-                });
+        let (header, low) = self.ten_bit_parts(address);
+        let result = match *bytes {
+            [] => self.request_read_reg_into(&header, low, buffer),
+            [first] => self.request_read_reg_into(&header, [low, first], buffer),
+            [first, second] => self.request_read_reg_into(&header, [low, first, second], buffer),
+            _ => {
+                return Err(HubrisI2cError {
+                    response_code: ResponseCode::OperationNotSupported,
+                    operation: "10bit_repeated_start",
+                    bus: self.name,
+                    detail: Some(ErrorDetail::Unsupported(
+                        UnsupportedFeature::TenBitRepeatedStart,
+                    )),
+                })
             }
+        };
 
-            match &self.expected_operations[self.operation_index] {
-                MockOperation::WriteRead {
-                    address: expected_addr,
-                    expected_write,
-                    read_response,
-                } => {
-                    if *expected_addr != address {
-                        return Err(MockI2cError {
-                            message: "WriteRead address mismatch",
-                        });
-                    }
+        result.map(|_| ()).map_err(|response_code| HubrisI2cError {
+            response_code,
+            operation: "10bit_write_read",
+            bus: self.name,
+            detail: None,
+        })
+    }
 
-                    if bytes != expected_write.as_slice() {
-                        return Err(MockI2cError {
-                            message: "WriteRead write data mismatch",
-                        });
-                    }
+    /// Read `N` bytes from the device and return them by value
+    ///
+    /// Fails with `TooMuchData` if `N` exceeds
+    /// [`max_transfer_len`](Self::max_transfer_len).
+    pub fn read_array<const N: usize>(&self) -> Result<[u8; N], HubrisI2cError> {
+        self.check_array_len(N, "read_array")?;
 
-                    if buffer.len() != read_response.len() {
-                        return Err(MockI2cError {
-                            message: "WriteRead read buffer size mismatch",
-                        });
-                    }
+        let mut array = [0u8; N];
+        self.request_read_into(&self.device, &mut array)
+            .map_err(|response_code| HubrisI2cError {
+                response_code,
+                operation: "read_array",
+                bus: self.name,
+                detail: None,
+            })?;
 
-                    buffer.copy_from_slice(read_response);
-                    self.operation_index += 1;
-                    Ok(())
-                }
-                _ => Err(MockI2cError {
-                    message: "Expected write_read operation",
+        Ok(array)
+    }
+
+    /// Read `N` bytes starting at `reg` and return them by value
+    ///
+    /// Fails with `TooMuchData` if `N` exceeds
+    /// [`max_transfer_len`](Self::max_transfer_len).
+    pub fn read_reg_array<const N: usize>(&self, reg: u8) -> Result<[u8; N], HubrisI2cError> {
+        self.check_array_len(N, "read_reg_array")?;
+
+        let mut array = [0u8; N];
+        self.request_read_reg_into(&self.device, reg, &mut array)
+            .map_err(|response_code| HubrisI2cError {
+                response_code,
+                operation: "read_reg_array",
+                bus: self.name,
+                detail: None,
+            })?;
+
+        Ok(array)
+    }
+
+    /// Read up to `buffer.len()` bytes from `reg`, requiring at least `min`
+    ///
+    /// For variable-length responses that are meaningless below some
+    /// size. Returns the number of bytes the server reports reading; fewer
+    /// than `min` fails with `BadResponse` instead of leaving the caller
+    /// with a silently short buffer. Fails with `BadArg` if `min` exceeds
+    /// `buffer.len()`, since the requirement could never be met.
+    pub fn read_at_least(
+        &self,
+        reg: u8,
+        buffer: &mut [u8],
+        min: usize,
+    ) -> Result<usize, HubrisI2cError> {
+        if min > buffer.len() {
+            return Err(HubrisI2cError {
+                response_code: ResponseCode::BadArg,
+                operation: "read_at_least_length",
+                bus: self.name,
+                detail: None,
+            });
+        }
+
+        let count = self
+            .request_read_reg_into(&self.device, reg, buffer)
+            .map_err(|response_code| HubrisI2cError {
+                response_code,
+                operation: "read_at_least",
+                bus: self.name,
+                detail: None,
+            })?;
+
+        if count < min {
+            return Err(HubrisI2cError {
+                response_code: ResponseCode::BadResponse,
+                operation: "read_at_least_short",
+                bus: self.name,
+                detail: None,
+            });
+        }
+
+        Ok(count)
+    }
+
+    /// Read `reg` once per slot of `samples`, re-sending the register each time
+    ///
+    /// For devices that neither auto-increment nor keep their register
+    /// pointer between reads: each sample is its own register read, so the
+    /// device always returns the current value of `reg`. This differs from
+    /// [`read_reg_array`](Self::read_reg_array), which sends `reg` once
+    /// and relies on auto-increment to read the following registers, and
+    /// from [`fifo_reader`](Self::fifo_reader), which sends it once and
+    /// relies on the device keeping its pointer. Costs one server call per
+    /// sample; stops at the first failure.
+    pub fn read_repeated(&self, reg: u8, samples: &mut [u8]) -> Result<(), HubrisI2cError> {
+        for sample in samples.iter_mut() {
+            *sample = self
+                .request_read_reg(&self.device, reg)
+                .map_err(|response_code| HubrisI2cError {
+                    response_code,
+                    operation: "read_repeated",
+                    bus: self.name,
+                    detail: None,
+                })?;
+        }
+
+        Ok(())
+    }
+
+    /// Read a block of consecutive registers starting at `start_reg` as a `T`
+    ///
+    /// Reads `size_of::<T>()` bytes in one write-read and reinterprets
+    /// them as `T`, e.g. all six accelerometer output registers as a
+    /// `#[repr(C)]` struct of three `i16`s. The bytes are used as-is: no
+    /// byte order conversion is applied, so multi-byte fields hold the
+    /// device's byte order and must be declared accordingly (such as
+    /// zerocopy's `byteorder::I16<BigEndian>`) or converted by the caller.
+    ///
+    /// Fails with `TooMuchData` if `T` is larger than
+    /// [`max_transfer_len`](Self::max_transfer_len).
+    pub fn read_struct<T: zerocopy::FromBytes>(&self, start_reg: u8) -> Result<T, HubrisI2cError> {
+        let len = core::mem::size_of::<T>();
+        self.check_array_len(len, "read_struct")?;
+
+        let mut raw = [0u8; MAX_TRANSFER_LEN];
+        let raw = &mut raw[..len];
+        self.request_read_reg_into(&self.device, start_reg, raw)
+            .map_err(|response_code| HubrisI2cError {
+                response_code,
+                operation: "read_struct",
+                bus: self.name,
+                detail: None,
+            })?;
+
+        // Only fails on a size mismatch, which the slice above rules out
+        T::read_from_bytes(raw).map_err(|_| HubrisI2cError {
+            response_code: ResponseCode::BadArg,
+            operation: "read_struct",
+            bus: self.name,
+            detail: None,
+        })
+    }
+
+    fn check_array_len(&self, len: usize, operation: &'static str) -> Result<(), HubrisI2cError> {
+        let limit = HubrisI2c::max_transfer_len();
+        if len > limit {
+            return Err(HubrisI2cError {
+                response_code: ResponseCode::TooMuchData,
+                operation,
+                bus: self.name,
+                detail: Some(ErrorDetail::TooLong {
+                    limit,
+                    attempted: len,
                 }),
-            }
+            });
         }
+        Ok(())
+    }
 
-        fn transaction(
-            &mut self,
-            address: SevenBitAddr,
-            operations: &mut [Operation<'_>],
-        ) -> Result<(), Self::Error> {
-            for operation in operations.iter_mut() {
-                match operation {
-                    Operation::Read(buffer) => {
-                        self.read(address, buffer)?;
-                    }
-                    Operation::Write(data) => {
-                        self.write(address, data)?;
-                    }
-                }
-            }
-            Ok(())
+    /// Read a signed value of `bits` width from `reg`, sign-extended to `i32`
+    ///
+    /// Reads `bits.div_ceil(8)` bytes, assembles them in the configured
+    /// byte order (host order if none is set, as for
+    /// [`read_register`](Self::read_register)) and treats the low `bits`
+    /// bits as a two's-complement value. A left-justified value (such as a
+    /// 12-bit reading in the top of a 16-bit register) must be shifted by
+    /// the caller first. Fails with `BadArg` unless `bits` is in `1..=32`.
+    pub fn read_signed_register(&self, reg: u8, bits: u8) -> Result<i32, HubrisI2cError> {
+        if !(1..=32).contains(&bits) {
+            return Err(HubrisI2cError {
+                response_code: ResponseCode::BadArg,
+                operation: "read_signed_register_width",
+                bus: self.name,
+                detail: None,
+            });
         }
+
+        let len = usize::from(bits.div_ceil(8));
+        let mut bytes = [0u8; 4];
+        self.request_read_reg_into(&self.device, reg, &mut bytes[..len])
+            .map_err(|response_code| HubrisI2cError {
+                response_code,
+                operation: "read_signed_register",
+                bus: self.name,
+                detail: None,
+            })?;
+
+        let raw = match self.byte_order.unwrap_or(ByteOrder::HOST) {
+            ByteOrder::Big => bytes[..len]
+                .iter()
+                .fold(0u32, |acc, &byte| acc << 8 | u32::from(byte)),
+            ByteOrder::Little => bytes[..len]
+                .iter()
+                .rev()
+                .fold(0u32, |acc, &byte| acc << 8 | u32::from(byte)),
+        };
+
+        // Move the sign bit to bit 31, then shift back arithmetically
+        let shift = 32 - u32::from(bits);
+        Ok(((raw << shift) as i32) >> shift)
     }
 
-    impl Default for MockI2c {
-        fn default() -> Self {
-            Self::new()
+    /// Read an ASCII identification string (model, serial) from a block
+    ///
+    /// Does an SMBus block read into `buffer`, trims trailing NUL and space
+    /// padding, and returns the text. Fails with `BadResponse` if the
+    /// contents aren't valid UTF-8.
+    pub fn read_string_block<'b>(
+        &self,
+        reg: u8,
+        buffer: &'b mut [u8],
+    ) -> Result<&'b str, HubrisI2cError> {
+        let count = self
+            .read_block(reg, buffer)
+            .map_err(|err| err.with_operation("read_string_block"))?;
+
+        let text = &buffer[..count];
+        let len = text
+            .iter()
+            .rposition(|&byte| byte != 0 && byte != b' ')
+            .map_or(0, |last| last + 1);
+
+        core::str::from_utf8(&text[..len]).map_err(|_| HubrisI2cError {
+            response_code: ResponseCode::BadResponse,
+            operation: "read_string_block_encoding",
+            bus: self.name,
+            detail: None,
+        })
+    }
+
+    /// Run a transaction, rejecting empty operations instead of skipping them
+    ///
+    /// The embedded-hal `transaction` silently skips zero-length reads and
+    /// writes. Here any empty operation fails the whole transaction with
+    /// `BadArg` before anything is issued, for callers who treat one as a
+    /// bug.
+    pub fn transaction_strict(
+        &mut self,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), HubrisI2cError> {
+        if operations.iter().any(is_empty_operation) {
+            return Err(HubrisI2cError {
+                response_code: ResponseCode::BadArg,
+                operation: "transaction_empty_operation",
+                bus: self.name,
+                detail: None,
+            });
         }
+
+        let address = self.address();
+        embedded_hal::i2c::I2c::transaction(self, address, operations)
     }
-}
 
-// Re-export common types for convenience
-pub use embedded_hal::i2c::{Error, ErrorKind, I2c, NoAcknowledgeSource, Operation};
+    /// Read a PMBus LINEAR11 value from `command`
+    ///
+    /// LINEAR11 packs a 5-bit two's-complement exponent `N` (bits 15:11)
+    /// and an 11-bit two's-complement mantissa `Y` (bits 10:0) into an
+    /// SMBus word; the value is `Y * 2^N`. Most PMBus telemetry (READ_VIN,
+    /// READ_IOUT, READ_POUT, ...) uses it.
+    pub fn read_linear11(&self, command: u8) -> Result<f32, HubrisI2cError> {
+        let (mantissa, exponent) = self.read_linear11_raw(command, "read_linear11")?;
+
+        let scale = (1u32 << exponent.unsigned_abs()) as f32;
+        Ok(if exponent >= 0 {
+            mantissa as f32 * scale
+        } else {
+            mantissa as f32 / scale
+        })
+    }
+
+    /// Read a PMBus LINEAR11 value from `command` in thousandths
+    ///
+    /// Fixed-point variant of [`read_linear11`](Self::read_linear11) for
+    /// targets without an FPU, e.g. millivolts for READ_VOUT-style
+    /// commands. Fractions below one thousandth are truncated toward
+    /// negative infinity. Large exponents overflow `i32`, hence `i64`.
+    pub fn read_linear11_milli(&self, command: u8) -> Result<i64, HubrisI2cError> {
+        let (mantissa, exponent) = self.read_linear11_raw(command, "read_linear11_milli")?;
+
+        let milli = i64::from(mantissa) * 1000;
+        Ok(if exponent >= 0 {
+            milli << exponent
+        } else {
+            milli >> exponent.unsigned_abs()
+        })
+    }
+
+    /// Read and split a LINEAR11 word into `(mantissa, exponent)`
+    fn read_linear11_raw(
+        &self,
+        command: u8,
+        operation: &'static str,
+    ) -> Result<(i16, i8), HubrisI2cError> {
+        let word = self
+            .smbus_read_word(command)
+            .map_err(|err| err.with_operation(operation))?;
+
+        // Sign-extend each field by shifting it to the top and back
+        let mantissa = ((word << 5) as i16) >> 5;
+        let exponent = ((word >> 8) as i8) >> 3;
+        Ok((mantissa, exponent))
+    }
+
+    /// Read a signed 16-bit temperature register in millidegrees Celsius
+    ///
+    /// The raw register is assembled in the configured byte order, or
+    /// big-endian if none is set (as temperature sensors almost always
+    /// are), then converted with `scale`. Fails with `BadArg` if the scale
+    /// has a zero denominator or the conversion overflows.
+    pub fn read_temperature_millicelsius(
+        &self,
+        reg: u8,
+        scale: TempScale,
+    ) -> Result<i32, HubrisI2cError> {
+        let mut bytes = [0u8; 2];
+        self.request_read_reg_into(&self.device, reg, &mut bytes)
+            .map_err(|response_code| HubrisI2cError {
+                response_code,
+                operation: "read_temperature",
+                bus: self.name,
+                detail: None,
+            })?;
+
+        let raw = match self.byte_order.unwrap_or(ByteOrder::Big) {
+            ByteOrder::Big => i16::from_be_bytes(bytes),
+            ByteOrder::Little => i16::from_le_bytes(bytes),
+        };
+
+        scale.to_millicelsius(raw).ok_or(HubrisI2cError {
+            response_code: ResponseCode::BadArg,
+            operation: "read_temperature_scale",
+            bus: self.name,
+            detail: None,
+        })
+    }
+
+    /// Run a [`RawTransaction`]
+    ///
+    /// The whole sequence is checked before the first request is issued.
+    /// Segments are separate server requests, so a failure part-way
+    /// leaves the earlier segments applied.
+    pub fn raw_transaction(&self, transaction: RawTransaction<'_>) -> Result<(), HubrisI2cError> {
+        let requests = transaction
+            .plan()
+            .map_err(|response_code| HubrisI2cError {
+                response_code,
+                operation: "raw_transaction_sequence",
+                bus: self.name,
+                detail: (response_code == ResponseCode::OperationNotSupported)
+                    .then_some(ErrorDetail::Unsupported(UnsupportedFeature::RawSequence)),
+            })?;
+
+        for request in requests {
+            let result = match request {
+                RawRequest::Write(bytes) => self.request_write(&self.device, bytes),
+                RawRequest::Read(buffer) => {
+                    self.request_read_into(&self.device, buffer).map(|_| ())
+                }
+                RawRequest::ReadReg(reg, buffer) => {
+                    self.request_read_reg_into(&self.device, reg, buffer).map(|_| ())
+                }
+            };
+
+            result.map_err(|response_code| HubrisI2cError {
+                response_code,
+                operation: "raw_transaction",
+                bus: self.name,
+                detail: None,
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /// Report whether SDA and SCL are currently released
+    ///
+    /// Meant as a check before a time-critical sequence: a line held low
+    /// by a stuck target calls for [`recover_bus`](Self::recover_bus)
+    /// rather than another attempt. Needs a server with
+    /// [`Capabilities::LINE_STATE`]; `drv-i2c-api` has no request for
+    /// sampling the lines, so against the stock server this fails fast
+    /// with `OperationNotSupported` and a stuck bus only surfaces as
+    /// `BusLocked` from the operation itself.
+    pub fn bus_health(&self) -> Result<BusHealth, HubrisI2cError> {
+        self.require(Capabilities::LINE_STATE, "bus_health")?;
+
+        let (sda_high, scl_high) =
+            self.backend
+                .line_state(&self.device)
+                .map_err(|response_code| HubrisI2cError {
+                    response_code,
+                    operation: "bus_health",
+                    bus: self.name,
+                    detail: None,
+                })?;
+
+        Ok(BusHealth {
+            sda_high,
+            scl_high,
+            idle: sda_high && scl_high,
+        })
+    }
+
+    /// SCL frequency the controller is running at, in hertz
+    ///
+    /// Meant for estimating transfer durations when sizing timeouts.
+    /// Needs a server with [`Capabilities::BUS_FREQUENCY`]. The stock
+    /// server fixes the speed in its board configuration and
+    /// `drv-i2c-api` has no request to report it, so against it this fails
+    /// fast with `OperationNotSupported`; take the speed from the same
+    /// board configuration instead.
+    pub fn bus_frequency_hz(&self) -> Result<u32, HubrisI2cError> {
+        self.require(Capabilities::BUS_FREQUENCY, "bus_frequency_hz")?;
 
-#[cfg(feature = "testing")]
-pub use mock::MockI2c;
+        self.backend
+            .bus_frequency_hz(&self.device)
+            .map_err(|response_code| HubrisI2cError {
+                response_code,
+                operation: "bus_frequency_hz",
+                bus: self.name,
+                detail: None,
+            })
+    }
+
+    /// SMBus Read Byte with PEC, see [`smbus_read_byte`](Self::smbus_read_byte)
+    ///
+    /// The `_pec` variants compute and check PEC in the client, which works
+    /// with the stock server: a write carries the PEC as one more data
+    /// byte, and a read asks for one more byte than the payload and
+    /// verifies it. The PEC covers the address bytes with their R/W bit
+    /// as well as the command and data, per the SMBus spec. A mismatch on
+    /// a read fails with `BadResponse`.
+    pub fn smbus_read_byte_pec(&self, command: u8) -> Result<u8, HubrisI2cError> {
+        let mut byte = [0u8; 1];
+        self.smbus_read_pec(command, &mut byte, "smbus_read_byte_pec")?;
+        Ok(byte[0])
+    }
+
+    /// SMBus Write Byte with PEC
+    pub fn smbus_write_byte_pec(&self, command: u8, value: u8) -> Result<(), HubrisI2cError> {
+        self.smbus_write_pec(command, &[value], "smbus_write_byte_pec")
+    }
+
+    /// SMBus Read Word (little-endian) with PEC
+    pub fn smbus_read_word_pec(&self, command: u8) -> Result<u16, HubrisI2cError> {
+        let mut word = [0u8; 2];
+        self.smbus_read_pec(command, &mut word, "smbus_read_word_pec")?;
+        Ok(u16::from_le_bytes(word))
+    }
+
+    /// SMBus Write Word (little-endian) with PEC
+    pub fn smbus_write_word_pec(&self, command: u8, value: u16) -> Result<(), HubrisI2cError> {
+        self.smbus_write_pec(command, &value.to_le_bytes(), "smbus_write_word_pec")
+    }
+
+    /// Read `data.len()` bytes of `command` plus a trailing PEC byte and check it
+    fn smbus_read_pec(
+        &self,
+        command: u8,
+        data: &mut [u8],
+        operation: &'static str,
+    ) -> Result<(), HubrisI2cError> {
+        let mut raw = [0u8; 3];
+        let raw = &mut raw[..data.len() + 1];
+        self.request_read_reg_into(&self.device, command, raw)
+            .map_err(|response_code| HubrisI2cError {
+                response_code,
+                operation,
+                bus: self.name,
+                detail: None,
+            })?;
+
+        let (payload, pec) = raw.split_at(data.len());
+        let mut expected = PecAccumulator::new();
+        expected.update(&[self.device.address << 1, command, self.device.address << 1 | 1]);
+        expected.update(payload);
+        if expected.finish() != pec[0] {
+            return Err(HubrisI2cError {
+                response_code: ResponseCode::BadResponse,
+                operation: "smbus_pec_mismatch",
+                bus: self.name,
+                detail: None,
+            });
+        }
+
+        data.copy_from_slice(payload);
+        Ok(())
+    }
+
+    /// Write `command` and `data` followed by their PEC byte
+    fn smbus_write_pec(
+        &self,
+        command: u8,
+        data: &[u8],
+        operation: &'static str,
+    ) -> Result<(), HubrisI2cError> {
+        let mut frame = [0u8; 4];
+        let len = data.len() + 1;
+        frame[0] = command;
+        frame[1..len].copy_from_slice(data);
+
+        let mut pec = PecAccumulator::new();
+        pec.update(&[self.device.address << 1]);
+        pec.update(&frame[..len]);
+        frame[len] = pec.finish();
+
+        self.request_write(&self.device, &frame[..len + 1])
+            .map_err(|response_code| HubrisI2cError {
+                response_code,
+                operation,
+                bus: self.name,
+                detail: None,
+            })
+    }
+}
+
+/// Check for a zero-length read or write
+fn is_empty_operation(operation: &Operation<'_>) -> bool {
+    match operation {
+        Operation::Read(buffer) => buffer.is_empty(),
+        Operation::Write(data) => data.is_empty(),
+    }
+}
+
+/// Registers whose value in `actual` differs from `expected`
+///
+/// Returns `(reg, expected, actual)` for each mismatch, in the order of
+/// `expected`. A register missing from `actual` (never read) is reported
+/// with an `actual` of `None`. Registers only in `actual` are ignored, so
+/// a snapshot may cover more than the golden map and the two maps may
+/// have different capacities.
+pub fn diff_registers<const N: usize, const M: usize>(
+    expected: &heapless::FnvIndexMap<u8, u8, N>,
+    actual: &heapless::FnvIndexMap<u8, u8, M>,
+) -> heapless::Vec<(u8, u8, Option<u8>), N> {
+    let mut mismatches = heapless::Vec::new();
+
+    for (&reg, &want) in expected {
+        let got = actual.get(&reg).copied();
+        if got != Some(want) {
+            // Can't overflow: at most one entry per key in `expected`
+            let _ = mismatches.push((reg, want, got));
+        }
+    }
+
+    mismatches
+}
+
+/// Physical state of the bus lines, see [`HubrisI2c::bus_health`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BusHealth {
+    pub sda_high: bool,
+    pub scl_high: bool,
+    /// Both lines high, so a new transfer can start
+    pub idle: bool,
+}
+
+/// Kind of operation recorded in a [`LastOp`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LastOpKind {
+    Read,
+    Write,
+    WriteRead,
+}
+
+/// Single-slot record of an operation, see [`HubrisI2c::last_operation`]
+///
+/// Transactions are issued one operation at a time, so they show up as
+/// their most recent read or write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LastOp {
+    pub kind: LastOpKind,
+    /// First byte written (usually the register), if anything was written
+    pub first_byte: Option<u8>,
+    /// Total bytes written and read
+    pub len: usize,
+}
+
+/// Temperature represented by one LSB of a 16-bit register
+///
+/// Stored as the exact ratio `numerator / denominator` millidegrees
+/// Celsius, since common resolutions (7.8125 m°C) aren't whole numbers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TempScale {
+    pub numerator: i32,
+    pub denominator: i32,
+}
+
+impl TempScale {
+    /// TI TMP117: 7.8125 m°C per LSB
+    pub const TMP117: TempScale = TempScale {
+        numerator: 125,
+        denominator: 16,
+    };
+
+    /// LM75 family: 0.5 °C (9-bit) or 0.125 °C (11-bit) in the top bits
+    /// of the register, both 3.90625 m°C per LSB of the full 16-bit value
+    pub const LM75: TempScale = TempScale {
+        numerator: 125,
+        denominator: 32,
+    };
+
+    /// Convert a raw reading, `None` on a zero denominator or overflow
+    ///
+    /// Rounds toward zero.
+    pub fn to_millicelsius(self, raw: i16) -> Option<i32> {
+        i32::from(raw)
+            .checked_mul(self.numerator)?
+            .checked_div(self.denominator)
+    }
+}
+
+/// Byte order of multi-byte values on the wire
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteOrder {
+    /// Most significant byte first (most sensors)
+    Big,
+    /// Least significant byte first (SMBus/PMBus words)
+    Little,
+}
+
+impl ByteOrder {
+    /// Byte order of the host MCU
+    pub const HOST: ByteOrder = if cfg!(target_endian = "big") {
+        ByteOrder::Big
+    } else {
+        ByteOrder::Little
+    };
+
+    /// Convert a scalar value read in this byte order to host order
+    pub fn to_host<V>(self, value: &mut V)
+    where
+        V: zerocopy::IntoBytes + zerocopy::FromBytes,
+    {
+        if self != Self::HOST {
+            value.as_mut_bytes().reverse();
+        }
+    }
+}
+
+/// Streaming reader for a FIFO register, see [`HubrisI2c::fifo_reader`]
+pub struct FifoReader<'a, B = IpcBackend> {
+    wrapper: &'a HubrisI2c<B>,
+}
+
+impl<B: I2cBackend> FifoReader<'_, B> {
+    /// Read the next sample without re-sending the register pointer
+    pub fn next(&mut self, buffer: &mut [u8]) -> Result<(), HubrisI2cError> {
+        self.wrapper
+            .request_read_into(&self.wrapper.device, buffer)
+            .map(|_| ())
+            .map_err(|response_code| HubrisI2cError {
+                response_code,
+                operation: "fifo_read",
+                bus: self.wrapper.name,
+                detail: None,
+            })
+    }
+}
+
+/// Read-only view of a [`HubrisI2c`], see [`HubrisI2c::read_only`]
+///
+/// Deliberately doesn't implement `embedded_hal::i2c::I2c`, so writes are
+/// unavailable at the type level:
+///
+/// ```compile_fail
+/// use drv_i2c_generic::embedded_hal_wrapper::HubrisI2c;
+/// use drv_i2c_types::{Controller, PortIndex};
+/// use userlib::TaskId;
+/// # const I2C_SERVER_TASK: TaskId = TaskId::KERNEL;
+///
+/// let i2c = HubrisI2c::new_simple(I2C_SERVER_TASK, Controller::I2c1, PortIndex(0), 0x48);
+/// i2c.read_only().write(&[0x01, 0x00]);
+/// ```
+pub struct ReadOnlyI2c<'a, B = IpcBackend> {
+    wrapper: &'a HubrisI2c<B>,
+}
+
+impl<B: I2cBackend> ReadOnlyI2c<'_, B> {
+    /// Read bytes from the device
+    pub fn read(&self, buffer: &mut [u8]) -> Result<(), HubrisI2cError> {
+        self.wrapper
+            .request_read_into(&self.wrapper.device, buffer)
+            .map(|_| ())
+            .map_err(|response_code| HubrisI2cError {
+                response_code,
+                operation: "read",
+                bus: self.wrapper.name,
+                detail: None,
+            })
+    }
+
+    /// Read register `reg` into `buffer`
+    pub fn read_reg_into(&self, reg: u8, buffer: &mut [u8]) -> Result<(), HubrisI2cError> {
+        self.wrapper
+            .request_read_reg_into(&self.wrapper.device, reg, buffer)
+            .map(|_| ())
+            .map_err(|response_code| HubrisI2cError {
+                response_code,
+                operation: "write_read_reg",
+                bus: self.wrapper.name,
+                detail: None,
+            })
+    }
+
+    /// Typed register read, see [`HubrisI2c::read_register`]
+    pub fn read_register<V>(&self, reg: u8) -> Result<V, HubrisI2cError>
+    where
+        V: zerocopy::IntoBytes + zerocopy::FromBytes,
+    {
+        self.wrapper.read_register(reg)
+    }
+
+    /// SMBus block read, see [`HubrisI2c::read_block`]
+    pub fn read_block(&self, reg: u8, buffer: &mut [u8]) -> Result<usize, HubrisI2cError> {
+        self.wrapper.read_block(reg, buffer)
+    }
+}
+
+/// Write-only view of a [`HubrisI2c`], see [`HubrisI2c::write_only`]
+pub struct WriteOnlyI2c<'a, B = IpcBackend> {
+    wrapper: &'a HubrisI2c<B>,
+}
+
+impl<B: I2cBackend> WriteOnlyI2c<'_, B> {
+    /// Write bytes to the device
+    pub fn write(&self, bytes: &[u8]) -> Result<(), HubrisI2cError> {
+        if bytes.is_empty() {
+            return self.wrapper.write_address_only();
+        }
+
+        self.wrapper
+            .request_write(&self.wrapper.device, bytes)
+            .map_err(|response_code| HubrisI2cError {
+                response_code,
+                operation: "write",
+                bus: self.wrapper.name,
+                detail: None,
+            })
+    }
+}
+
+/// Compile-time description of a device, for `static` device tables
+///
+/// `I2cDevice::new` isn't `const`, and the server's `TaskId` is only known
+/// at runtime, so device tables are declared as specs and turned into
+/// [`HubrisI2c`] handles during init:
+///
+/// ```rust
+/// use drv_i2c_generic::embedded_hal_wrapper::HubrisI2cSpec;
+/// use drv_i2c_types::{Controller, PortIndex};
+/// use userlib::TaskId;
+/// # const I2C_SERVER_TASK: TaskId = TaskId::KERNEL;
+///
+/// static SENSORS: [HubrisI2cSpec; 2] = [
+///     HubrisI2cSpec::new_simple(Controller::I2c1, PortIndex(0), 0x48),
+///     HubrisI2cSpec::new_simple(Controller::I2c1, PortIndex(0), 0x49),
+/// ];
+///
+/// for spec in &SENSORS {
+///     let i2c = spec.connect(I2C_SERVER_TASK);
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HubrisI2cSpec {
+    pub controller: Controller,
+    pub port: PortIndex,
+    pub segment: Option<(Mux, Segment)>,
+    pub address: SevenBitAddr,
+}
+
+impl HubrisI2cSpec {
+    /// Create a device spec
+    ///
+    /// # Panics
+    ///
+    /// Panics if `address` is outside the 7-bit range or reserved. In a
+    /// `static` or `const` initializer this is a compile error.
+    pub const fn new(
+        controller: Controller,
+        port: PortIndex,
+        segment: Option<(Mux, Segment)>,
+        address: u8,
+    ) -> Self {
+        assert!(
+            address >= 0x08 && address <= 0x77,
+            "I2C device address is reserved or exceeds 7-bit range"
+        );
+        Self {
+            controller,
+            port,
+            segment,
+            address: SevenBitAddr(address),
+        }
+    }
+
+    /// Create a spec for a device without multiplexer
+    pub const fn new_simple(controller: Controller, port: PortIndex, address: u8) -> Self {
+        Self::new(controller, port, None, address)
+    }
+
+    /// Build the runtime handle for this device
+    pub fn connect(&self, i2c_server_task: TaskId) -> HubrisI2c {
+        HubrisI2c::new(
+            i2c_server_task,
+            self.controller,
+            self.port,
+            self.segment,
+            self.address.0,
+        )
+    }
+}
+
+/// Requests a [`HubrisI2c`] sends to the I2C server
+///
+/// [`IpcBackend`] sends them to `drv-i2c-server` over IPC. Anything else
+/// implementing this (typically a host-test fake that records requests
+/// and scripts replies) can stand in for the server via
+/// [`HubrisI2c::with_backend`]. Each request names its target with an
+/// [`I2cDevice`], whose routing and address the backend must use as given.
+pub trait I2cBackend {
+    /// Write `bytes`: START, address+W, `bytes`, STOP
+    fn write(&self, device: &I2cDevice, bytes: &[u8]) -> Result<(), ResponseCode>;
+
+    /// Read into `buffer`, returning the number of bytes read
+    fn read_into(&self, device: &I2cDevice, buffer: &mut [u8]) -> Result<usize, ResponseCode>;
+
+    /// Write `reg`, then read into `buffer` after a repeated START
+    fn read_reg_into<R>(
+        &self,
+        device: &I2cDevice,
+        reg: R,
+        buffer: &mut [u8],
+    ) -> Result<usize, ResponseCode>
+    where
+        R: zerocopy::IntoBytes + zerocopy::Immutable;
+
+    /// SMBus block read of `reg`, returning the block length
+    fn read_block<R>(
+        &self,
+        device: &I2cDevice,
+        reg: R,
+        buffer: &mut [u8],
+    ) -> Result<usize, ResponseCode>
+    where
+        R: zerocopy::IntoBytes + zerocopy::Immutable;
+
+    /// Typed register read, by default through [`read_reg_into`](Self::read_reg_into)
+    fn read_reg<R, V>(&self, device: &I2cDevice, reg: R) -> Result<V, ResponseCode>
+    where
+        R: zerocopy::IntoBytes + zerocopy::Immutable,
+        V: zerocopy::IntoBytes + zerocopy::FromBytes,
+    {
+        let mut value = V::new_zeroed();
+        self.read_reg_into(device, reg, value.as_mut_bytes())?;
+        Ok(value)
+    }
+
+    /// Optional features this server supports
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::NONE
+    }
+
+    /// Clock out a stuck target on `device`'s bus
+    ///
+    /// Only called when [`capabilities`](Self::capabilities) includes
+    /// [`Capabilities::BUS_RECOVERY`].
+    fn recover_bus(&self, device: &I2cDevice) -> Result<(), ResponseCode> {
+        let _ = device;
+        Err(ResponseCode::OperationNotSupported)
+    }
+
+    /// Emit a STOP condition on `device`'s bus
+    ///
+    /// Only called when [`capabilities`](Self::capabilities) includes
+    /// [`Capabilities::FORCE_STOP`].
+    fn force_stop(&self, device: &I2cDevice) -> Result<(), ResponseCode> {
+        let _ = device;
+        Err(ResponseCode::OperationNotSupported)
+    }
+
+    /// Sample `device`'s bus lines, as `(sda_high, scl_high)`
+    ///
+    /// Only called when [`capabilities`](Self::capabilities) includes
+    /// [`Capabilities::LINE_STATE`].
+    fn line_state(&self, device: &I2cDevice) -> Result<(bool, bool), ResponseCode> {
+        let _ = device;
+        Err(ResponseCode::OperationNotSupported)
+    }
+
+    /// SCL frequency of `device`'s bus, in hertz
+    ///
+    /// Only called when [`capabilities`](Self::capabilities) includes
+    /// [`Capabilities::BUS_FREQUENCY`].
+    fn bus_frequency_hz(&self, device: &I2cDevice) -> Result<u32, ResponseCode> {
+        let _ = device;
+        Err(ResponseCode::OperationNotSupported)
+    }
+
+    /// Block until a controller addresses `device`, which is our own address
+    ///
+    /// This and the other `target_*` requests are only called when
+    /// [`capabilities`](Self::capabilities) includes
+    /// [`Capabilities::TARGET_MODE`].
+    #[cfg(feature = "target-mode")]
+    fn target_listen(&self, device: &I2cDevice) -> Result<target::TargetEvent, ResponseCode> {
+        let _ = device;
+        Err(ResponseCode::OperationNotSupported)
+    }
+
+    /// Supply `data` for the controller read pending on `device`
+    #[cfg(feature = "target-mode")]
+    fn target_respond_read(&self, device: &I2cDevice, data: &[u8]) -> Result<(), ResponseCode> {
+        let _ = (device, data);
+        Err(ResponseCode::OperationNotSupported)
+    }
+
+    /// Receive the controller write pending on `device`, returning its length
+    #[cfg(feature = "target-mode")]
+    fn target_accept_write(
+        &self,
+        device: &I2cDevice,
+        buffer: &mut [u8],
+    ) -> Result<usize, ResponseCode> {
+        let _ = (device, buffer);
+        Err(ResponseCode::OperationNotSupported)
+    }
+
+    /// Issue the requests `request` makes at scheduling `priority`
+    ///
+    /// Higher is more urgent. A FIFO-only server has nowhere to put the
+    /// hint, so the default ignores it and issues `request` as it is.
+    fn with_priority<T>(
+        &self,
+        priority: u8,
+        request: impl FnOnce(&Self) -> Result<T, ResponseCode>,
+    ) -> Result<T, ResponseCode> {
+        let _ = priority;
+        request(self)
+    }
+
+    /// Issue the requests `request` makes with a bound on each reply
+    ///
+    /// A server that doesn't answer within `timeout` must make the request
+    /// fail with `BusTimeout` instead of blocking. Only called when
+    /// [`capabilities`](Self::capabilities) includes [`Capabilities::TIMEOUT`].
+    fn with_timeout<T>(
+        &self,
+        timeout: core::time::Duration,
+        request: impl FnOnce(&Self) -> Result<T, ResponseCode>,
+    ) -> Result<T, ResponseCode> {
+        let _ = (timeout, request);
+        Err(ResponseCode::OperationNotSupported)
+    }
+}
+
+impl<B: I2cBackend + ?Sized> I2cBackend for &B {
+    fn write(&self, device: &I2cDevice, bytes: &[u8]) -> Result<(), ResponseCode> {
+        (**self).write(device, bytes)
+    }
+
+    fn read_into(&self, device: &I2cDevice, buffer: &mut [u8]) -> Result<usize, ResponseCode> {
+        (**self).read_into(device, buffer)
+    }
+
+    fn read_reg_into<R>(
+        &self,
+        device: &I2cDevice,
+        reg: R,
+        buffer: &mut [u8],
+    ) -> Result<usize, ResponseCode>
+    where
+        R: zerocopy::IntoBytes + zerocopy::Immutable,
+    {
+        (**self).read_reg_into(device, reg, buffer)
+    }
+
+    fn read_block<R>(
+        &self,
+        device: &I2cDevice,
+        reg: R,
+        buffer: &mut [u8],
+    ) -> Result<usize, ResponseCode>
+    where
+        R: zerocopy::IntoBytes + zerocopy::Immutable,
+    {
+        (**self).read_block(device, reg, buffer)
+    }
+
+    fn read_reg<R, V>(&self, device: &I2cDevice, reg: R) -> Result<V, ResponseCode>
+    where
+        R: zerocopy::IntoBytes + zerocopy::Immutable,
+        V: zerocopy::IntoBytes + zerocopy::FromBytes,
+    {
+        (**self).read_reg(device, reg)
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        (**self).capabilities()
+    }
+
+    fn recover_bus(&self, device: &I2cDevice) -> Result<(), ResponseCode> {
+        (**self).recover_bus(device)
+    }
+
+    fn force_stop(&self, device: &I2cDevice) -> Result<(), ResponseCode> {
+        (**self).force_stop(device)
+    }
+
+    fn line_state(&self, device: &I2cDevice) -> Result<(bool, bool), ResponseCode> {
+        (**self).line_state(device)
+    }
+
+    fn bus_frequency_hz(&self, device: &I2cDevice) -> Result<u32, ResponseCode> {
+        (**self).bus_frequency_hz(device)
+    }
+
+    #[cfg(feature = "target-mode")]
+    fn target_listen(&self, device: &I2cDevice) -> Result<target::TargetEvent, ResponseCode> {
+        (**self).target_listen(device)
+    }
+
+    #[cfg(feature = "target-mode")]
+    fn target_respond_read(&self, device: &I2cDevice, data: &[u8]) -> Result<(), ResponseCode> {
+        (**self).target_respond_read(device, data)
+    }
+
+    #[cfg(feature = "target-mode")]
+    fn target_accept_write(
+        &self,
+        device: &I2cDevice,
+        buffer: &mut [u8],
+    ) -> Result<usize, ResponseCode> {
+        (**self).target_accept_write(device, buffer)
+    }
+
+    fn with_priority<T>(
+        &self,
+        priority: u8,
+        request: impl FnOnce(&Self) -> Result<T, ResponseCode>,
+    ) -> Result<T, ResponseCode> {
+        (**self).with_priority(priority, |_| request(self))
+    }
+
+    fn with_timeout<T>(
+        &self,
+        timeout: core::time::Duration,
+        request: impl FnOnce(&Self) -> Result<T, ResponseCode>,
+    ) -> Result<T, ResponseCode> {
+        (**self).with_timeout(timeout, |_| request(self))
+    }
+}
+
+/// [`I2cBackend`] that sends each request to `drv-i2c-server` over IPC
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IpcBackend;
+
+impl I2cBackend for IpcBackend {
+    fn write(&self, device: &I2cDevice, bytes: &[u8]) -> Result<(), ResponseCode> {
+        device.write(bytes)
+    }
+
+    fn read_into(&self, device: &I2cDevice, buffer: &mut [u8]) -> Result<usize, ResponseCode> {
+        device.read_into(buffer)
+    }
+
+    fn read_reg_into<R>(
+        &self,
+        device: &I2cDevice,
+        reg: R,
+        buffer: &mut [u8],
+    ) -> Result<usize, ResponseCode>
+    where
+        R: zerocopy::IntoBytes + zerocopy::Immutable,
+    {
+        device.read_reg_into(reg, buffer)
+    }
+
+    fn read_block<R>(
+        &self,
+        device: &I2cDevice,
+        reg: R,
+        buffer: &mut [u8],
+    ) -> Result<usize, ResponseCode>
+    where
+        R: zerocopy::IntoBytes + zerocopy::Immutable,
+    {
+        device.read_block(reg, buffer)
+    }
+
+    fn read_reg<R, V>(&self, device: &I2cDevice, reg: R) -> Result<V, ResponseCode>
+    where
+        R: zerocopy::IntoBytes + zerocopy::Immutable,
+        V: zerocopy::IntoBytes + zerocopy::FromBytes,
+    {
+        device.read_reg(reg)
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::SERVER
+    }
+}
+
+/// Optional I2C server features, as a set of flags
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capabilities(u32);
+
+impl Capabilities {
+    /// No optional features
+    pub const NONE: Self = Capabilities(0);
+    /// 10-bit addressing: the server passes writes and write-then-reads to
+    /// the reserved header addresses `0x78..=0x7B` through to the bus
+    pub const TEN_BIT: Self = Capabilities(1 << 0);
+    /// Multi-operation transactions with repeated START
+    pub const ATOMIC_TRANSACTION: Self = Capabilities(1 << 1);
+    /// Bus recovery (SCL clock-out of a stuck target)
+    pub const BUS_RECOVERY: Self = Capabilities(1 << 2);
+    /// Per-operation timeouts
+    pub const TIMEOUT: Self = Capabilities(1 << 3);
+    /// SMBus PEC computed and checked by the server
+    pub const PEC_OFFLOAD: Self = Capabilities(1 << 4);
+    /// Acting as an I2C target (slave) on a controller
+    pub const TARGET_MODE: Self = Capabilities(1 << 5);
+    /// Emitting a bare STOP, see [`HubrisI2c::force_stop`]
+    pub const FORCE_STOP: Self = Capabilities(1 << 6);
+    /// Sampling SDA and SCL, see [`HubrisI2c::bus_health`]
+    pub const LINE_STATE: Self = Capabilities(1 << 7);
+    /// Reporting the SCL frequency, see [`HubrisI2c::bus_frequency_hz`]
+    pub const BUS_FREQUENCY: Self = Capabilities(1 << 8);
+
+    /// Capabilities of the stock `drv-i2c-server` build
+    ///
+    /// The server only exposes plain reads, writes, register reads and
+    /// SMBus block reads to 7-bit addresses, so none of the optional
+    /// features are present. In particular the 10-bit `I2c` impl,
+    /// [`HubrisI2c::recover_bus`], [`HubrisI2c::force_stop`],
+    /// [`HubrisI2c::bus_health`] and [`HubrisI2c::bus_frequency_hz`] fail
+    /// fast against it.
+    pub const SERVER: Self = Self::NONE;
+
+    /// Raw flag bits
+    pub const fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// Check whether every flag in `other` is also set in `self`
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Combine two sets of flags
+    pub const fn union(self, other: Self) -> Self {
+        Capabilities(self.0 | other.0)
+    }
+}
+
+/// Feature a server lacked, see [`HubrisI2cError::unsupported_feature`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnsupportedFeature {
+    /// 10-bit addressing at all, see [`Capabilities::TEN_BIT`]
+    TenBit,
+    /// Repeated START between a 10-bit write and read
+    TenBitRepeatedStart,
+    /// Clocking out a stuck target, see [`Capabilities::BUS_RECOVERY`]
+    BusRecovery,
+    /// Server-enforced per-operation timeout
+    OpTimeout,
+    /// Issuing a bare STOP condition
+    ForceStop,
+    /// Sampling the SDA/SCL line state
+    LineState,
+    /// Reporting the SCL frequency
+    BusFrequency,
+    /// Explicit raw START/STOP sequences other than the supported shapes
+    RawSequence,
+    /// Responding to another controller as an I2C target
+    TargetMode,
+    /// A capability named by the caller of [`HubrisI2c::require`]
+    Other,
+}
+
+impl UnsupportedFeature {
+    /// Feature reported when `required` is missing
+    ///
+    /// Capabilities without a dedicated variant, or several at once,
+    /// report [`Other`](Self::Other).
+    pub fn for_capabilities(required: Capabilities) -> Self {
+        match required {
+            Capabilities::TEN_BIT => UnsupportedFeature::TenBit,
+            Capabilities::BUS_RECOVERY => UnsupportedFeature::BusRecovery,
+            Capabilities::TIMEOUT => UnsupportedFeature::OpTimeout,
+            Capabilities::TARGET_MODE => UnsupportedFeature::TargetMode,
+            Capabilities::FORCE_STOP => UnsupportedFeature::ForceStop,
+            Capabilities::LINE_STATE => UnsupportedFeature::LineState,
+            Capabilities::BUS_FREQUENCY => UnsupportedFeature::BusFrequency,
+            _ => UnsupportedFeature::Other,
+        }
+    }
+}
+
+impl core::fmt::Display for UnsupportedFeature {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let feature = match self {
+            UnsupportedFeature::TenBit => "10-bit addressing",
+            UnsupportedFeature::TenBitRepeatedStart => "10-bit repeated start",
+            UnsupportedFeature::BusRecovery => "bus recovery",
+            UnsupportedFeature::OpTimeout => "operation timeouts",
+            UnsupportedFeature::ForceStop => "forced STOP",
+            UnsupportedFeature::LineState => "bus line sampling",
+            UnsupportedFeature::BusFrequency => "bus frequency reporting",
+            UnsupportedFeature::RawSequence => "this raw transaction sequence",
+            UnsupportedFeature::TargetMode => "target mode",
+            UnsupportedFeature::Other => "requested capability",
+        };
+        write!(f, "{} not supported by server", feature)
+    }
+}
+
+/// Error type that maps Hubris ResponseCode to embedded-hal errors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HubrisI2cError {
+    pub response_code: ResponseCode,
+    pub operation: &'static str,
+    /// Name of the bus the error came from, see [`HubrisI2c::with_name`]
+    pub bus: Option<&'static str>,
+    /// Structured context beyond the response code, where there is any
+    pub detail: Option<ErrorDetail>,
+}
+
+/// Structured context carried by a [`HubrisI2cError`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorDetail {
+    /// Address validation failed before anything was sent
+    InvalidAddress(InvalidAddress),
+    /// A request was refused up front for exceeding a size limit
+    TooLong {
+        /// Largest size accepted, in bytes
+        limit: usize,
+        /// Size that was asked for, in bytes
+        attempted: usize,
+    },
+    /// The server lacks a feature the request needed
+    Unsupported(UnsupportedFeature),
+    /// A wait gave up, as opposed to a request failing
+    Timeout {
+        /// Time spent waiting before giving up
+        waited: core::time::Duration,
+    },
+}
+
+impl core::fmt::Display for ErrorDetail {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ErrorDetail::InvalidAddress(err) => err.fmt(f),
+            ErrorDetail::TooLong { limit, attempted } => {
+                write!(f, "{} bytes exceeds the {} byte limit", attempted, limit)
+            }
+            ErrorDetail::Unsupported(feature) => feature.fmt(f),
+            ErrorDetail::Timeout { waited } => write!(f, "gave up after {:?}", waited),
+        }
+    }
+}
+
+impl embedded_hal::i2c::Error for HubrisI2cError {
+    fn kind(&self) -> ErrorKind {
+        match self.response_code {
+            ResponseCode::Success => ErrorKind::Other, // Should not happen
+
+            // Address/Data NACK errors
+            ResponseCode::AddressNackSentEarly | ResponseCode::AddressNackSentLate => {
+                ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address)
+            }
+            ResponseCode::DataNackSent => {
+                ErrorKind::NoAcknowledge(NoAcknowledgeSource::Data)
+            }
+
+            // Bus condition errors
+            ResponseCode::BusError => ErrorKind::Bus,
+            ResponseCode::ArbitrationLost => ErrorKind::ArbitrationLoss,
+
+            // All other errors map to Other
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
+impl core::fmt::Display for HubrisI2cError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self.bus {
+            Some(bus) => write!(
+                f,
+                "I2C {} operation failed on {}: {:?}",
+                self.operation, bus, self.response_code
+            )?,
+            None => write!(
+                f,
+                "I2C {} operation failed: {:?}",
+                self.operation, self.response_code
+            )?,
+        }
+
+        match self.detail {
+            Some(detail) => write!(f, " ({})", detail),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for HubrisI2cError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match &self.detail {
+            Some(ErrorDetail::InvalidAddress(err)) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Operation names in the order [`HubrisI2cError::code`] numbers them
+///
+/// Append-only: an entry's position is its code, so new operations go at
+/// the end and a renamed or retired operation keeps its slot. At most 255
+/// entries fit the 8-bit field.
+pub const OPERATION_IDS: &[&str] = &[
+    "10bit_addressing",
+    "10bit_read",
+    "10bit_repeated_start",
+    "10bit_write",
+    "10bit_write_read",
+    "10bit_write_too_long",
+    "address_only_write",
+    "arp_assign_address",
+    "arp_get_udid",
+    "arp_get_udid_length",
+    "arp_prepare",
+    "bus_frequency_hz",
+    "bus_health",
+    "conversion_result_read",
+    "conversion_trigger",
+    "drain_fifo",
+    "drain_fifo_status",
+    "fifo_read",
+    "fifo_set_pointer",
+    "force_stop",
+    "general_call",
+    "init_config_write",
+    "init_whoami",
+    "invalid_address_10bit_range",
+    "invalid_address_7bit_range",
+    "invalid_address_not_7bit",
+    "invalid_address_reserved",
+    "optimized_block_read",
+    "optimized_register_read",
+    "optimized_transaction",
+    "optimized_word_read",
+    "optimized_write_read",
+    "poll_until",
+    "poll_until_change",
+    "poll_until_change_timeout",
+    "poll_until_timeout",
+    "presence_cached",
+    "probe",
+    "probe_auto_increment",
+    "raw_transaction",
+    "raw_transaction_sequence",
+    "read",
+    "read_array",
+    "read_at_least",
+    "read_at_least_length",
+    "read_at_least_short",
+    "read_bit",
+    "read_block16",
+    "read_block16_length",
+    "read_linear11",
+    "read_linear11_milli",
+    "read_reg_array",
+    "read_reg_broadcast",
+    "read_reg_broadcast_length",
+    "read_register_map",
+    "read_register_set",
+    "read_register_set_length",
+    "read_registers_isolated",
+    "read_registers_isolated_length",
+    "read_repeated",
+    "read_signed_register",
+    "read_signed_register_width",
+    "read_string_block",
+    "read_string_block_encoding",
+    "read_struct",
+    "read_temperature",
+    "read_temperature_scale",
+    "read_with_crc",
+    "read_with_crc_length",
+    "read_with_crc_mismatch",
+    "recover_bus",
+    "replay_trace_length",
+    "set_op_timeout",
+    "smbus_alert_response",
+    "smbus_block_length",
+    "smbus_block_read",
+    "smbus_pec_mismatch",
+    "smbus_read_byte",
+    "smbus_read_byte_pec",
+    "smbus_read_word",
+    "smbus_read_word_pec",
+    "smbus_reset_all",
+    "smbus_write_byte",
+    "smbus_write_byte_pec",
+    "smbus_write_word",
+    "smbus_write_word_pec",
+    "snapshot_registers",
+    "target_accept_write",
+    "target_listen",
+    "target_mode",
+    "target_respond_read",
+    "transaction",
+    "transaction_empty_operation",
+    "transaction_op_count",
+    "transaction_read",
+    "transaction_size",
+    "transaction_write",
+    "write",
+    "write_gang",
+    "write_read",
+    "write_read_read_phase",
+    "write_read_reg",
+    "write_read_write_phase",
+    "write_register_verified_mismatch",
+    "write_register_verified_read",
+    "write_register_verified_write",
+    "write_tolerant",
+    "write_with_crc",
+    "write_with_crc_length",
+    "drain_fifo_bit",
+];
+
+impl HubrisI2cError {
+    /// Add operation context to error
+    pub fn with_operation(mut self, operation: &'static str) -> Self {
+        self.operation = operation;
+        self
+    }
+
+    /// Check if error indicates device not present
+    pub fn is_device_not_found(&self) -> bool {
+        matches!(
+            self.response_code,
+            ResponseCode::AddressNackSentEarly
+                | ResponseCode::AddressNackSentLate
+                | ResponseCode::NoDevice
+        )
+    }
+
+    /// Check if error indicates a malformed request or response
+    ///
+    /// The wrapper reports framing problems it detects itself as
+    /// `BadResponse`, which the server also uses for replies it couldn't
+    /// decode. Internally this comes from:
+    ///
+    /// - invalid addresses converted via `From<InvalidAddress>`
+    /// - SMBus ARP Get UDID replies with the wrong block length
+    /// - SMBus block reads reporting a length of 0 or over the block limit
+    /// - device CRC mismatches in [`HubrisI2c::read_with_crc`]
+    /// - string blocks that aren't valid UTF-8
+    /// - PEC mismatches in the `smbus_*_pec` reads
+    /// - short replies to [`HubrisI2c::read_at_least`]
+    /// - read-back mismatches from [`HubrisI2c::write_register_verified`]
+    ///
+    /// A protocol error means "the request or reply was malformed", not
+    /// "the bus failed", so retrying the same operation won't help.
+    pub fn is_protocol_error(&self) -> bool {
+        self.response_code == ResponseCode::BadResponse
+    }
+
+    /// Stable numeric code for host tooling
+    ///
+    /// Encoded as `(response << 8) | operation`:
+    ///
+    /// - `response` is the low byte of the `ResponseCode` discriminant as
+    ///   defined by `drv-i2c-api`
+    /// - `operation` is one plus the operation's index in
+    ///   [`OPERATION_IDS`], or 0 for a name not in the table, such as one
+    ///   set by a caller through [`HubrisI2cError::with_operation`]
+    ///
+    /// Tooling maps codes back to operations with the same table. The
+    /// encoding depends only on those two inputs and not on the `Display`
+    /// text, so it won't change across minor versions.
+    pub fn code(&self) -> u16 {
+        let operation = OPERATION_IDS
+            .iter()
+            .position(|&name| name == self.operation)
+            .map_or(0, |index| index as u8 + 1);
+        let response = (self.response_code as u32) as u8;
+
+        (response as u16) << 8 | operation as u16
+    }
+
+    /// Numeric `ResponseCode` discriminant exactly as the server sent it
+    ///
+    /// Codes this crate doesn't classify still surface as
+    /// `ErrorKind::Other`; the raw value lets a bug report name the code
+    /// even when it was added to `drv-i2c-api` after this crate.
+    pub fn raw_response_code(&self) -> u32 {
+        self.response_code as u32
+    }
+
+    /// Check if the controller was reset while the operation was in flight
+    ///
+    /// The server resets a controller (or the mux segment) when it finds
+    /// the bus in a bad state. Whatever part of the operation had reached
+    /// the device before the reset may or may not have taken effect.
+    pub fn is_bus_reset(&self) -> bool {
+        matches!(
+            self.response_code,
+            ResponseCode::BusReset | ResponseCode::BusResetMux
+        )
+    }
+
+    /// Which feature was missing, if this is an `OperationNotSupported` error
+    ///
+    /// Every unsupported path in this module records the feature in
+    /// [`ErrorDetail::Unsupported`] when it builds the error, so "10-bit
+    /// repeated start" and "operation timeouts" can be told apart without
+    /// matching on strings, and renaming the operation doesn't lose it.
+    /// `OperationNotSupported` without a recorded feature, such as one
+    /// returned by the server itself, reports [`UnsupportedFeature::Other`].
+    pub fn unsupported_feature(&self) -> Option<UnsupportedFeature> {
+        match self.detail {
+            Some(ErrorDetail::Unsupported(feature)) => Some(feature),
+            _ if self.response_code == ResponseCode::OperationNotSupported => {
+                Some(UnsupportedFeature::Other)
+            }
+            _ => None,
+        }
+    }
+
+    /// Check if error indicates temporary bus condition
+    pub fn is_temporary(&self) -> bool {
+        matches!(
+            self.response_code,
+            ResponseCode::BusLocked | ResponseCode::BusTimeout | ResponseCode::ArbitrationLost
+        )
+    }
+
+    /// Get suggested retry delay for temporary errors
+    pub fn retry_delay(&self) -> Option<core::time::Duration> {
+        match self.response_code {
+            ResponseCode::BusLocked => Some(core::time::Duration::from_millis(10)),
+            ResponseCode::BusTimeout => Some(core::time::Duration::from_millis(100)),
+            ResponseCode::ArbitrationLost => Some(core::time::Duration::from_millis(1)),
+            _ => None,
+        }
+    }
+}
+
+/// Failure of one operation within a multi-operation sequence
+///
+/// Operations before `failed_index` completed and were not rolled back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransactionError {
+    pub failed_index: usize,
+    pub source: HubrisI2cError,
+}
+
+impl core::fmt::Display for TransactionError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "operation {} failed: {}", self.failed_index, self.source)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TransactionError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+/// Failure of a verified write, see [`HubrisI2c::write_register_verified`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerifyError {
+    /// The write or the read-back failed on the bus
+    Bus(HubrisI2cError),
+    /// The register read back a different value in the compared bits
+    Mismatch {
+        reg: u8,
+        expected: u8,
+        actual: u8,
+        mask: u8,
+        bus: Option<&'static str>,
+    },
+}
+
+impl core::fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            VerifyError::Bus(err) => err.fmt(f),
+            VerifyError::Mismatch {
+                reg,
+                expected,
+                actual,
+                mask,
+                ..
+            } => write!(
+                f,
+                "register 0x{:02X} read back 0x{:02X}, expected 0x{:02X} (mask 0x{:02X})",
+                reg, actual, expected, mask
+            ),
+        }
+    }
+}
+
+impl From<VerifyError> for HubrisI2cError {
+    fn from(err: VerifyError) -> Self {
+        match err {
+            VerifyError::Bus(err) => err,
+            VerifyError::Mismatch { bus, .. } => HubrisI2cError {
+                response_code: ResponseCode::BadResponse,
+                operation: "write_register_verified_mismatch",
+                bus,
+                detail: None,
+            },
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for VerifyError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            VerifyError::Bus(err) => Some(err),
+            VerifyError::Mismatch { .. } => None,
+        }
+    }
+}
+
+/// Address wrapper for 7-bit addressing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SevenBitAddr(pub u8);
+
+impl SevenBitAddr {
+    // Special-purpose addresses. These are built directly rather than via
+    // `try_new`, since most of them sit in the reserved range.
+
+    /// General call, received by every device that implements it
+    pub const GENERAL_CALL: SevenBitAddr = SevenBitAddr(0x00);
+    /// SMBus host, the address devices use to notify the host
+    pub const SMBUS_HOST: SevenBitAddr = SevenBitAddr(0x08);
+    /// SMBus Alert Response Address, read to find which device asserted ALERT#
+    pub const ALERT_RESPONSE: SevenBitAddr = SevenBitAddr(0x0C);
+    /// SMBus Device Default Address, used by ARP
+    pub const ARP: SevenBitAddr = SevenBitAddr(0x61);
+    /// Device ID, used to read a device's manufacturer and part ID
+    pub const DEVICE_ID: SevenBitAddr = SevenBitAddr(0x7C);
+
+    /// Create new 7-bit address
+    pub const fn new(addr: u8) -> Self {
+        SevenBitAddr(addr)
+    }
+
+    /// Get the address value
+    pub fn get(self) -> u8 {
+        self.0
+    }
+
+    /// Validate 7-bit address range
+    pub fn try_new(addr: u8) -> Result<Self, InvalidAddress> {
+        Self::try_new_with_policy(addr, AddressPolicy::Strict)
+    }
+
+    /// Validate a 7-bit address under an explicit reserved-range policy
+    ///
+    /// [`try_new`](Self::try_new) is this with [`AddressPolicy::Strict`].
+    /// Addresses above 0x7F are rejected under either policy.
+    pub fn try_new_with_policy(addr: u8, policy: AddressPolicy) -> Result<Self, InvalidAddress> {
+        if addr > 0x7F {
+            Err(InvalidAddress::SevenBitRange(addr))
+        } else if policy == AddressPolicy::Strict && !(0x08..=0x77).contains(&addr) {
+            Err(InvalidAddress::Reserved(addr))
+        } else {
+            Ok(SevenBitAddr(addr))
+        }
+    }
+}
+
+/// How [`SevenBitAddr::try_new_with_policy`] treats the reserved ranges
+///
+/// 0x00-0x07 and 0x78-0x7F are set aside by the I2C and SMBus specs for
+/// general call, CBUS, high-speed mode, 10-bit addressing and the like,
+/// so an ordinary device configured there is almost certainly a typo.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressPolicy {
+    /// Reject reserved addresses; right for normal device addresses
+    Strict,
+    /// Accept reserved addresses, for code that deliberately targets
+    /// them. Gives up the typo check, so prefer the named constants such
+    /// as [`SevenBitAddr::GENERAL_CALL`] where one exists.
+    AllowReserved,
+}
+
+/// Address wrapper for 10-bit addressing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TenBitAddr(pub u16);
+
+impl TenBitAddr {
+    /// Create new 10-bit address
+    pub const fn new(addr: u16) -> Self {
+        TenBitAddr(addr)
+    }
+
+    /// Get the address value
+    pub fn get(self) -> u16 {
+        self.0
+    }
+
+    /// Validate 10-bit address range
+    pub fn try_new(addr: u16) -> Result<Self, InvalidAddress> {
+        if addr > 0x3FF {
+            Err(InvalidAddress::TenBitRange(addr))
+        } else {
+            Ok(TenBitAddr(addr))
+        }
+    }
+}
+
+/// Address validation errors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidAddress {
+    SevenBitRange(u8),  // Address > 0x7F
+    TenBitRange(u16),   // Address > 0x3FF
+    Reserved(u8),       // Address in reserved range
+    NotSevenBit(u16),   // 10-bit address too wide to narrow to 7 bits
+}
+
+impl core::fmt::Display for InvalidAddress {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            InvalidAddress::SevenBitRange(addr) => {
+                write!(f, "Address 0x{:02X} exceeds 7-bit range (0x00-0x7F)", addr)
+            }
+            InvalidAddress::TenBitRange(addr) => {
+                write!(f, "Address 0x{:03X} exceeds 10-bit range (0x000-0x3FF)", addr)
+            }
+            InvalidAddress::Reserved(addr) => {
+                write!(f, "Address 0x{:02X} is in reserved range", addr)
+            }
+            InvalidAddress::NotSevenBit(addr) => {
+                write!(f, "Address 0x{:03X} does not fit in 7 bits", addr)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for InvalidAddress {}
+
+/// Lets a single `Result<_, HubrisI2cError>` cover both address validation
+/// and bus operations
+///
+/// The error maps to `BadResponse` (and so `ErrorKind::Other`). The
+/// original `InvalidAddress`, offending address included, is kept in
+/// `detail` and reported as the error's `source`; the kind of validation
+/// failure is also in the operation name.
+impl From<InvalidAddress> for HubrisI2cError {
+    fn from(err: InvalidAddress) -> HubrisI2cError {
+        HubrisI2cError {
+            response_code: ResponseCode::BadResponse,
+            operation: match err {
+                InvalidAddress::SevenBitRange(_) => "invalid_address_7bit_range",
+                InvalidAddress::TenBitRange(_) => "invalid_address_10bit_range",
+                InvalidAddress::Reserved(_) => "invalid_address_reserved",
+                InvalidAddress::NotSevenBit(_) => "invalid_address_not_7bit",
+            },
+            bus: None,
+            detail: Some(ErrorDetail::InvalidAddress(err)),
+        }
+    }
+}
+
+// Address type conversions
+//
+// Raw integers convert through `TryFrom` so `byte.try_into()?` validates the
+// address. An infallible `From<u8>`/`From<u16>` cannot coexist with these
+// (core's blanket `TryFrom` impl would overlap), so unchecked construction
+// goes through `SevenBitAddr::new`/`TenBitAddr::new` instead.
+impl From<SevenBitAddr> for u8 {
+    fn from(addr: SevenBitAddr) -> u8 {
+        addr.0
+    }
+}
+
+impl TryFrom<u8> for SevenBitAddr {
+    type Error = InvalidAddress;
+
+    fn try_from(addr: u8) -> Result<SevenBitAddr, InvalidAddress> {
+        SevenBitAddr::try_new(addr)
+    }
+}
+
+impl From<TenBitAddr> for u16 {
+    fn from(addr: TenBitAddr) -> u16 {
+        addr.0
+    }
+}
+
+impl TryFrom<u16> for TenBitAddr {
+    type Error = InvalidAddress;
+
+    fn try_from(addr: u16) -> Result<TenBitAddr, InvalidAddress> {
+        TenBitAddr::try_new(addr)
+    }
+}
+
+// Between address widths. Widening zero-extends and is always valid;
+// narrowing only checks that the value fits; it doesn't apply the 7-bit
+// reserved-range rules, so a round trip gives back the original address.
+impl From<SevenBitAddr> for TenBitAddr {
+    fn from(addr: SevenBitAddr) -> TenBitAddr {
+        TenBitAddr(u16::from(addr.0))
+    }
+}
+
+impl TryFrom<TenBitAddr> for SevenBitAddr {
+    type Error = InvalidAddress;
+
+    fn try_from(addr: TenBitAddr) -> Result<SevenBitAddr, InvalidAddress> {
+        u8::try_from(addr.0)
+            .ok()
+            .filter(|&a| a <= 0x7F)
+            .map(SevenBitAddr)
+            .ok_or(InvalidAddress::NotSevenBit(addr.0))
+    }
+}
+
+// Embedded-HAL trait implementations
+impl<B: I2cBackend> ErrorType for HubrisI2c<B> {
+    type Error = HubrisI2cError;
+}
+
+/// Implementation for 7-bit addressing
+impl<B: I2cBackend> embedded_hal::i2c::I2c<SevenBitAddr> for HubrisI2c<B> {
+    fn read(&mut self, _address: SevenBitAddr, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        // Note: We ignore the address parameter since Hubris I2cDevice
+        // already contains the device address. This is a limitation of
+        // bridging between embedded-hal's per-operation addressing and
+        // Hubris's device-centric model.
+
+        self.request_read_into(&self.device, buffer)
+            .map(|_| ()) // Discard byte count
+            .map_err(|response_code| HubrisI2cError {
+                response_code,
+                operation: "read",
+                bus: self.name,
+                detail: None,
+            })
+    }
+
+    /// Write `bytes` to the device
+    ///
+    /// An empty `bytes` is well-defined: it issues an address-only write
+    /// (see [`HubrisI2c::write_address_only`]) and reports the address
+    /// ACK/NACK. Empty operations inside `transaction` are skipped instead;
+    /// see [`HubrisI2c::transaction_strict`].
+    fn write(&mut self, _address: SevenBitAddr, bytes: &[u8]) -> Result<(), Self::Error> {
+        if bytes.is_empty() {
+            return self.write_address_only();
+        }
+
+        self.request_write(&self.device, bytes)
+            .map_err(|response_code| HubrisI2cError {
+                response_code,
+                operation: "write",
+                bus: self.name,
+                detail: None,
+            })
+    }
+
+    fn write_read(
+        &mut self,
+        _address: SevenBitAddr,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        // A single byte write almost always selects a register; use the
+        // explicit methods when that guess is wrong for a device
+        if bytes.len() == 1 {
+            self.write_read_optimized(bytes[0], buffer)
+        } else {
+            self.write_read_split(bytes, buffer)
+        }
+    }
+
+    fn transaction(
+        &mut self,
+        address: SevenBitAddr,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        // Process operations sequentially
+        // Note: This doesn't provide true I2C transaction semantics
+        // (repeated START conditions) but is the best we can do with
+        // the current Hubris API
+
+        // Reject oversized batches before any of it reaches the device
+        self.check_transaction(operations)?;
+
+        // Empty operations have no useful meaning mid-transaction, and
+        // with separate server requests would each be a bus access of
+        // their own, so they are skipped
+        for operation in operations
+            .iter_mut()
+            .filter(|operation| !is_empty_operation(operation))
+        {
+            match operation {
+                Operation::Read(buffer) => {
+                    self.read(address, buffer).map_err(|mut err| {
+                        err.operation = "transaction_read";
+                        err
+                    })?;
+                }
+                Operation::Write(data) => {
+                    self.write(address, data).map_err(|mut err| {
+                        err.operation = "transaction_write";
+                        err
+                    })?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Implementation for 10-bit addressing
+///
+/// Every operation fails fast with `OperationNotSupported` unless the
+/// server advertises [`Capabilities::TEN_BIT`]. Each one addresses the
+/// header `11110XX` as a 7-bit device (`XX` being address bits 9..8) and
+/// sends the low address byte `A7..A0` first, so the wire carries the
+/// standard 10-bit sequences:
+///
+/// ```text
+/// write: S 11110XX0 A7..A0 data... P
+/// read:  S 11110XX0 A7..A0 Sr 11110XX1 data... P
+/// ```
+///
+/// `write_read` is [`HubrisI2c::write_read_10bit`] without a repeated
+/// START, and `transaction` issues each operation as its own read or
+/// write.
+impl<B: I2cBackend> embedded_hal::i2c::I2c<TenBitAddr> for HubrisI2c<B> {
+    fn read(&mut self, address: TenBitAddr, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        self.require(Capabilities::TEN_BIT, "10bit_addressing")?;
+
+        // The low address byte is the write half of a write-then-read, so
+        // the server's repeated START re-sends just the header with R/W set
+        let (header, low) = self.ten_bit_parts(address);
+        self.request_read_reg_into(&header, low, buffer)
+            .map(|_| ())
+            .map_err(|response_code| HubrisI2cError {
+                response_code,
+                operation: "10bit_read",
+                bus: self.name,
+                detail: None,
+            })
+    }
+
+    fn write(&mut self, address: TenBitAddr, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.require(Capabilities::TEN_BIT, "10bit_addressing")?;
+
+        // Reject oversized writes before staging anything
+        if bytes.len() > HubrisI2c::max_10bit_write_len() {
+            return Err(HubrisI2cError {
+                response_code: ResponseCode::TooMuchData,
+                operation: "10bit_write_too_long",
+                bus: self.name,
+                detail: Some(ErrorDetail::TooLong {
+                    limit: HubrisI2c::max_10bit_write_len(),
+                    attempted: bytes.len(),
+                }),
+            });
+        }
+
+        // The low address byte leads the data in the same write
+        let (header, low) = self.ten_bit_parts(address);
+        let mut write_data = [0u8; 1 + MAX_10BIT_WRITE_LEN];
+        let len = 1 + bytes.len();
+        write_data[0] = low;
+        write_data[1..len].copy_from_slice(bytes);
+
+        self.request_write(&header, &write_data[..len])
+            .map_err(|response_code| HubrisI2cError {
+                response_code,
+                operation: "10bit_write",
+                bus: self.name,
+                detail: None,
+            })
+    }
+
+    fn write_read(
+        &mut self,
+        address: TenBitAddr,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        self.write_read_10bit(address, bytes, buffer, false)
+    }
+
+    fn transaction(
+        &mut self,
+        address: TenBitAddr,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        // Checked here too, so an empty transaction fails the same way
+        self.require(Capabilities::TEN_BIT, "10bit_addressing")?;
+        self.check_transaction(operations)?;
+
+        // Similar to 7-bit but with 10-bit addressing, and empty operations
+        // are skipped the same way
+        for operation in operations
+            .iter_mut()
+            .filter(|operation| !is_empty_operation(operation))
+        {
+            match operation {
+                Operation::Read(buffer) => {
+                    self.read(address, buffer)?;
+                }
+                Operation::Write(data) => {
+                    self.write(address, data)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// [`HubrisI2c`] whose routing can never change, see [`HubrisI2c::sealed`]
+///
+/// There is no way back to a mutable `HubrisI2c` (so no `Layered` impl),
+/// and the segment mutators simply don't exist:
+///
+/// ```compile_fail
+/// use drv_i2c_generic::embedded_hal_wrapper::HubrisI2c;
+/// use drv_i2c_types::{Controller, PortIndex};
+/// use userlib::TaskId;
+/// # const I2C_SERVER_TASK: TaskId = TaskId::KERNEL;
+///
+/// let mut i2c = HubrisI2c::new_simple(I2C_SERVER_TASK, Controller::I2c1, PortIndex(0), 0x48)
+///     .sealed();
+/// i2c.set_segment(None);
+/// ```
+pub struct SealedI2c<B = IpcBackend> {
+    wrapper: HubrisI2c<B>,
+}
+
+impl<B: I2cBackend> SealedI2c<B> {
+    /// Reference to the sealed handle, for its read-only accessors and
+    /// optimized register operations
+    pub fn inner(&self) -> &HubrisI2c<B> {
+        &self.wrapper
+    }
+}
+
+impl<B: I2cBackend> ErrorType for SealedI2c<B> {
+    type Error = HubrisI2cError;
+}
+
+impl<B: I2cBackend> embedded_hal::i2c::I2c<SevenBitAddr> for SealedI2c<B> {
+    fn read(&mut self, address: SevenBitAddr, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        self.wrapper.read(address, buffer)
+    }
+
+    fn write(&mut self, address: SevenBitAddr, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.wrapper.write(address, bytes)
+    }
+
+    fn write_read(
+        &mut self,
+        address: SevenBitAddr,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        self.wrapper.write_read(address, bytes, buffer)
+    }
+
+    fn transaction(
+        &mut self,
+        address: SevenBitAddr,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.wrapper.transaction(address, operations)
+    }
+}
+
+/// Wrapper layer that can be peeled to reach the implementation it wraps
+///
+/// Every wrapper in this module except [`SealedI2c`] (which must not hand
+/// out mutable access to its routing) and [`SharedHubrisI2c`] (which only
+/// borrows its bus) implements it, so stacks like
+/// `TimedI2c<RetryingI2c<HubrisI2c>>` can be unwound one layer at a time,
+/// e.g. `stack.inner().inner().read_register(..)`.
+pub trait Layered {
+    /// The wrapped implementation
+    type Inner;
+
+    /// Reference to the wrapped implementation
+    fn inner(&self) -> &Self::Inner;
+
+    /// Mutable reference to the wrapped implementation
+    fn inner_mut(&mut self) -> &mut Self::Inner;
+}
+
+/// Optimized wrapper for register-heavy devices
+pub struct RegisterOptimizedI2c<B = IpcBackend> {
+    wrapper: HubrisI2c<B>,
+}
+
+impl<B: I2cBackend> RegisterOptimizedI2c<B> {
+    /// Create new register-optimized wrapper
+    pub fn new(wrapper: HubrisI2c<B>) -> Self {
+        Self { wrapper }
+    }
+
+    /// Direct register access using Hubris optimized calls
+    ///
+    /// Honors the byte order configured on the wrapped [`HubrisI2c`].
+    pub fn read_register<T>(&self, reg: u8) -> Result<T, HubrisI2cError>
+    where
+        T: zerocopy::FromBytes + zerocopy::IntoBytes,
+    {
+        self.wrapper.read_register(reg)
+    }
+
+    /// Multi-register struct read, see [`HubrisI2c::read_struct`]
+    pub fn read_struct<T: zerocopy::FromBytes>(&self, start_reg: u8) -> Result<T, HubrisI2cError> {
+        self.wrapper.read_struct(start_reg)
+    }
+
+    /// Read a 16-bit register as a single combined write-read
+    ///
+    /// The hot path for 16-bit sensors: one server call into a stack
+    /// buffer, with no generic transaction handling. The word is assembled
+    /// in the byte order configured on the wrapped [`HubrisI2c`], or host
+    /// order if none is set, matching [`read_register`](Self::read_register).
+    pub fn read_reg_word(&self, reg: u8) -> Result<u16, HubrisI2cError> {
+        let mut word = [0u8; 2];
+        self.wrapper
+            .request_read_reg_into(&self.wrapper.device, reg, &mut word)
+            .map_err(|response_code| HubrisI2cError {
+                response_code,
+                operation: "optimized_word_read",
+                bus: self.wrapper.name,
+                detail: None,
+            })?;
+
+        Ok(match self.wrapper.byte_order().unwrap_or(ByteOrder::HOST) {
+            ByteOrder::Big => u16::from_be_bytes(word),
+            ByteOrder::Little => u16::from_le_bytes(word),
+        })
+    }
+
+    /// Block read using Hubris SMBus support
+    pub fn read_block(&self, reg: u8, buffer: &mut [u8]) -> Result<usize, HubrisI2cError> {
+        self.wrapper.request_read_block(&self.wrapper.device, reg, buffer).map_err(
+            |response_code| HubrisI2cError {
+                response_code,
+                operation: "optimized_block_read",
+                bus: self.wrapper.name,
+                detail: None,
+            },
+        )
+    }
+}
+
+impl<B: I2cBackend> ErrorType for RegisterOptimizedI2c<B> {
+    type Error = HubrisI2cError;
+}
+
+impl<B> Layered for RegisterOptimizedI2c<B> {
+    type Inner = HubrisI2c<B>;
+
+    fn inner(&self) -> &HubrisI2c<B> {
+        &self.wrapper
+    }
+
+    fn inner_mut(&mut self) -> &mut HubrisI2c<B> {
+        &mut self.wrapper
+    }
+}
+
+impl<B: I2cBackend> embedded_hal::i2c::I2c<SevenBitAddr> for RegisterOptimizedI2c<B> {
+    fn read(&mut self, address: SevenBitAddr, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        self.wrapper.read(address, buffer)
+    }
+
+    fn write(&mut self, address: SevenBitAddr, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.wrapper.write(address, bytes)
+    }
+
+    fn write_read(
+        &mut self,
+        address: SevenBitAddr,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        // Always use optimized register read for single-byte writes
+        if bytes.len() == 1 {
+            self.wrapper
+                .request_read_reg_into(&self.wrapper.device, bytes[0], buffer)
+                .map(|_| ())
+                .map_err(|response_code| HubrisI2cError {
+                    response_code,
+                    operation: "optimized_write_read",
+                    bus: self.wrapper.name,
+                    detail: None,
+                })
+        } else {
+            self.wrapper.write_read(address, bytes, buffer)
+        }
+    }
+
+    fn transaction(
+        &mut self,
+        address: SevenBitAddr,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.wrapper.check_transaction(operations)?;
+
+        // Collapse every write(1 byte) + read(n bytes) pair into an
+        // optimized register read; the wrapped transaction issues its
+        // operations as separate server calls anyway, so this only saves
+        // requests. Everything else runs as-is, in order.
+        let mut index = 0;
+        while index < operations.len() {
+            let rest = &mut operations[index..];
+
+            if let [Operation::Write(write_data), Operation::Read(read_buffer), ..] = rest {
+                if write_data.len() == 1 && !read_buffer.is_empty() {
+                    self.wrapper
+                        .request_read_reg_into(&self.wrapper.device, write_data[0], read_buffer)
+                        .map_err(|response_code| HubrisI2cError {
+                            response_code,
+                            operation: "optimized_transaction",
+                            bus: self.wrapper.name,
+                            detail: None,
+                        })?;
+                    index += 2;
+                    continue;
+                }
+            }
+
+            match &mut rest[0] {
+                operation if is_empty_operation(operation) => {}
+                Operation::Read(buffer) => {
+                    self.wrapper.read(address, buffer).map_err(|mut err| {
+                        err.operation = "transaction_read";
+                        err
+                    })?;
+                }
+                Operation::Write(data) => {
+                    self.wrapper.write(address, data).map_err(|mut err| {
+                        err.operation = "transaction_write";
+                        err
+                    })?;
+                }
+            }
+            index += 1;
+        }
+
+        Ok(())
+    }
+}
+
+/// Per-`ErrorKind` retry limits for [`RetryingI2c`]
+///
+/// Lets pure contention (`ArbitrationLoss`) be retried generously while
+/// a genuinely broken bus gives up quickly instead of consuming the whole
+/// budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryBudget {
+    pub bus: u8,
+    pub arbitration_loss: u8,
+    pub no_acknowledge: u8,
+    pub overrun: u8,
+    pub other: u8,
+}
+
+impl RetryBudget {
+    /// Budget matching the original uniform policy
+    ///
+    /// Arbitration loss and `Other` errors are retried up to `max_retries`
+    /// times; all other kinds are not retried.
+    pub const fn new(max_retries: u8) -> Self {
+        Self {
+            bus: 0,
+            arbitration_loss: max_retries,
+            no_acknowledge: 0,
+            overrun: 0,
+            other: max_retries,
+        }
+    }
+
+    /// Slot used to track attempts per kind
+    fn slot(kind: ErrorKind) -> usize {
+        match kind {
+            ErrorKind::Bus => 0,
+            ErrorKind::ArbitrationLoss => 1,
+            ErrorKind::NoAcknowledge(_) => 2,
+            ErrorKind::Overrun => 3,
+            _ => 4,
+        }
+    }
+
+    /// Retry limit for the kind tracked in `slot`
+    fn limit(&self, slot: usize) -> u8 {
+        [
+            self.bus,
+            self.arbitration_loss,
+            self.no_acknowledge,
+            self.overrun,
+            self.other,
+        ][slot]
+    }
+}
+
+/// An operation as seen by [`RetryingI2c`]'s idempotency classifier
+#[derive(Debug)]
+pub enum RetryOp<'a, 'b> {
+    Read,
+    Write(&'a [u8]),
+    WriteRead(&'a [u8]),
+    Transaction(&'a [Operation<'b>]),
+}
+
+impl RetryOp<'_, '_> {
+    /// Whether the operation sends any data to the device
+    fn writes(&self) -> bool {
+        match self {
+            RetryOp::Read => false,
+            RetryOp::Write(_) | RetryOp::WriteRead(_) => true,
+            RetryOp::Transaction(operations) => operations
+                .iter()
+                .any(|operation| matches!(operation, Operation::Write(_))),
+        }
+    }
+}
+
+/// Error detail beyond `ErrorKind` that [`RetryingI2c`] needs
+///
+/// `ErrorKind` has no bus-reset kind, so the retry wrapper asks the error
+/// directly. The default suits error types that can't report a reset.
+pub trait RetryClass: embedded_hal::i2c::Error {
+    /// The bus was reset underneath the operation
+    fn is_bus_reset(&self) -> bool {
+        false
+    }
+}
+
+impl RetryClass for HubrisI2cError {
+    fn is_bus_reset(&self) -> bool {
+        HubrisI2cError::is_bus_reset(self)
+    }
+}
+
+/// Decides whether an operation is safe to issue twice
+pub type IdempotencyClassifier = fn(&RetryOp<'_, '_>) -> bool;
+
+/// Default [`IdempotencyClassifier`]: reads and register reads only
+///
+/// A register read is a one-byte pointer write followed by a read, either
+/// as a `write_read` or as a `[Write, Read]` transaction. Anything else
+/// that writes is assumed to have side effects.
+pub fn reads_are_idempotent(op: &RetryOp<'_, '_>) -> bool {
+    match op {
+        RetryOp::Read => true,
+        RetryOp::Write(_) => false,
+        RetryOp::WriteRead(bytes) => bytes.len() <= 1,
+        RetryOp::Transaction(operations) => match operations {
+            [Operation::Write(pointer), Operation::Read(_)] => pointer.len() <= 1,
+            _ => operations
+                .iter()
+                .all(|operation| matches!(operation, Operation::Read(_))),
+        },
+    }
+}
+
+/// Wrapper that automatically retries on temporary errors
+///
+/// Only idempotent operations are retried. A write that failed part way
+/// may already have reached the device, and a FIFO push or a command
+/// register would then act twice. By default only reads and register
+/// reads count as idempotent (see [`reads_are_idempotent`]);
+/// [`with_idempotency`](Self::with_idempotency) substitutes a
+/// device-specific classifier.
+///
+/// Whatever the classifier says, an operation that writes is never
+/// retried after a bus reset (see [`RetryClass`]): the reset may have cut
+/// it off at any byte, so even a configuration write can land partly
+/// applied, and it is left to the caller to re-establish device state.
+///
+/// Retries back off on the kernel timer unless
+/// [`with_delay`](Self::with_delay) substitutes another delay source.
+pub struct RetryingI2c<I2C, D: DelayProvider = HubrisDelay> {
+    inner: I2C,
+    max_retries: u8,
+    budget: RetryBudget,
+    idempotent: IdempotencyClassifier,
+    delay: D,
+}
+
+impl<I2C> RetryingI2c<I2C> {
+    /// Create new retrying wrapper
+    ///
+    /// # Arguments
+    ///
+    /// * `inner` - The underlying I2C implementation
+    /// * `max_retries` - Maximum number of retry attempts
+    pub fn new(inner: I2C, max_retries: u8) -> Self {
+        Self {
+            inner,
+            max_retries,
+            budget: RetryBudget::new(max_retries),
+            idempotent: reads_are_idempotent,
+            delay: HubrisDelay::new(),
+        }
+    }
+}
+
+impl<I2C, D: DelayProvider> RetryingI2c<I2C, D> {
+    /// Replace the source of the backoff delay between retries
+    pub fn with_delay<D2: DelayProvider>(self, delay: D2) -> RetryingI2c<I2C, D2> {
+        RetryingI2c {
+            inner: self.inner,
+            max_retries: self.max_retries,
+            budget: self.budget,
+            idempotent: self.idempotent,
+            delay,
+        }
+    }
+
+    /// Replace the per-kind retry limits
+    ///
+    /// `max_retries` still caps the total number of retries across all
+    /// kinds.
+    pub fn with_budget(mut self, budget: RetryBudget) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    /// Replace the classifier deciding which operations may be retried
+    ///
+    /// E.g. a device whose writes only ever set configuration registers
+    /// can mark all writes idempotent. Writes are still not retried after
+    /// a bus reset.
+    pub fn with_idempotency(mut self, classifier: IdempotencyClassifier) -> Self {
+        self.idempotent = classifier;
+        self
+    }
+
+    /// Execute operation with automatic retry on temporary errors
+    ///
+    /// Whether `op` may be retried at all, and whether it writes
+    fn classify(&self, op: &RetryOp<'_, '_>) -> (bool, bool) {
+        ((self.idempotent)(op), op.writes())
+    }
+
+    /// Operations not classified as `idempotent` are issued exactly once,
+    /// as are operations that `writes` once the bus has been reset.
+    fn retry_operation<F, R>(
+        &mut self,
+        idempotent: bool,
+        writes: bool,
+        mut operation: F,
+    ) -> Result<R, I2C::Error>
+    where
+        F: FnMut(&mut I2C) -> Result<R, I2C::Error>,
+        I2C::Error: RetryClass,
+        I2C: embedded_hal::i2c::I2c<SevenBitAddr>,
+    {
+        let mut attempts = [0u8; 5];
+        let mut retries = 0u8;
+
+        loop {
+            match operation(&mut self.inner) {
+                Ok(result) => return Ok(result),
+                Err(error) => {
+                    if !idempotent || (writes && error.is_bus_reset()) {
+                        return Err(error);
+                    }
+
+                    // Check if error is retryable for its kind
+                    let slot = RetryBudget::slot(error.kind());
+                    if retries >= self.max_retries || attempts[slot] >= self.budget.limit(slot) {
+                        return Err(error);
+                    }
+
+                    attempts[slot] += 1;
+                    retries += 1;
+
+                    // Wait before retry (linear backoff: 10 ms, 20 ms, ...)
+                    self.delay
+                        .delay(core::time::Duration::from_millis(10 * retries as u64));
+                }
+            }
+        }
+    }
+}
+
+impl<I2C, D: DelayProvider> ErrorType for RetryingI2c<I2C, D>
+where
+    I2C: ErrorType,
+{
+    type Error = I2C::Error;
+}
+
+impl<I2C, D: DelayProvider> Layered for RetryingI2c<I2C, D> {
+    type Inner = I2C;
+
+    fn inner(&self) -> &I2C {
+        &self.inner
+    }
+
+    fn inner_mut(&mut self) -> &mut I2C {
+        &mut self.inner
+    }
+}
+
+impl<I2C, D: DelayProvider> embedded_hal::i2c::I2c<SevenBitAddr> for RetryingI2c<I2C, D>
+where
+    I2C: embedded_hal::i2c::I2c<SevenBitAddr>,
+    I2C::Error: RetryClass,
+{
+    fn read(&mut self, address: SevenBitAddr, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        let (idempotent, writes) = self.classify(&RetryOp::Read);
+        self.retry_operation(idempotent, writes, |i2c| i2c.read(address, buffer))
+    }
+
+    fn write(&mut self, address: SevenBitAddr, bytes: &[u8]) -> Result<(), Self::Error> {
+        let (idempotent, writes) = self.classify(&RetryOp::Write(bytes));
+        self.retry_operation(idempotent, writes, |i2c| i2c.write(address, bytes))
+    }
+
+    fn write_read(
+        &mut self,
+        address: SevenBitAddr,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        let (idempotent, writes) = self.classify(&RetryOp::WriteRead(bytes));
+        self.retry_operation(idempotent, writes, |i2c| i2c.write_read(address, bytes, buffer))
+    }
+
+    fn transaction(
+        &mut self,
+        address: SevenBitAddr,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        let (idempotent, writes) = self.classify(&RetryOp::Transaction(operations));
+        self.retry_operation(idempotent, writes, |i2c| i2c.transaction(address, operations))
+    }
+}
+
+/// Wrapper that fails over between two controllers wired to the same device
+///
+/// Operations go to whichever controller last succeeded. On a bus error
+/// the operation is retried on the other controller, and that controller
+/// becomes the preferred one if it succeeds. Some errors are returned
+/// as-is, because the other controller can't fix them or replaying isn't
+/// safe:
+///
+/// - a NACK, meaning the device itself declined
+/// - `BadArg`, `TooMuchData` and `OperationNotSupported`, which reject
+///   the request rather than report a bus failure
+/// - a bus reset during an operation that writes, which may already have
+///   reached the device; see [`HubrisI2cError::is_bus_reset`]
+pub struct RedundantI2c<B = IpcBackend> {
+    controllers: [HubrisI2c<B>; 2],
+    active: usize,
+}
+
+impl<B: I2cBackend> RedundantI2c<B> {
+    /// Create new redundant wrapper, starting on `primary`
+    pub fn new(primary: HubrisI2c<B>, secondary: HubrisI2c<B>) -> Self {
+        Self {
+            controllers: [primary, secondary],
+            active: 0,
+        }
+    }
+
+    /// Check whether operations currently go to the primary controller
+    pub fn is_on_primary(&self) -> bool {
+        self.active == 0
+    }
+
+    /// Execute operation on the preferred controller, failing over on error
+    ///
+    /// `writes` marks operations that send data to the device, which are
+    /// not replayed after a bus reset.
+    fn failover<F>(&mut self, writes: bool, mut operation: F) -> Result<(), HubrisI2cError>
+    where
+        F: FnMut(&mut HubrisI2c<B>, SevenBitAddr) -> Result<(), HubrisI2cError>,
+    {
+        let address = self.controllers[self.active].address();
+
+        let error = match operation(&mut self.controllers[self.active], address) {
+            Ok(()) => return Ok(()),
+            Err(error) => error,
+        };
+
+        let nack = matches!(error.kind(), ErrorKind::NoAcknowledge(_));
+        let rejected = matches!(
+            error.response_code,
+            ResponseCode::BadArg | ResponseCode::TooMuchData | ResponseCode::OperationNotSupported
+        );
+        if nack || rejected || (writes && error.is_bus_reset()) {
+            return Err(error);
+        }
+
+        let standby = 1 - self.active;
+        operation(&mut self.controllers[standby], address)?;
+        self.active = standby;
+        Ok(())
+    }
+}
+
+impl<B: I2cBackend> ErrorType for RedundantI2c<B> {
+    type Error = HubrisI2cError;
+}
+
+impl<B: I2cBackend> embedded_hal::i2c::I2c<SevenBitAddr> for RedundantI2c<B> {
+    fn read(&mut self, _address: SevenBitAddr, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        self.failover(false, |i2c, address| i2c.read(address, buffer))
+    }
+
+    fn write(&mut self, _address: SevenBitAddr, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.failover(true, |i2c, address| i2c.write(address, bytes))
+    }
+
+    fn write_read(
+        &mut self,
+        _address: SevenBitAddr,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        self.failover(true, |i2c, address| i2c.write_read(address, bytes, buffer))
+    }
+
+    fn transaction(
+        &mut self,
+        _address: SevenBitAddr,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        let writes = operations
+            .iter()
+            .any(|operation| matches!(operation, Operation::Write(_)));
+        self.failover(writes, |i2c, address| i2c.transaction(address, operations))
+    }
+}
+
+/// Monotonic time source
+///
+/// Abstracted so deadline and latency logic can be driven by a fake clock
+/// in host tests.
+pub trait Clock {
+    /// Time elapsed since an arbitrary fixed origin (typically boot)
+    fn now(&self) -> core::time::Duration;
+}
+
+/// [`Clock`] backed by the Hubris kernel timer
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HubrisClock;
+
+impl Clock for HubrisClock {
+    fn now(&self) -> core::time::Duration {
+        core::time::Duration::from_millis(userlib::sys_get_timer().now)
+    }
+}
+
+/// Blocking delay source
+///
+/// Abstracted so timing-dependent helpers (and the mock's simulated bus
+/// latency) can be driven by a fake delay in host tests.
+pub trait DelayProvider {
+    /// Block for at least `duration`
+    fn delay(&mut self, duration: core::time::Duration);
+}
+
+/// [`DelayProvider`] that returns immediately
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoDelay;
+
+impl DelayProvider for NoDelay {
+    fn delay(&mut self, _duration: core::time::Duration) {}
+}
+
+/// [`DelayProvider`] backed by the Hubris kernel timer
+///
+/// The kernel sleeps in whole 1 ms ticks, and the tick a sleep starts in
+/// is already partly over, so a request is rounded up to whole
+/// milliseconds and then padded by one tick. Delays overshoot by up to
+/// 2 ms but never undershoot. Also implements
+/// `embedded_hal::delay::DelayNs`, so a driver taking `(I2C, Delay)` can
+/// be built from this module's types alone.
+///
+/// `S` performs the sleep; it is `userlib::sys::sleep_for` unless a
+/// host test substitutes its own with [`HubrisDelay::with_sleep`].
+#[derive(Debug, Clone, Copy)]
+pub struct HubrisDelay<S = fn(userlib::time::Duration)> {
+    sleep: S,
+}
+
+impl HubrisDelay {
+    /// Delay that sleeps on the kernel timer
+    pub const fn new() -> Self {
+        Self {
+            sleep: userlib::sys::sleep_for,
+        }
+    }
+}
+
+impl Default for HubrisDelay {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: FnMut(userlib::time::Duration)> HubrisDelay<S> {
+    /// Delay that hands its rounded sleeps to `sleep`
+    pub const fn with_sleep(sleep: S) -> Self {
+        Self { sleep }
+    }
+}
+
+impl<S: FnMut(userlib::time::Duration)> DelayProvider for HubrisDelay<S> {
+    fn delay(&mut self, duration: core::time::Duration) {
+        if duration.is_zero() {
+            return;
+        }
+
+        let mut ms = duration.as_millis() as u64;
+        if !duration.subsec_nanos().is_multiple_of(1_000_000) {
+            ms += 1;
+        }
+        // The current tick may be about to end
+        ms += 1;
+        (self.sleep)(userlib::time::Duration::from_millis(ms));
+    }
+}
+
+/// Lets drivers that take an `embedded_hal::delay::DelayNs` share the
+/// kernel timer with [`HubrisI2c`]
+///
+/// Same rounding as the [`DelayProvider`] impl, so nanosecond and
+/// microsecond requests cost at least one full tick.
+impl<S: FnMut(userlib::time::Duration)> embedded_hal::delay::DelayNs for HubrisDelay<S> {
+    fn delay_ns(&mut self, ns: u32) {
+        DelayProvider::delay(self, core::time::Duration::from_nanos(ns.into()));
+    }
+
+    fn delay_us(&mut self, us: u32) {
+        DelayProvider::delay(self, core::time::Duration::from_micros(us.into()));
+    }
+
+    fn delay_ms(&mut self, ms: u32) {
+        DelayProvider::delay(self, core::time::Duration::from_millis(ms.into()));
+    }
+}
+
+/// Maximum number of segments recorded by [`discover_topology`]
+pub const MAX_TOPOLOGY_SEGMENTS: usize = 64;
+
+/// Maximum number of addresses recorded per segment
+pub const MAX_SEGMENT_DEVICES: usize = 16;
+
+/// Segments a mux can have, in the order discovery walks them
+const MUX_SEGMENTS: [Segment; 8] = [
+    Segment::S1,
+    Segment::S2,
+    Segment::S3,
+    Segment::S4,
+    Segment::S5,
+    Segment::S6,
+    Segment::S7,
+    Segment::S8,
+];
+
+/// Devices found on a bus, keyed by segment (`None` is the root bus)
+#[derive(Debug, Default)]
+pub struct Topology {
+    pub segments: heapless::LinearMap<
+        Option<(Mux, Segment)>,
+        heapless::Vec<SevenBitAddr, MAX_SEGMENT_DEVICES>,
+        MAX_TOPOLOGY_SEGMENTS,
+    >,
+    /// Set when the deadline (or a capacity limit) cut discovery short
+    pub truncated: bool,
+}
+
+impl Topology {
+    /// Addresses found on `segment`, if it was scanned
+    pub fn devices(&self, segment: Option<(Mux, Segment)>) -> Option<&[SevenBitAddr]> {
+        self.segments.get(&segment).map(|found| found.as_slice())
+    }
+}
+
+/// Discover every device on a port within a time budget
+///
+/// Scans the root bus of `bus` and then, for each `(mux, segments)` in
+/// `muxes`, that mux's first `segments` segments (at most the eight a
+/// [`Segment`] can name), probing all non-reserved 7-bit addresses. The
+/// probes go through `bus`'s backend; its own segment is ignored. When
+/// `deadline` has elapsed (measured from the call) the walk stops and the
+/// partial result is returned with `truncated` set. Probe failures other
+/// than a NACK are treated as "not present" so one misbehaving segment
+/// can't abort boot.
+pub fn discover_topology<B: I2cBackend + Clone, C: Clock>(
+    bus: &BusParams<B>,
+    muxes: &[(Mux, usize)],
+    deadline: core::time::Duration,
+    clock: &C,
+) -> Topology {
+    let start = clock.now();
+    let mut topology = Topology::default();
+
+    let root = core::iter::once(None);
+    let segments = muxes.iter().flat_map(|&(mux, count)| {
+        MUX_SEGMENTS
+            .iter()
+            .take(count)
+            .map(move |&segment| Some((mux, segment)))
+    });
+
+    for segment in root.chain(segments) {
+        let mut found = heapless::Vec::new();
+        let on_segment = BusParams {
+            segment,
+            ..bus.clone()
+        };
+
+        for address in (0x08..=0x77).map(SevenBitAddr) {
+            if clock.now().saturating_sub(start) >= deadline {
+                topology.truncated = true;
+                // Keep whatever this segment yielded so far; a full map
+                // changes nothing since we're already reporting truncation
+                let _ = topology.segments.insert(segment, found);
+                return topology;
+            }
+
+            if let Ok(true) = on_segment.device(address).probe() {
+                if found.push(address).is_err() {
+                    topology.truncated = true;
+                    break;
+                }
+            }
+        }
+
+        if topology.segments.insert(segment, found).is_err() {
+            topology.truncated = true;
+            return topology;
+        }
+    }
+
+    topology
+}
+
+/// Check that no address appears twice in a table of devices on one bus
+///
+/// A `const fn`, so a static bus table can be checked at compile time:
+///
+/// ```
+/// use drv_i2c_generic::embedded_hal_wrapper::assert_unique_addresses;
+///
+/// const SENSORS: [u8; 3] = [0x48, 0x49, 0x4A];
+/// const _: () = assert!(assert_unique_addresses(&SENSORS), "duplicate I2C address");
+/// ```
+///
+/// A duplicate then fails the build instead of surfacing as garbage
+/// reads at bring-up:
+///
+/// ```compile_fail
+/// use drv_i2c_generic::embedded_hal_wrapper::assert_unique_addresses;
+///
+/// const SENSORS: [u8; 3] = [0x48, 0x49, 0x48];
+/// const _: () = assert!(assert_unique_addresses(&SENSORS), "duplicate I2C address");
+/// ```
+pub const fn assert_unique_addresses(addrs: &[u8]) -> bool {
+    let mut i = 0;
+    while i < addrs.len() {
+        let mut j = i + 1;
+        while j < addrs.len() {
+            if addrs[i] == addrs[j] {
+                return false;
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// Everything that identifies a bus, short of a device address
+///
+/// For helpers that talk to several devices sharing one controller, port
+/// and segment. Requests go through `backend`, as for [`HubrisI2c`].
+#[derive(Debug, Clone, Copy)]
+pub struct BusParams<B = IpcBackend> {
+    pub task: TaskId,
+    pub controller: Controller,
+    pub port: PortIndex,
+    pub segment: Option<(Mux, Segment)>,
+    pub backend: B,
+}
+
+impl BusParams {
+    /// Create bus parameters for the root segment of a port
+    pub fn new(task: TaskId, controller: Controller, port: PortIndex) -> Self {
+        Self {
+            task,
+            controller,
+            port,
+            segment: None,
+            backend: IpcBackend,
+        }
+    }
+}
+
+impl<B: I2cBackend + Clone> BusParams<B> {
+    /// Send requests for this bus's devices through `backend` instead
+    pub fn with_backend<B2: I2cBackend + Clone>(self, backend: B2) -> BusParams<B2> {
+        BusParams {
+            task: self.task,
+            controller: self.controller,
+            port: self.port,
+            segment: self.segment,
+            backend,
+        }
+    }
+
+    /// Wrapper for the device at `address` on this bus
+    pub fn device(&self, address: SevenBitAddr) -> HubrisI2c<B> {
+        HubrisI2c::new(self.task, self.controller, self.port, self.segment, address.0)
+            .with_backend(self.backend.clone())
+    }
+}
+
+/// Scan a bus and read an identification register from each responder
+///
+/// Probes every non-reserved 7-bit address on `bus` and, for each device
+/// that ACKs, reads `id_reg` (typically a WHO_AM_I register). Entries are
+/// written to `out` in address order and the number written is returned;
+/// the scan stops early once `out` is full. Devices that ACK addressing
+/// but fail the ID read, such as by NACKing the register, are recorded
+/// with `None`, and probe failures other than a NACK are treated as "not
+/// present", as in [`discover_topology`]. Both the probes and the ID
+/// reads go through `bus`'s backend.
+pub fn scan_and_identify<B: I2cBackend + Clone>(
+    bus: &BusParams<B>,
+    id_reg: u8,
+    out: &mut [(SevenBitAddr, Option<u8>)],
+) -> usize {
+    let mut count = 0;
+
+    for address in (0x08..=0x77).map(SevenBitAddr) {
+        if count == out.len() {
+            break;
+        }
+
+        let device = bus.device(address);
+        if let Ok(true) = device.probe() {
+            let id = device.read_register::<u8, u8>(id_reg).ok();
+            out[count] = (address, id);
+            count += 1;
+        }
+    }
+
+    count
+}
+
+/// Read the same 8-bit register from several devices on one bus
+///
+/// Each device is read on its own and its outcome stored in the matching
+/// slot of `out`, so one absent sensor doesn't hide the rest of a
+/// telemetry sweep. Fails up front with `BadArg` if `out` and `addrs`
+/// differ in length.
+pub fn read_reg_broadcast<B: I2cBackend + Clone>(
+    bus: &BusParams<B>,
+    addrs: &[SevenBitAddr],
+    reg: u8,
+    out: &mut [Result<u8, HubrisI2cError>],
+) -> Result<(), HubrisI2cError> {
+    if addrs.len() != out.len() {
+        return Err(HubrisI2cError {
+            response_code: ResponseCode::BadArg,
+            operation: "read_reg_broadcast_length",
+            bus: None,
+            detail: None,
+        });
+    }
+
+    for (&address, slot) in addrs.iter().zip(out.iter_mut()) {
+        *slot = bus
+            .device(address)
+            .read_register(reg)
+            .map_err(|err| err.with_operation("read_reg_broadcast"));
+    }
+
+    Ok(())
+}
+
+/// Latency statistics for one kind of operation
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LatencyStats {
+    pub count: u32,
+    pub min: core::time::Duration,
+    pub max: core::time::Duration,
+    pub last: core::time::Duration,
+}
+
+impl LatencyStats {
+    fn record(&mut self, elapsed: core::time::Duration) {
+        if self.count == 0 || elapsed < self.min {
+            self.min = elapsed;
+        }
+        if elapsed > self.max {
+            self.max = elapsed;
+        }
+        self.last = elapsed;
+        self.count = self.count.saturating_add(1);
+    }
+}
+
+/// Per-operation-kind latency statistics collected by [`TimedI2c`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Latencies {
+    pub read: LatencyStats,
+    pub write: LatencyStats,
+    pub write_read: LatencyStats,
+    pub transaction: LatencyStats,
+}
+
+/// Wrapper that measures the round-trip time of each operation
+///
+/// Useful for quantifying the IPC cost of the server, e.g. split
+/// `write_read` versus the optimized register path. Failed operations
+/// are timed too, since a slow NACK is still bus time.
+pub struct TimedI2c<I2C, C: Clock> {
+    inner: I2C,
+    clock: C,
+    latencies: Latencies,
+}
+
+impl<I2C, C: Clock> TimedI2c<I2C, C> {
+    /// Create new timing wrapper
+    pub fn new(inner: I2C, clock: C) -> Self {
+        Self {
+            inner,
+            clock,
+            latencies: Latencies::default(),
+        }
+    }
+
+    /// Latencies recorded so far
+    pub fn latencies(&self) -> &Latencies {
+        &self.latencies
+    }
+
+    /// Clear all recorded latencies
+    pub fn reset_latencies(&mut self) {
+        self.latencies = Latencies::default();
+    }
+
+    fn timed<F, R>(
+        &mut self,
+        select: fn(&mut Latencies) -> &mut LatencyStats,
+        operation: F,
+    ) -> R
+    where
+        F: FnOnce(&mut I2C) -> R,
+    {
+        let start = self.clock.now();
+        let result = operation(&mut self.inner);
+        let elapsed = self.clock.now().saturating_sub(start);
+        select(&mut self.latencies).record(elapsed);
+        result
+    }
+}
+
+impl<I2C, C> ErrorType for TimedI2c<I2C, C>
+where
+    I2C: ErrorType,
+    C: Clock,
+{
+    type Error = I2C::Error;
+}
+
+impl<I2C, C: Clock> Layered for TimedI2c<I2C, C> {
+    type Inner = I2C;
+
+    fn inner(&self) -> &I2C {
+        &self.inner
+    }
+
+    fn inner_mut(&mut self) -> &mut I2C {
+        &mut self.inner
+    }
+}
+
+impl<I2C, C> embedded_hal::i2c::I2c<SevenBitAddr> for TimedI2c<I2C, C>
+where
+    I2C: embedded_hal::i2c::I2c<SevenBitAddr>,
+    C: Clock,
+{
+    fn read(&mut self, address: SevenBitAddr, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        self.timed(|l| &mut l.read, |i2c| i2c.read(address, buffer))
+    }
+
+    fn write(&mut self, address: SevenBitAddr, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.timed(|l| &mut l.write, |i2c| i2c.write(address, bytes))
+    }
+
+    fn write_read(
+        &mut self,
+        address: SevenBitAddr,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        self.timed(|l| &mut l.write_read, |i2c| i2c.write_read(address, bytes, buffer))
+    }
+
+    fn transaction(
+        &mut self,
+        address: SevenBitAddr,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.timed(|l| &mut l.transaction, |i2c| i2c.transaction(address, operations))
+    }
+}
+
+/// Wrapper that converts errors into a driver's own error type
+///
+/// Lets a driver crate expose a single error type without hand-writing a
+/// `From<HubrisI2cError>`: `MappedI2c::new(i2c, MyError::Bus)`.
+pub struct MappedI2c<I2C, E, F> {
+    inner: I2C,
+    map: F,
+    _error: core::marker::PhantomData<E>,
+}
+
+impl<I2C, E, F> MappedI2c<I2C, E, F>
+where
+    I2C: ErrorType,
+    F: Fn(I2C::Error) -> E,
+{
+    /// Create new error-mapping wrapper
+    pub fn new(inner: I2C, map: F) -> Self {
+        Self {
+            inner,
+            map,
+            _error: core::marker::PhantomData,
+        }
+    }
+}
+
+impl<I2C, E, F> ErrorType for MappedI2c<I2C, E, F>
+where
+    I2C: ErrorType,
+    E: embedded_hal::i2c::Error,
+    F: Fn(I2C::Error) -> E,
+{
+    type Error = E;
+}
+
+impl<I2C, E, F> Layered for MappedI2c<I2C, E, F> {
+    type Inner = I2C;
+
+    fn inner(&self) -> &I2C {
+        &self.inner
+    }
+
+    fn inner_mut(&mut self) -> &mut I2C {
+        &mut self.inner
+    }
+}
+
+impl<I2C, E, F> embedded_hal::i2c::I2c<SevenBitAddr> for MappedI2c<I2C, E, F>
+where
+    I2C: embedded_hal::i2c::I2c<SevenBitAddr>,
+    E: embedded_hal::i2c::Error,
+    F: Fn(I2C::Error) -> E,
+{
+    fn read(&mut self, address: SevenBitAddr, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        self.inner.read(address, buffer).map_err(&self.map)
+    }
+
+    fn write(&mut self, address: SevenBitAddr, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.inner.write(address, bytes).map_err(&self.map)
+    }
+
+    fn write_read(
+        &mut self,
+        address: SevenBitAddr,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        self.inner
+            .write_read(address, bytes, buffer)
+            .map_err(&self.map)
+    }
+
+    fn transaction(
+        &mut self,
+        address: SevenBitAddr,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.inner
+            .transaction(address, operations)
+            .map_err(&self.map)
+    }
+}
+
+/// Operation and byte counts collected by [`CountingI2c`]
+///
+/// All counters saturate rather than wrap on long-running systems.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct I2cStats {
+    pub reads: u32,
+    pub writes: u32,
+    pub write_reads: u32,
+    pub transactions: u32,
+    pub errors: u32,
+    /// Payload bytes requested from devices
+    pub bytes_read: u64,
+    /// Payload bytes sent to devices
+    pub bytes_written: u64,
+}
+
+impl I2cStats {
+    fn add_read(&mut self, len: usize) {
+        self.bytes_read = self.bytes_read.saturating_add(len as u64);
+    }
+
+    fn add_written(&mut self, len: usize) {
+        self.bytes_written = self.bytes_written.saturating_add(len as u64);
+    }
+}
+
+/// Wrapper that counts operations and bytes transferred
+///
+/// Bytes are counted for every attempt, successful or not, since a failed
+/// operation still occupied the bus. Combined with [`TimedI2c`] latencies
+/// this gives effective throughput for capacity planning.
+pub struct CountingI2c<I2C> {
+    inner: I2C,
+    stats: I2cStats,
+}
+
+impl<I2C> CountingI2c<I2C> {
+    /// Create new counting wrapper
+    pub fn new(inner: I2C) -> Self {
+        Self {
+            inner,
+            stats: I2cStats::default(),
+        }
+    }
+
+    /// Counts collected so far
+    pub fn stats(&self) -> &I2cStats {
+        &self.stats
+    }
+
+    /// Clear all counts
+    pub fn reset_stats(&mut self) {
+        self.stats = I2cStats::default();
+    }
+
+    fn count_result<R, E>(&mut self, result: Result<R, E>) -> Result<R, E> {
+        if result.is_err() {
+            self.stats.errors = self.stats.errors.saturating_add(1);
+        }
+        result
+    }
+}
+
+impl<I2C> ErrorType for CountingI2c<I2C>
+where
+    I2C: ErrorType,
+{
+    type Error = I2C::Error;
+}
+
+impl<I2C> Layered for CountingI2c<I2C> {
+    type Inner = I2C;
+
+    fn inner(&self) -> &I2C {
+        &self.inner
+    }
+
+    fn inner_mut(&mut self) -> &mut I2C {
+        &mut self.inner
+    }
+}
+
+impl<I2C> embedded_hal::i2c::I2c<SevenBitAddr> for CountingI2c<I2C>
+where
+    I2C: embedded_hal::i2c::I2c<SevenBitAddr>,
+{
+    fn read(&mut self, address: SevenBitAddr, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        self.stats.reads = self.stats.reads.saturating_add(1);
+        self.stats.add_read(buffer.len());
+        let result = self.inner.read(address, buffer);
+        self.count_result(result)
+    }
+
+    fn write(&mut self, address: SevenBitAddr, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.stats.writes = self.stats.writes.saturating_add(1);
+        self.stats.add_written(bytes.len());
+        let result = self.inner.write(address, bytes);
+        self.count_result(result)
+    }
+
+    fn write_read(
+        &mut self,
+        address: SevenBitAddr,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        self.stats.write_reads = self.stats.write_reads.saturating_add(1);
+        self.stats.add_written(bytes.len());
+        self.stats.add_read(buffer.len());
+        let result = self.inner.write_read(address, bytes, buffer);
+        self.count_result(result)
+    }
+
+    fn transaction(
+        &mut self,
+        address: SevenBitAddr,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.stats.transactions = self.stats.transactions.saturating_add(1);
+        for operation in operations.iter() {
+            match operation {
+                Operation::Read(buffer) => self.stats.add_read(buffer.len()),
+                Operation::Write(data) => self.stats.add_written(data.len()),
+            }
+        }
+        let result = self.inner.transaction(address, operations);
+        self.count_result(result)
+    }
+}
+
+/// Kind of operation a [`DryRunI2c`] validated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DryRunKind {
+    Read,
+    Write,
+    WriteRead,
+    Transaction,
+}
+
+/// One operation a [`DryRunI2c`] would have issued
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DryRunOp {
+    pub kind: DryRunKind,
+    pub address: SevenBitAddr,
+    pub bytes_written: usize,
+    pub bytes_read: usize,
+}
+
+/// Validates operations without touching the bus
+///
+/// Each call runs the checks the real path would (address range, transfer
+/// size against [`MAX_TRANSFER_LEN`]) and, if they pass, records the
+/// operation and returns `Ok` without any server call. Useful for linting a
+/// configuration before deploying it. Read buffers are left untouched.
+///
+/// Up to `N` operations are recorded; later ones are still validated but
+/// only counted in [`dropped`](Self::dropped).
+pub struct DryRunI2c<const N: usize = 32> {
+    log: heapless::Vec<DryRunOp, N>,
+    dropped: usize,
+}
+
+impl<const N: usize> DryRunI2c<N> {
+    /// Create new dry-run bus with an empty log
+    pub fn new() -> Self {
+        Self {
+            log: heapless::Vec::new(),
+            dropped: 0,
+        }
+    }
+
+    /// Operations that passed validation, in order
+    pub fn log(&self) -> &[DryRunOp] {
+        &self.log
+    }
+
+    /// Operations that passed validation but didn't fit in the log
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    /// Clear the log
+    pub fn clear(&mut self) {
+        self.log.clear();
+        self.dropped = 0;
+    }
+
+    fn check(
+        &mut self,
+        kind: DryRunKind,
+        address: SevenBitAddr,
+        operations: &[Operation<'_>],
+    ) -> Result<(), HubrisI2cError> {
+        SevenBitAddr::try_new(address.0)?;
+        HubrisI2c::validate_transaction(operations)?;
+
+        let mut op = DryRunOp {
+            kind,
+            address,
+            bytes_written: 0,
+            bytes_read: 0,
+        };
+        for operation in operations {
+            match operation {
+                Operation::Read(buffer) => op.bytes_read += buffer.len(),
+                Operation::Write(data) => op.bytes_written += data.len(),
+            }
+        }
+
+        if self.log.push(op).is_err() {
+            self.dropped = self.dropped.saturating_add(1);
+        }
+        Ok(())
+    }
+}
+
+impl<const N: usize> Default for DryRunI2c<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> ErrorType for DryRunI2c<N> {
+    type Error = HubrisI2cError;
+}
+
+impl<const N: usize> embedded_hal::i2c::I2c<SevenBitAddr> for DryRunI2c<N> {
+    fn read(&mut self, address: SevenBitAddr, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        self.check(DryRunKind::Read, address, &[Operation::Read(buffer)])
+    }
+
+    fn write(&mut self, address: SevenBitAddr, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.check(DryRunKind::Write, address, &[Operation::Write(bytes)])
+    }
+
+    fn write_read(
+        &mut self,
+        address: SevenBitAddr,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        self.check(
+            DryRunKind::WriteRead,
+            address,
+            &[Operation::Write(bytes), Operation::Read(buffer)],
+        )
+    }
+
+    fn transaction(
+        &mut self,
+        address: SevenBitAddr,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.check(DryRunKind::Transaction, address, operations)
+    }
+}
+
+/// Switchable power to an I2C device
+///
+/// Implemented by whatever controls the rail: a GPIO, a load switch, a
+/// PMIC channel.
+pub trait PowerGate {
+    /// Turn the device's rail on
+    fn enable(&mut self);
+
+    /// Turn the device's rail off
+    fn disable(&mut self);
+}
+
+/// Wrapper that powers a gated device up around each operation
+///
+/// Every operation runs as enable, wait `settle`, operate, disable. The
+/// rail is disabled even when the operation fails, so an error can't leave
+/// a battery-powered device drawing current.
+pub struct GatedI2c<I2C, G: PowerGate, D: DelayProvider = HubrisDelay> {
+    inner: I2C,
+    gate: G,
+    settle: core::time::Duration,
+    delay: D,
+}
+
+impl<I2C, G: PowerGate> GatedI2c<I2C, G> {
+    /// Create new gated wrapper that waits on the kernel timer
+    pub fn new(inner: I2C, gate: G, settle: core::time::Duration) -> Self {
+        Self::with_delay(inner, gate, settle, HubrisDelay::new())
+    }
+}
+
+impl<I2C, G: PowerGate, D: DelayProvider> GatedI2c<I2C, G, D> {
+    /// Create new gated wrapper with a custom settle-delay source
+    pub fn with_delay(inner: I2C, gate: G, settle: core::time::Duration, delay: D) -> Self {
+        Self {
+            inner,
+            gate,
+            settle,
+            delay,
+        }
+    }
+
+    /// The power gate
+    pub fn gate(&self) -> &G {
+        &self.gate
+    }
+
+    fn gated<R>(&mut self, f: impl FnOnce(&mut I2C) -> R) -> R {
+        self.gate.enable();
+        self.delay.delay(self.settle);
+        let result = f(&mut self.inner);
+        self.gate.disable();
+        result
+    }
+}
+
+impl<I2C, G: PowerGate, D: DelayProvider> ErrorType for GatedI2c<I2C, G, D>
+where
+    I2C: ErrorType,
+{
+    type Error = I2C::Error;
+}
+
+impl<I2C, G: PowerGate, D: DelayProvider> Layered for GatedI2c<I2C, G, D> {
+    type Inner = I2C;
+
+    fn inner(&self) -> &I2C {
+        &self.inner
+    }
+
+    fn inner_mut(&mut self) -> &mut I2C {
+        &mut self.inner
+    }
+}
+
+impl<I2C, G: PowerGate, D: DelayProvider> embedded_hal::i2c::I2c<SevenBitAddr>
+    for GatedI2c<I2C, G, D>
+where
+    I2C: embedded_hal::i2c::I2c<SevenBitAddr>,
+{
+    fn read(&mut self, address: SevenBitAddr, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        self.gated(|inner| inner.read(address, buffer))
+    }
+
+    fn write(&mut self, address: SevenBitAddr, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.gated(|inner| inner.write(address, bytes))
+    }
+
+    fn write_read(
+        &mut self,
+        address: SevenBitAddr,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        self.gated(|inner| inner.write_read(address, bytes, buffer))
+    }
+
+    fn transaction(
+        &mut self,
+        address: SevenBitAddr,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.gated(|inner| inner.transaction(address, operations))
+    }
+}
+
+/// Board-independent identifier for a device, resolved to an address at runtime
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LogicalDevice(pub u8);
+
+/// Maps logical devices to the addresses they occupy on this board
+///
+/// Typically backed by strap pins read at boot, so one image can serve
+/// several board variants.
+pub trait AddressResolver {
+    /// Address of `logical` on this board
+    fn resolve(&self, logical: LogicalDevice) -> SevenBitAddr;
+}
+
+/// Wrapper that addresses devices by [`LogicalDevice`] instead of address
+///
+/// The address is resolved on every operation, so drivers never hold a
+/// physical address and the resolver's answer can depend on state read
+/// after construction.
+pub struct ResolvingI2c<I2C, R: AddressResolver> {
+    inner: I2C,
+    resolver: R,
+}
+
+impl<I2C, R: AddressResolver> ResolvingI2c<I2C, R> {
+    /// Create new resolving wrapper
+    pub fn new(inner: I2C, resolver: R) -> Self {
+        Self { inner, resolver }
+    }
+
+    /// The address resolver
+    pub fn resolver(&self) -> &R {
+        &self.resolver
+    }
+}
+
+impl<I2C, R> ResolvingI2c<I2C, R>
+where
+    I2C: embedded_hal::i2c::I2c<SevenBitAddr>,
+    R: AddressResolver,
+{
+    /// Read from `device` into `buffer`
+    pub fn read(&mut self, device: LogicalDevice, buffer: &mut [u8]) -> Result<(), I2C::Error> {
+        let address = self.resolver.resolve(device);
+        self.inner.read(address, buffer)
+    }
+
+    /// Write `bytes` to `device`
+    pub fn write(&mut self, device: LogicalDevice, bytes: &[u8]) -> Result<(), I2C::Error> {
+        let address = self.resolver.resolve(device);
+        self.inner.write(address, bytes)
+    }
+
+    /// Write `bytes` to `device`, then read into `buffer`
+    pub fn write_read(
+        &mut self,
+        device: LogicalDevice,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), I2C::Error> {
+        let address = self.resolver.resolve(device);
+        self.inner.write_read(address, bytes, buffer)
+    }
+
+    /// Run `operations` against `device`
+    pub fn transaction(
+        &mut self,
+        device: LogicalDevice,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), I2C::Error> {
+        let address = self.resolver.resolve(device);
+        self.inner.transaction(address, operations)
+    }
+}
+
+impl<I2C, R: AddressResolver> Layered for ResolvingI2c<I2C, R> {
+    type Inner = I2C;
+
+    fn inner(&self) -> &I2C {
+        &self.inner
+    }
+
+    fn inner_mut(&mut self) -> &mut I2C {
+        &mut self.inner
+    }
+}
+
+/// Owned, type-erased I2C bus for host tools
+///
+/// Lets a harness choose between a mock and a real bus at runtime while
+/// driving both through one type. embedded-hal only forwards `I2c`
+/// through `&mut T`. `I2c<SevenBitAddr>` itself could be implemented on
+/// `Box<dyn I2c<SevenBitAddr, Error = E>>`, since `SevenBitAddr` is ours,
+/// but its supertrait `ErrorType` has no such parameter: implementing a
+/// foreign trait on the foreign `Box` falls to the orphan rule, so the box
+/// is wrapped.
+#[cfg(feature = "std")]
+pub struct BoxedI2c<E> {
+    inner: std::boxed::Box<dyn embedded_hal::i2c::I2c<SevenBitAddr, Error = E>>,
+}
+
+#[cfg(feature = "std")]
+impl<E> BoxedI2c<E> {
+    /// Box `inner` behind the trait object
+    pub fn new<I2C>(inner: I2C) -> Self
+    where
+        I2C: embedded_hal::i2c::I2c<SevenBitAddr, Error = E> + 'static,
+    {
+        Self {
+            inner: std::boxed::Box::new(inner),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: embedded_hal::i2c::Error> ErrorType for BoxedI2c<E> {
+    type Error = E;
+}
+
+#[cfg(feature = "std")]
+impl<E: embedded_hal::i2c::Error> embedded_hal::i2c::I2c<SevenBitAddr> for BoxedI2c<E> {
+    fn read(&mut self, address: SevenBitAddr, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        self.inner.read(address, buffer)
+    }
+
+    fn write(&mut self, address: SevenBitAddr, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.inner.write(address, bytes)
+    }
+
+    fn write_read(
+        &mut self,
+        address: SevenBitAddr,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        self.inner.write_read(address, bytes, buffer)
+    }
+
+    fn transaction(
+        &mut self,
+        address: SevenBitAddr,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.inner.transaction(address, operations)
+    }
+}
+
+/// Bus where every operation succeeds and nothing is on the other end
+///
+/// Writes are discarded and reads return zeros. A stand-in for a bus a
+/// test doesn't care about, or a placeholder alongside real
+/// implementations behind a [`BoxedI2c`]; `E` is the error type it claims.
+pub struct NullI2c<E> {
+    _error: core::marker::PhantomData<E>,
+}
+
+impl<E> NullI2c<E> {
+    /// Create a bus that accepts everything
+    pub const fn new() -> Self {
+        Self {
+            _error: core::marker::PhantomData,
+        }
+    }
+}
+
+impl<E> Default for NullI2c<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E: embedded_hal::i2c::Error> ErrorType for NullI2c<E> {
+    type Error = E;
+}
+
+impl<E: embedded_hal::i2c::Error> embedded_hal::i2c::I2c<SevenBitAddr> for NullI2c<E> {
+    fn read(&mut self, _address: SevenBitAddr, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        buffer.fill(0);
+        Ok(())
+    }
+
+    fn write(&mut self, _address: SevenBitAddr, _bytes: &[u8]) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    fn write_read(
+        &mut self,
+        _address: SevenBitAddr,
+        _bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        buffer.fill(0);
+        Ok(())
+    }
+
+    fn transaction(
+        &mut self,
+        _address: SevenBitAddr,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        for operation in operations {
+            if let Operation::Read(buffer) = operation {
+                buffer.fill(0);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Wrapper that emits a `log` trace event for every operation
+///
+/// Each operation logs its direction, address and byte count under the
+/// `i2c` target once it completes, along with the outcome, so host tools
+/// and test harnesses that already consume the `log` facade see bus
+/// traffic without a custom sink. Only exists with the `log` feature;
+/// without it there is nothing to compile in.
+#[cfg(feature = "log")]
+pub struct LoggingI2c<I2C> {
+    inner: I2C,
+}
+
+#[cfg(feature = "log")]
+impl<I2C> LoggingI2c<I2C> {
+    /// Log every operation on `inner`
+    pub fn new(inner: I2C) -> Self {
+        Self { inner }
+    }
+}
+
+#[cfg(feature = "log")]
+impl<I2C: embedded_hal::i2c::I2c<SevenBitAddr>> LoggingI2c<I2C> {
+    fn logged(
+        direction: &'static str,
+        address: SevenBitAddr,
+        len: usize,
+        result: Result<(), I2C::Error>,
+    ) -> Result<(), I2C::Error> {
+        match &result {
+            Ok(()) => log::trace!(
+                target: "i2c",
+                "{} addr=0x{:02x} len={} ok",
+                direction,
+                address.0,
+                len
+            ),
+            Err(error) => log::trace!(
+                target: "i2c",
+                "{} addr=0x{:02x} len={} failed: {:?}",
+                direction,
+                address.0,
+                len,
+                error
+            ),
+        }
+        result
+    }
+}
+
+#[cfg(feature = "log")]
+impl<I2C: embedded_hal::i2c::I2c<SevenBitAddr>> ErrorType for LoggingI2c<I2C> {
+    type Error = I2C::Error;
+}
+
+#[cfg(feature = "log")]
+impl<I2C> Layered for LoggingI2c<I2C> {
+    type Inner = I2C;
+
+    fn inner(&self) -> &I2C {
+        &self.inner
+    }
+
+    fn inner_mut(&mut self) -> &mut I2C {
+        &mut self.inner
+    }
+}
+
+#[cfg(feature = "log")]
+impl<I2C: embedded_hal::i2c::I2c<SevenBitAddr>> embedded_hal::i2c::I2c<SevenBitAddr>
+    for LoggingI2c<I2C>
+{
+    fn read(&mut self, address: SevenBitAddr, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        let len = buffer.len();
+        Self::logged("read", address, len, self.inner.read(address, buffer))
+    }
+
+    fn write(&mut self, address: SevenBitAddr, bytes: &[u8]) -> Result<(), Self::Error> {
+        Self::logged("write", address, bytes.len(), self.inner.write(address, bytes))
+    }
+
+    fn write_read(
+        &mut self,
+        address: SevenBitAddr,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        let len = bytes.len() + buffer.len();
+        let result = self.inner.write_read(address, bytes, buffer);
+        Self::logged("write_read", address, len, result)
+    }
+
+    fn transaction(
+        &mut self,
+        address: SevenBitAddr,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        let len = operations
+            .iter()
+            .map(|operation| match operation {
+                Operation::Read(buffer) => buffer.len(),
+                Operation::Write(data) => data.len(),
+            })
+            .sum();
+        let result = self.inner.transaction(address, operations);
+        Self::logged("transaction", address, len, result)
+    }
+}
+
+/// Error from a [`FaultInjectingI2c`]: either injected or from the bus
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FaultError<E> {
+    /// Failure injected in place of the operation, which was never issued
+    Injected(ErrorKind),
+    /// Genuine error from the wrapped bus
+    Inner(E),
+}
+
+impl<E: embedded_hal::i2c::Error> embedded_hal::i2c::Error for FaultError<E> {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            FaultError::Injected(kind) => *kind,
+            FaultError::Inner(error) => error.kind(),
+        }
+    }
+}
+
+impl<E: RetryClass> RetryClass for FaultError<E> {
+    fn is_bus_reset(&self) -> bool {
+        match self {
+            FaultError::Injected(_) => false,
+            FaultError::Inner(error) => error.is_bus_reset(),
+        }
+    }
+}
+
+impl<E: core::fmt::Display> core::fmt::Display for FaultError<E> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            FaultError::Injected(kind) => write!(f, "injected I2C fault: {:?}", kind),
+            FaultError::Inner(error) => error.fmt(f),
+        }
+    }
+}
+
+/// Kinds an injected fault is drawn from
+const FAULT_KINDS: [ErrorKind; 7] = [
+    ErrorKind::Bus,
+    ErrorKind::ArbitrationLoss,
+    ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address),
+    ErrorKind::NoAcknowledge(NoAcknowledgeSource::Data),
+    ErrorKind::NoAcknowledge(NoAcknowledgeSource::Unknown),
+    ErrorKind::Overrun,
+    ErrorKind::Other,
+];
+
+/// Wrapper that fails a seeded, reproducible fraction of operations
+///
+/// Each operation draws from an xorshift64 generator seeded at
+/// construction; with probability `rate_per_mille / 1000` the operation
+/// is not issued and a [`FaultError::Injected`] of a random kind is
+/// returned instead. The sequence depends only on the seed and the number
+/// of operations, so a failing fuzz run replays exactly from its
+/// [`seed`](Self::seed); [`operation_index`](Self::operation_index)
+/// pins down where in the run a crash happened.
+pub struct FaultInjectingI2c<I2C> {
+    inner: I2C,
+    seed: u64,
+    state: u64,
+    rate_per_mille: u16,
+    operation_index: u64,
+}
+
+impl<I2C> FaultInjectingI2c<I2C> {
+    /// Create new fault-injecting wrapper
+    ///
+    /// A `rate_per_mille` of 1000 or more fails every operation.
+    pub fn new(inner: I2C, seed: u64, rate_per_mille: u16) -> Self {
+        Self {
+            inner,
+            seed,
+            // xorshift gets stuck at zero, so remap that one seed
+            state: if seed == 0 { 0x9E37_79B9_7F4A_7C15 } else { seed },
+            rate_per_mille,
+            operation_index: 0,
+        }
+    }
+
+    /// Seed this run was started with
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Number of operations seen so far, injected or not
+    pub fn operation_index(&self) -> u64 {
+        self.operation_index
+    }
+
+    fn next_random(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    /// Run `operation` unless this one is chosen to fail
+    fn inject<E>(
+        &mut self,
+        operation: impl FnOnce(&mut I2C) -> Result<(), E>,
+    ) -> Result<(), FaultError<E>> {
+        self.operation_index += 1;
+
+        let roll = self.next_random();
+        if roll % 1000 < u64::from(self.rate_per_mille) {
+            let kind = FAULT_KINDS[(self.next_random() % FAULT_KINDS.len() as u64) as usize];
+            return Err(FaultError::Injected(kind));
+        }
+
+        operation(&mut self.inner).map_err(FaultError::Inner)
+    }
+}
+
+impl<I2C> ErrorType for FaultInjectingI2c<I2C>
+where
+    I2C: ErrorType,
+{
+    type Error = FaultError<I2C::Error>;
+}
+
+impl<I2C> Layered for FaultInjectingI2c<I2C> {
+    type Inner = I2C;
+
+    fn inner(&self) -> &I2C {
+        &self.inner
+    }
+
+    fn inner_mut(&mut self) -> &mut I2C {
+        &mut self.inner
+    }
+}
+
+impl<I2C> embedded_hal::i2c::I2c<SevenBitAddr> for FaultInjectingI2c<I2C>
+where
+    I2C: embedded_hal::i2c::I2c<SevenBitAddr>,
+{
+    fn read(&mut self, address: SevenBitAddr, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        self.inject(|inner| inner.read(address, buffer))
+    }
+
+    fn write(&mut self, address: SevenBitAddr, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.inject(|inner| inner.write(address, bytes))
+    }
+
+    fn write_read(
+        &mut self,
+        address: SevenBitAddr,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        self.inject(|inner| inner.write_read(address, bytes, buffer))
+    }
+
+    fn transaction(
+        &mut self,
+        address: SevenBitAddr,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.inject(|inner| inner.transaction(address, operations))
+    }
+}
+
+/// PMBus PAGE command code
+pub const PMBUS_PAGE: u8 = 0x00;
+
+/// PMBus device with tracked PAGE state
+///
+/// PMBus commands act on whichever page (rail) was last written to the
+/// PAGE register. Issuing a command while on the wrong page is a classic
+/// bug, so page changes go through [`set_page`](Self::set_page) or the
+/// scoped [`with_page`](Self::with_page), which keep a cached copy of the
+/// current page. Other bus users changing the page behind this wrapper's
+/// back invalidate the cache; call [`forget_page`](Self::forget_page) if
+/// that may have happened.
+pub struct PmbusI2c<I2C> {
+    inner: I2C,
+    address: SevenBitAddr,
+    page: Option<u8>,
+}
+
+impl<I2C> PmbusI2c<I2C>
+where
+    I2C: embedded_hal::i2c::I2c<SevenBitAddr>,
+{
+    /// Create new PMBus wrapper for the device at `address`
+    ///
+    /// The current page is unknown until it is read or set.
+    pub fn new(inner: I2C, address: SevenBitAddr) -> Self {
+        Self {
+            inner,
+            address,
+            page: None,
+        }
+    }
+
+    /// Current page, reading the PAGE register if it isn't known yet
+    pub fn page(&mut self) -> Result<u8, I2C::Error> {
+        match self.page {
+            Some(page) => Ok(page),
+            None => {
+                let page = self.read_byte(PMBUS_PAGE)?;
+                self.page = Some(page);
+                Ok(page)
+            }
+        }
+    }
+
+    /// Select `page`, skipping the write if it is already selected
+    pub fn set_page(&mut self, page: u8) -> Result<(), I2C::Error> {
+        if self.page == Some(page) {
+            return Ok(());
+        }
+
+        // On failure the device may or may not have latched the new page
+        self.page = None;
+        self.write_byte(PMBUS_PAGE, page)?;
+        self.page = Some(page);
+        Ok(())
+    }
+
+    /// Drop the cached page so the next access re-reads it
+    pub fn forget_page(&mut self) {
+        self.page = None;
+    }
+
+    /// Run `f` with `page` selected, then restore the previous page
+    ///
+    /// The previous page is restored even if `f` changed it. If restoring
+    /// fails, that error is returned and `f`'s result is dropped.
+    pub fn with_page<R>(
+        &mut self,
+        page: u8,
+        f: impl FnOnce(&mut Self) -> R,
+    ) -> Result<R, I2C::Error> {
+        let previous = self.page()?;
+        self.set_page(page)?;
+        let result = f(self);
+        self.set_page(previous)?;
+        Ok(result)
+    }
+
+    /// SMBus Read Byte of `command` on the current page
+    pub fn read_byte(&mut self, command: u8) -> Result<u8, I2C::Error> {
+        let mut byte = [0u8; 1];
+        self.inner.write_read(self.address, &[command], &mut byte)?;
+        Ok(byte[0])
+    }
+
+    /// SMBus Write Byte of `command` on the current page
+    ///
+    /// Writing [`PMBUS_PAGE`] this way bypasses the page cache; use
+    /// [`set_page`](Self::set_page) instead.
+    pub fn write_byte(&mut self, command: u8, value: u8) -> Result<(), I2C::Error> {
+        self.inner.write(self.address, &[command, value])
+    }
+
+    /// SMBus Read Word (little-endian) of `command` on the current page
+    pub fn read_word(&mut self, command: u8) -> Result<u16, I2C::Error> {
+        let mut word = [0u8; 2];
+        self.inner.write_read(self.address, &[command], &mut word)?;
+        Ok(u16::from_le_bytes(word))
+    }
+
+    /// SMBus Write Word (little-endian) of `command` on the current page
+    pub fn write_word(&mut self, command: u8, value: u16) -> Result<(), I2C::Error> {
+        let [low, high] = value.to_le_bytes();
+        self.inner.write(self.address, &[command, low, high])
+    }
+}
+
+impl<I2C> Layered for PmbusI2c<I2C> {
+    type Inner = I2C;
+
+    fn inner(&self) -> &I2C {
+        &self.inner
+    }
+
+    fn inner_mut(&mut self) -> &mut I2C {
+        &mut self.inner
+    }
+}
+
+/// Builds a wrapper stack from the layers asked for
+///
+/// The `with_*` calls only record which layers to add, in any order and
+/// each at most once; [`build`](Self::build) nests them in the
+/// recommended order, innermost to outermost:
+///
+/// 1. Register optimization, which can only wrap a bare [`HubrisI2c`]
+/// 2. Counting, then timing, inside the retries so they see every bus
+///    attempt rather than only what the driver asked for
+/// 3. Retries, outermost
+///
+/// ```rust
+/// use drv_i2c_generic::embedded_hal_wrapper::{
+///     CountingI2c, HubrisI2c, I2cStackBuilder, RegisterOptimizedI2c, RetryingI2c,
+/// };
+/// use drv_i2c_types::{Controller, PortIndex};
+/// use userlib::TaskId;
+///
+/// let base = HubrisI2c::new_simple(TaskId::KERNEL, Controller::I2c1, PortIndex(0), 0x48);
+/// let i2c: RetryingI2c<CountingI2c<RegisterOptimizedI2c>> = I2cStackBuilder::new(base)
+///     .with_retries(3)
+///     .with_counting()
+///     .with_register_optimization()
+///     .build();
+/// ```
+///
+/// The type parameters after `I2C` record the chosen layers, `()` for a
+/// layer that was left out.
+pub struct I2cStackBuilder<I2C, O = (), C = (), T = (), R = ()> {
+    base: I2C,
+    optimization: O,
+    counting: C,
+    timing: T,
+    retries: R,
+}
+
+/// One layer recorded by an [`I2cStackBuilder`], or `()` for none
+pub trait StackLayer<I2C> {
+    /// `I2C` with this layer around it
+    type Output;
+
+    /// Wrap `inner` in this layer
+    fn wrap(self, inner: I2C) -> Self::Output;
+}
+
+impl<I2C> StackLayer<I2C> for () {
+    type Output = I2C;
+
+    fn wrap(self, inner: I2C) -> I2C {
+        inner
+    }
+}
+
+/// [`RegisterOptimizedI2c`] layer, see [`I2cStackBuilder::with_register_optimization`]
+#[derive(Debug, Clone, Copy)]
+pub struct RegisterOptimization;
+
+impl<B: I2cBackend> StackLayer<HubrisI2c<B>> for RegisterOptimization {
+    type Output = RegisterOptimizedI2c<B>;
+
+    fn wrap(self, inner: HubrisI2c<B>) -> RegisterOptimizedI2c<B> {
+        RegisterOptimizedI2c::new(inner)
+    }
+}
+
+/// [`CountingI2c`] layer, see [`I2cStackBuilder::with_counting`]
+#[derive(Debug, Clone, Copy)]
+pub struct Counting;
+
+impl<I2C> StackLayer<I2C> for Counting {
+    type Output = CountingI2c<I2C>;
+
+    fn wrap(self, inner: I2C) -> CountingI2c<I2C> {
+        CountingI2c::new(inner)
+    }
+}
+
+/// [`TimedI2c`] layer, see [`I2cStackBuilder::with_timing`]
+#[derive(Debug, Clone, Copy)]
+pub struct Timing<C>(C);
+
+impl<I2C, C: Clock> StackLayer<I2C> for Timing<C> {
+    type Output = TimedI2c<I2C, C>;
+
+    fn wrap(self, inner: I2C) -> TimedI2c<I2C, C> {
+        TimedI2c::new(inner, self.0)
+    }
+}
+
+/// [`RetryingI2c`] layer, see [`I2cStackBuilder::with_retries`]
+#[derive(Debug, Clone, Copy)]
+pub struct Retries(u8);
+
+impl<I2C> StackLayer<I2C> for Retries {
+    type Output = RetryingI2c<I2C>;
+
+    fn wrap(self, inner: I2C) -> RetryingI2c<I2C> {
+        RetryingI2c::new(inner, self.0)
+    }
+}
+
+impl<I2C> I2cStackBuilder<I2C> {
+    /// Start a stack on `base`, a real bus or a mock
+    pub fn new(base: I2C) -> Self {
+        Self {
+            base,
+            optimization: (),
+            counting: (),
+            timing: (),
+            retries: (),
+        }
+    }
+}
+
+impl<I2C, O, C, T> I2cStackBuilder<I2C, O, C, T, ()> {
+    /// Retry temporary errors, see [`RetryingI2c`]
+    pub fn with_retries(self, max_retries: u8) -> I2cStackBuilder<I2C, O, C, T, Retries> {
+        I2cStackBuilder {
+            base: self.base,
+            optimization: self.optimization,
+            counting: self.counting,
+            timing: self.timing,
+            retries: Retries(max_retries),
+        }
+    }
+}
+
+impl<I2C, O, T, R> I2cStackBuilder<I2C, O, (), T, R> {
+    /// Count operations and bytes, see [`CountingI2c`]
+    pub fn with_counting(self) -> I2cStackBuilder<I2C, O, Counting, T, R> {
+        I2cStackBuilder {
+            base: self.base,
+            optimization: self.optimization,
+            counting: Counting,
+            timing: self.timing,
+            retries: self.retries,
+        }
+    }
+}
+
+impl<I2C, O, C, R> I2cStackBuilder<I2C, O, C, (), R> {
+    /// Measure operation latency, see [`TimedI2c`]
+    pub fn with_timing<Clk: Clock>(
+        self,
+        clock: Clk,
+    ) -> I2cStackBuilder<I2C, O, C, Timing<Clk>, R> {
+        I2cStackBuilder {
+            base: self.base,
+            optimization: self.optimization,
+            counting: self.counting,
+            timing: Timing(clock),
+            retries: self.retries,
+        }
+    }
+}
+
+impl<B: I2cBackend, C, T, R> I2cStackBuilder<HubrisI2c<B>, (), C, T, R> {
+    /// Use the server's combined register operations, see
+    /// [`RegisterOptimizedI2c`]
+    pub fn with_register_optimization(
+        self,
+    ) -> I2cStackBuilder<HubrisI2c<B>, RegisterOptimization, C, T, R> {
+        I2cStackBuilder {
+            base: self.base,
+            optimization: RegisterOptimization,
+            counting: self.counting,
+            timing: self.timing,
+            retries: self.retries,
+        }
+    }
+}
+
+impl<I2C, O, C, T, R> I2cStackBuilder<I2C, O, C, T, R>
+where
+    O: StackLayer<I2C>,
+    C: StackLayer<O::Output>,
+    T: StackLayer<C::Output>,
+    R: StackLayer<T::Output>,
+{
+    /// Finish, returning the nested wrappers
+    pub fn build(self) -> R::Output {
+        let stack = self.optimization.wrap(self.base);
+        let stack = self.counting.wrap(stack);
+        let stack = self.timing.wrap(stack);
+        self.retries.wrap(stack)
+    }
+}
+
+/// Maximum number of steps in a [`RawTransaction`]
+pub const MAX_RAW_STEPS: usize = 8;
+
+/// One bus condition or data phase of a [`RawTransaction`]
+#[derive(Debug)]
+pub enum RawStep<'a> {
+    Start,
+    Write(&'a [u8]),
+    RepeatedStart,
+    Read(&'a mut [u8]),
+    Stop,
+}
+
+/// Transaction with explicit START, repeated START and STOP placement
+///
+/// An escape hatch for devices whose framing the [`Operation`] model
+/// can't express; most users should prefer `transaction`. Run it with
+/// [`HubrisI2c::raw_transaction`], which maps each START..STOP segment
+/// onto a server request. The server offers three shapes:
+///
+/// ```text
+/// S W P          write
+/// S R P          read
+/// S W Sr R P     register read (one-byte write)
+/// ```
+///
+/// A sequence that isn't well-formed I2C (data outside START..STOP,
+/// START while a transfer is open, no final STOP) fails with `BadArg`;
+/// a well-formed one the server has no request for fails with
+/// `OperationNotSupported`. Either way nothing is issued.
+#[derive(Debug, Default)]
+pub struct RawTransaction<'a> {
+    steps: heapless::Vec<RawStep<'a>, MAX_RAW_STEPS>,
+    overflowed: bool,
+}
+
+impl<'a> RawTransaction<'a> {
+    /// Create an empty transaction
+    pub fn new() -> Self {
+        Self {
+            steps: heapless::Vec::new(),
+            overflowed: false,
+        }
+    }
+
+    /// Append a START condition
+    pub fn start(self) -> Self {
+        self.push(RawStep::Start)
+    }
+
+    /// Append a write of `bytes`
+    pub fn write(self, bytes: &'a [u8]) -> Self {
+        self.push(RawStep::Write(bytes))
+    }
+
+    /// Append a repeated START condition
+    pub fn repeated_start(self) -> Self {
+        self.push(RawStep::RepeatedStart)
+    }
+
+    /// Append a read filling `buffer`
+    pub fn read(self, buffer: &'a mut [u8]) -> Self {
+        self.push(RawStep::Read(buffer))
+    }
+
+    /// Append a STOP condition
+    pub fn stop(self) -> Self {
+        self.push(RawStep::Stop)
+    }
+
+    fn push(mut self, step: RawStep<'a>) -> Self {
+        if self.steps.push(step).is_err() {
+            self.overflowed = true;
+        }
+        self
+    }
+
+    /// Map every segment to a server request, checking the whole sequence
+    fn plan(self) -> Result<heapless::Vec<RawRequest<'a>, MAX_RAW_STEPS>, ResponseCode> {
+        if self.overflowed {
+            return Err(ResponseCode::TooMuchData);
+        }
+
+        // Every request takes at least three steps, so pushes can't fail
+        let mut requests = heapless::Vec::new();
+        let mut state = RawState::Idle;
+
+        for step in self.steps {
+            state = match (state, step) {
+                (RawState::Idle, RawStep::Start) => RawState::Started,
+                (RawState::Idle, _) => return Err(ResponseCode::BadArg),
+                (_, RawStep::Start) => return Err(ResponseCode::BadArg),
+
+                (RawState::Started, RawStep::Write(bytes)) => RawState::Wrote(bytes),
+                (RawState::Started, RawStep::Read(buffer)) => RawState::Read(None, buffer),
+                (RawState::Wrote(bytes), RawStep::RepeatedStart) => RawState::Restarted(bytes),
+                (RawState::Restarted(bytes), RawStep::Read(buffer)) => {
+                    RawState::Read(Some(bytes), buffer)
+                }
+                // Changing direction needs a repeated START
+                (RawState::Wrote(_), RawStep::Read(_)) => return Err(ResponseCode::BadArg),
+
+                (RawState::Wrote(bytes), RawStep::Stop) => {
+                    let _ = requests.push(RawRequest::Write(bytes));
+                    RawState::Idle
+                }
+                (RawState::Read(None, buffer), RawStep::Stop) => {
+                    let _ = requests.push(RawRequest::Read(buffer));
+                    RawState::Idle
+                }
+                (RawState::Read(Some(&[reg]), buffer), RawStep::Stop) => {
+                    let _ = requests.push(RawRequest::ReadReg(reg, buffer));
+                    RawState::Idle
+                }
+
+                _ => return Err(ResponseCode::OperationNotSupported),
+            };
+        }
+
+        match state {
+            RawState::Idle => Ok(requests),
+            _ => Err(ResponseCode::BadArg),
+        }
+    }
+}
+
+/// Position within a [`RawTransaction`] segment while planning
+enum RawState<'a> {
+    Idle,
+    Started,
+    Wrote(&'a [u8]),
+    Restarted(&'a [u8]),
+    Read(Option<&'a [u8]>, &'a mut [u8]),
+}
+
+/// Server request a [`RawTransaction`] segment maps to
+enum RawRequest<'a> {
+    Write(&'a [u8]),
+    Read(&'a mut [u8]),
+    ReadReg(u8, &'a mut [u8]),
+}
+
+/// Error from a [`SharedHubrisI2c`]: either a reentrant use or from the bus
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SharedBusError<E> {
+    /// The bus was already borrowed by an operation still in progress,
+    /// nearly always a driver calling back into the bus from inside one
+    /// of its own operations. Retrying can't help.
+    Busy,
+    /// Genuine error from the shared bus
+    Inner(E),
+}
+
+impl<E: embedded_hal::i2c::Error> embedded_hal::i2c::Error for SharedBusError<E> {
+    fn kind(&self) -> ErrorKind {
+        match self {
+            SharedBusError::Busy => ErrorKind::Other,
+            SharedBusError::Inner(error) => error.kind(),
+        }
+    }
+}
+
+impl<E: core::fmt::Display> core::fmt::Display for SharedBusError<E> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            SharedBusError::Busy => write!(f, "I2C bus already in use (reentrant access)"),
+            SharedBusError::Inner(error) => error.fmt(f),
+        }
+    }
+}
+
+/// Proxy that lets several drivers share one bus through a `RefCell`
+///
+/// Each driver gets its own copy of the proxy; every operation borrows
+/// the bus for its duration. A plain `borrow_mut` would panic if a driver
+/// re-entered the bus from inside an operation, so the proxy uses
+/// `try_borrow_mut` and reports that case as [`SharedBusError::Busy`]
+/// instead. The proxy only borrows the bus, so unlike most wrappers here
+/// it doesn't implement [`Layered`]; reach the bus through the `RefCell`.
+pub struct SharedHubrisI2c<'a, I2C = HubrisI2c> {
+    bus: &'a core::cell::RefCell<I2C>,
+}
+
+impl<'a, I2C> SharedHubrisI2c<'a, I2C> {
+    /// Create a proxy for `bus`
+    pub fn new(bus: &'a core::cell::RefCell<I2C>) -> Self {
+        Self { bus }
+    }
+}
+
+impl<I2C> Clone for SharedHubrisI2c<'_, I2C> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<I2C> Copy for SharedHubrisI2c<'_, I2C> {}
+
+impl<I2C: embedded_hal::i2c::I2c<SevenBitAddr>> SharedHubrisI2c<'_, I2C> {
+    fn with_bus<R>(
+        &self,
+        f: impl FnOnce(&mut I2C) -> Result<R, I2C::Error>,
+    ) -> Result<R, SharedBusError<I2C::Error>> {
+        let mut bus = self
+            .bus
+            .try_borrow_mut()
+            .map_err(|_| SharedBusError::Busy)?;
+        f(&mut bus).map_err(SharedBusError::Inner)
+    }
+}
+
+impl<I2C: embedded_hal::i2c::I2c<SevenBitAddr>> ErrorType for SharedHubrisI2c<'_, I2C> {
+    type Error = SharedBusError<I2C::Error>;
+}
+
+impl<I2C: embedded_hal::i2c::I2c<SevenBitAddr>> embedded_hal::i2c::I2c<SevenBitAddr>
+    for SharedHubrisI2c<'_, I2C>
+{
+    fn read(&mut self, address: SevenBitAddr, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        self.with_bus(|bus| bus.read(address, buffer))
+    }
+
+    fn write(&mut self, address: SevenBitAddr, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.with_bus(|bus| bus.write(address, bytes))
+    }
+
+    fn write_read(
+        &mut self,
+        address: SevenBitAddr,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        self.with_bus(|bus| bus.write_read(address, bytes, buffer))
+    }
+
+    fn transaction(
+        &mut self,
+        address: SevenBitAddr,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.with_bus(|bus| bus.transaction(address, operations))
+    }
+}
+
+/// Wrapper that remembers which addresses recently failed to respond
+///
+/// In a polling loop over many optional devices, an absent device costs
+/// a bus transaction to rediscover every cycle. Once an operation fails
+/// with a device-not-found error, further operations to that address
+/// fail immediately with a cached `NoDevice` (operation
+/// `"presence_cached"`) until `ttl` has elapsed on `clock`, and are then
+/// let through again. A successful operation clears the entry. Up to `N`
+/// absent addresses are remembered; beyond that, newly absent devices
+/// simply aren't cached.
+pub struct CachingPresenceI2c<I2C, C: Clock, const N: usize = 16> {
+    inner: I2C,
+    clock: C,
+    ttl: core::time::Duration,
+    absent: heapless::LinearMap<u8, core::time::Duration, N>,
+}
+
+impl<I2C, C: Clock, const N: usize> CachingPresenceI2c<I2C, C, N> {
+    /// Cache absence of devices on `inner` for `ttl`
+    pub fn new(inner: I2C, clock: C, ttl: core::time::Duration) -> Self {
+        Self {
+            inner,
+            clock,
+            ttl,
+            absent: heapless::LinearMap::new(),
+        }
+    }
+
+    /// Forget that `address` was absent, so the next operation reaches the bus
+    pub fn invalidate(&mut self, address: SevenBitAddr) {
+        self.absent.remove(&address.0);
+    }
+
+    /// Forget every cached absence
+    pub fn clear(&mut self) {
+        self.absent.clear();
+    }
+
+    fn cached<F>(&mut self, address: SevenBitAddr, operation: F) -> Result<(), HubrisI2cError>
+    where
+        F: FnOnce(&mut I2C) -> Result<(), HubrisI2cError>,
+    {
+        let now = self.clock.now();
+        if let Some(&since) = self.absent.get(&address.0) {
+            if now.saturating_sub(since) < self.ttl {
+                return Err(HubrisI2cError {
+                    response_code: ResponseCode::NoDevice,
+                    operation: "presence_cached",
+                    bus: None,
+                    detail: None,
+                });
+            }
+            self.absent.remove(&address.0);
+        }
+
+        let result = operation(&mut self.inner);
+        match &result {
+            Ok(()) => {
+                self.absent.remove(&address.0);
+            }
+            Err(err) if err.is_device_not_found() => {
+                // A full cache just means this device gets probed again
+                let _ = self.absent.insert(address.0, now);
+            }
+            Err(_) => {}
+        }
+        result
+    }
+}
+
+impl<I2C, C: Clock, const N: usize> ErrorType for CachingPresenceI2c<I2C, C, N> {
+    type Error = HubrisI2cError;
+}
+
+impl<I2C, C: Clock, const N: usize> Layered for CachingPresenceI2c<I2C, C, N> {
+    type Inner = I2C;
+
+    fn inner(&self) -> &I2C {
+        &self.inner
+    }
+
+    fn inner_mut(&mut self) -> &mut I2C {
+        &mut self.inner
+    }
+}
+
+impl<I2C, C, const N: usize> embedded_hal::i2c::I2c<SevenBitAddr> for CachingPresenceI2c<I2C, C, N>
+where
+    I2C: embedded_hal::i2c::I2c<SevenBitAddr, Error = HubrisI2cError>,
+    C: Clock,
+{
+    fn read(&mut self, address: SevenBitAddr, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        self.cached(address, |i2c| i2c.read(address, buffer))
+    }
+
+    fn write(&mut self, address: SevenBitAddr, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.cached(address, |i2c| i2c.write(address, bytes))
+    }
+
+    fn write_read(
+        &mut self,
+        address: SevenBitAddr,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        self.cached(address, |i2c| i2c.write_read(address, bytes, buffer))
+    }
+
+    fn transaction(
+        &mut self,
+        address: SevenBitAddr,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.cached(address, |i2c| i2c.transaction(address, operations))
+    }
+}
+
+/// Implementations that can pass a scheduling priority hint to the
+/// server, see [`SchedulingI2c`]
+pub trait PriorityHint {
+    /// Attach `priority` to subsequent requests, or stop with `None`
+    fn set_priority_hint(&mut self, priority: Option<u8>);
+}
+
+/// Requests are issued through [`I2cBackend::with_priority`] while a hint
+/// is set
+impl<B: I2cBackend> PriorityHint for HubrisI2c<B> {
+    fn set_priority_hint(&mut self, priority: Option<u8>) {
+        self.priority = priority;
+    }
+}
+
+/// Wrapper that tags its operations with a scheduling priority hint
+///
+/// Meant for mixed-criticality buses, where a latency-sensitive sensor
+/// read should get ahead of a bulk EEPROM write. Every request an
+/// operation makes is issued through [`I2cBackend::with_priority`], so
+/// `SchedulingI2c` wraps the [`HubrisI2c`] directly and other layers go
+/// outside it. A FIFO-only server ignores the hint; that includes the
+/// stock server, since `drv-i2c-api` requests carry no priority. On
+/// Hubris the kernel already delivers waiting requests to the server in
+/// order of the *sending task's* priority, so until the server grows
+/// prioritized requests the effective control is which task issues the
+/// latency-sensitive reads.
+pub struct SchedulingI2c<I2C> {
+    inner: I2C,
+    priority: u8,
+}
+
+impl<I2C> SchedulingI2c<I2C> {
+    /// Tag operations on `inner` with `priority` (higher is more urgent)
+    pub fn new(inner: I2C, priority: u8) -> Self {
+        Self { inner, priority }
+    }
+
+    /// Priority hint attached to operations
+    pub fn priority(&self) -> u8 {
+        self.priority
+    }
+
+    /// Change the priority hint for subsequent operations
+    pub fn set_priority(&mut self, priority: u8) {
+        self.priority = priority;
+    }
+
+    /// Run `f` with the priority hint temporarily set to `priority`
+    pub fn with_priority<R>(&mut self, priority: u8, f: impl FnOnce(&mut Self) -> R) -> R {
+        let previous = core::mem::replace(&mut self.priority, priority);
+        let result = f(self);
+        self.priority = previous;
+        result
+    }
+}
+
+impl<I2C: PriorityHint> SchedulingI2c<I2C> {
+    /// Run `f` on the inner implementation with the hint attached, so it
+    /// never leaks into calls made on the inner handle directly
+    fn prioritized<R>(&mut self, f: impl FnOnce(&mut I2C) -> R) -> R {
+        self.inner.set_priority_hint(Some(self.priority));
+        let result = f(&mut self.inner);
+        self.inner.set_priority_hint(None);
+        result
+    }
+}
+
+impl<I2C: ErrorType> ErrorType for SchedulingI2c<I2C> {
+    type Error = I2C::Error;
+}
+
+impl<I2C> Layered for SchedulingI2c<I2C> {
+    type Inner = I2C;
+
+    fn inner(&self) -> &I2C {
+        &self.inner
+    }
+
+    fn inner_mut(&mut self) -> &mut I2C {
+        &mut self.inner
+    }
+}
+
+impl<I2C> embedded_hal::i2c::I2c<SevenBitAddr> for SchedulingI2c<I2C>
+where
+    I2C: embedded_hal::i2c::I2c<SevenBitAddr> + PriorityHint,
+{
+    fn read(&mut self, address: SevenBitAddr, buffer: &mut [u8]) -> Result<(), Self::Error> {
+        self.prioritized(|i2c| i2c.read(address, buffer))
+    }
+
+    fn write(&mut self, address: SevenBitAddr, bytes: &[u8]) -> Result<(), Self::Error> {
+        self.prioritized(|i2c| i2c.write(address, bytes))
+    }
+
+    fn write_read(
+        &mut self,
+        address: SevenBitAddr,
+        bytes: &[u8],
+        buffer: &mut [u8],
+    ) -> Result<(), Self::Error> {
+        self.prioritized(|i2c| i2c.write_read(address, bytes, buffer))
+    }
+
+    fn transaction(
+        &mut self,
+        address: SevenBitAddr,
+        operations: &mut [Operation<'_>],
+    ) -> Result<(), Self::Error> {
+        self.prioritized(|i2c| i2c.transaction(address, operations))
+    }
+}
+
+/// One operation of a captured bus trace, see [`replay_trace`]
+///
+/// Reads carry only their length: a trace is replayed to reproduce the
+/// traffic, not to stand in for the devices' answers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordedOp<'a> {
+    /// A plain write
+    Write {
+        /// Device the write went to
+        address: SevenBitAddr,
+        /// Bytes written, register first where there is one
+        bytes: &'a [u8],
+    },
+    /// A plain read
+    Read {
+        /// Device the read came from
+        address: SevenBitAddr,
+        /// Bytes read
+        len: usize,
+    },
+    /// A write followed by a read, as issued by `I2c::write_read`
+    WriteRead {
+        /// Device addressed by both halves
+        address: SevenBitAddr,
+        /// Bytes written before the read, usually the register
+        bytes: &'a [u8],
+        /// Bytes read
+        len: usize,
+    },
+}
+
+/// Re-issue each operation of `trace` against `bus`, in order
+///
+/// For reproducing a field issue on a bench unit from a captured
+/// sequence of operations. Reads are executed again against the real
+/// device and their data discarded; nothing from the capture is fed back
+/// as a response. Stops at the first failure, whose `failed_index` is its
+/// position in `trace`. A read longer than [`MAX_TRANSFER_LEN`] fails
+/// with `TooMuchData` without being issued.
+pub fn replay_trace(
+    bus: &mut impl embedded_hal::i2c::I2c<SevenBitAddr, Error = HubrisI2cError>,
+    trace: &[RecordedOp<'_>],
+) -> Result<(), TransactionError> {
+    let mut scratch = [0u8; MAX_TRANSFER_LEN];
+
+    for (failed_index, op) in trace.iter().enumerate() {
+        let read_len = match *op {
+            RecordedOp::Write { .. } => 0,
+            RecordedOp::Read { len, .. } | RecordedOp::WriteRead { len, .. } => len,
+        };
+        if read_len > MAX_TRANSFER_LEN {
+            return Err(TransactionError {
+                failed_index,
+                source: HubrisI2cError {
+                    response_code: ResponseCode::TooMuchData,
+                    operation: "replay_trace_length",
+                    bus: None,
+                    detail: None,
+                },
+            });
+        }
+
+        let buffer = &mut scratch[..read_len];
+        let result = match *op {
+            RecordedOp::Write { address, bytes } => bus.write(address, bytes),
+            RecordedOp::Read { address, .. } => bus.read(address, buffer),
+            RecordedOp::WriteRead { address, bytes, .. } => bus.write_read(address, bytes, buffer),
+        };
+        result.map_err(|source| TransactionError {
+            failed_index,
+            source,
+        })?;
+    }
+
+    Ok(())
+}
+
+/// CRC-8 lookup table for SMBus PEC (polynomial x^8 + x^2 + x + 1)
+const PEC_TABLE: [u8; 256] = pec_table();
+
+const fn pec_table() -> [u8; 256] {
+    let mut table = [0u8; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u8;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 0x80 != 0 { (crc << 1) ^ 0x07 } else { crc << 1 };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+}
+
+/// Compute the SMBus PEC (CRC-8) over `bytes`
+///
+/// The caller supplies the full framing: per spec the PEC covers every
+/// byte on the wire, including the address byte(s) with their R/W bit.
+pub fn smbus_pec(bytes: &[u8]) -> u8 {
+    let mut pec = PecAccumulator::new();
+    pec.update(bytes);
+    pec.finish()
+}
+
+/// Incremental SMBus PEC computation for streamed messages
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PecAccumulator {
+    crc: u8,
+}
+
+impl PecAccumulator {
+    /// Start a new PEC computation
+    pub const fn new() -> Self {
+        Self { crc: 0 }
+    }
+
+    /// Feed more message bytes
+    pub fn update(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.crc = PEC_TABLE[(self.crc ^ byte) as usize];
+        }
+    }
+
+    /// PEC over all bytes fed so far
+    pub fn finish(&self) -> u8 {
+        self.crc
+    }
+}
+
+/// Which payload bytes a device's checksum byte covers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrcCoverage {
+    /// One CRC byte follows every `n`-byte word
+    PerWord(usize),
+    /// One CRC byte follows the whole payload
+    PerBlock,
+}
+
+/// CRC-8 parameters for devices that checksum their own payloads
+///
+/// Unrelated to SMBus PEC: these checksums cover data bytes only (no
+/// address framing) and are defined by the device, not the bus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CrcSpec {
+    pub polynomial: u8,
+    pub init: u8,
+    pub coverage: CrcCoverage,
+}
+
+impl CrcSpec {
+    /// Sensirion CRC-8: polynomial 0x31, init 0xFF, one CRC per 16-bit word
+    pub const SENSIRION: CrcSpec = CrcSpec {
+        polynomial: 0x31,
+        init: 0xFF,
+        coverage: CrcCoverage::PerWord(2),
+    };
+
+    /// CRC-8 of `bytes` under this spec (MSB first, no final XOR)
+    pub fn checksum(&self, bytes: &[u8]) -> u8 {
+        let mut crc = self.init;
+        for &byte in bytes {
+            crc ^= byte;
+            for _ in 0..8 {
+                crc = if crc & 0x80 != 0 {
+                    (crc << 1) ^ self.polynomial
+                } else {
+                    crc << 1
+                };
+            }
+        }
+        crc
+    }
+
+    /// Payload bytes covered by each CRC byte, for a `len`-byte payload
+    ///
+    /// `None` if the payload is empty or can't be split evenly into words.
+    fn chunk_len(&self, len: usize) -> Option<usize> {
+        match self.coverage {
+            _ if len == 0 => None,
+            CrcCoverage::PerWord(word) if word > 0 && len.is_multiple_of(word) => Some(word),
+            CrcCoverage::PerWord(_) => None,
+            CrcCoverage::PerBlock => Some(len),
+        }
+    }
+}
+
+pub mod smbus_arp {
+    //! SMBus Address Resolution Protocol (ARP) commands
+    //!
+    //! ARP lets a host discover hot-pluggable devices by their 128-bit UDID
+    //! and assign each a free address. All commands are sent to the SMBus
+    //! Device Default Address (0x61).
+    //!
+    //! The ARP spec requires PEC on these commands; the stock server doesn't
+    //! append or check it, so devices that enforce PEC will reject them.
+
+    use super::*;
+
+    /// SMBus Device Default Address used for all ARP commands
+    pub const ARP_ADDRESS: u8 = SevenBitAddr::ARP.0;
+
+    const PREPARE_TO_ARP: u8 = 0x01;
+    const GET_UDID: u8 = 0x03;
+    const ASSIGN_ADDRESS: u8 = 0x04;
+
+    /// Length of a Get UDID response: 16 UDID bytes plus the address byte
+    const UDID_RESPONSE_LEN: usize = 17;
+
+    /// 128-bit SMBus Unique Device Identifier, most significant byte first
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct Udid(pub [u8; 16]);
+
+    impl Udid {
+        /// Decode a UDID from the first 16 bytes of a Get UDID response
+        pub fn decode(bytes: &[u8]) -> Option<Self> {
+            let mut udid = [0u8; 16];
+            udid.copy_from_slice(bytes.get(..16)?);
+            Some(Udid(udid))
+        }
+
+        /// Device capabilities (address type and PEC support)
+        pub fn device_capabilities(&self) -> u8 {
+            self.0[0]
+        }
+
+        /// UDID version and silicon revision
+        pub fn version_revision(&self) -> u8 {
+            self.0[1]
+        }
+
+        pub fn vendor_id(&self) -> u16 {
+            u16::from_be_bytes([self.0[2], self.0[3]])
+        }
+
+        pub fn device_id(&self) -> u16 {
+            u16::from_be_bytes([self.0[4], self.0[5]])
+        }
+
+        /// Supported SMBus interface version and protocols
+        pub fn interface(&self) -> u16 {
+            u16::from_be_bytes([self.0[6], self.0[7]])
+        }
+
+        pub fn subsystem_vendor_id(&self) -> u16 {
+            u16::from_be_bytes([self.0[8], self.0[9]])
+        }
+
+        pub fn subsystem_device_id(&self) -> u16 {
+            u16::from_be_bytes([self.0[10], self.0[11]])
+        }
+
+        pub fn vendor_specific_id(&self) -> u32 {
+            u32::from_be_bytes([self.0[12], self.0[13], self.0[14], self.0[15]])
+        }
+    }
+
+    /// ARP host bound to the Device Default Address on one bus
+    pub struct SmbusArp<B = IpcBackend> {
+        i2c: HubrisI2c<B>,
+    }
+
+    impl SmbusArp {
+        /// Create ARP host for the given bus
+        pub fn new(
+            i2c_server_task: TaskId,
+            controller: Controller,
+            port: PortIndex,
+            segment: Option<(Mux, Segment)>,
+        ) -> Self {
+            Self {
+                i2c: HubrisI2c::new(i2c_server_task, controller, port, segment, ARP_ADDRESS),
+            }
+        }
+    }
+
+    impl<B: I2cBackend> SmbusArp<B> {
+        /// Send ARP commands through `backend` instead, see
+        /// [`HubrisI2c::with_backend`]
+        pub fn with_backend<B2: I2cBackend>(self, backend: B2) -> SmbusArp<B2> {
+            SmbusArp {
+                i2c: self.i2c.with_backend(backend),
+            }
+        }
+
+        /// Tell all ARP-capable devices that address resolution is starting
+        pub fn prepare_to_arp(&self) -> Result<(), HubrisI2cError> {
+            self.i2c
+                .request_write(&self.i2c.device, &[PREPARE_TO_ARP])
+                .map_err(|response_code| HubrisI2cError {
+                    response_code,
+                    operation: "arp_prepare",
+                    bus: self.i2c.name,
+                    detail: None,
+                })
+        }
+
+        /// Read the UDID of the highest-priority device still contending
+        pub fn get_udid(&self) -> Result<Udid, HubrisI2cError> {
+            let mut response = [0u8; UDID_RESPONSE_LEN];
+            let len = self
+                .i2c
+                .request_read_block(&self.i2c.device, GET_UDID, &mut response)
+                .map_err(|response_code| HubrisI2cError {
+                    response_code,
+                    operation: "arp_get_udid",
+                    bus: self.i2c.name,
+                    detail: None,
+                })?;
+
+            if len != UDID_RESPONSE_LEN {
+                return Err(HubrisI2cError {
+                    response_code: ResponseCode::BadResponse,
+                    operation: "arp_get_udid_length",
+                    bus: self.i2c.name,
+                    detail: None,
+                });
+            }
+
+            Udid::decode(&response).ok_or(HubrisI2cError {
+                response_code: ResponseCode::BadResponse,
+                operation: "arp_get_udid_length",
+                bus: self.i2c.name,
+                detail: None,
+            })
+        }
+
+        /// Assign `addr` to the device identified by `udid`
+        pub fn assign_address(&self, udid: &Udid, addr: SevenBitAddr) -> Result<(), HubrisI2cError> {
+            let mut request = [0u8; 2 + UDID_RESPONSE_LEN];
+            request[0] = ASSIGN_ADDRESS;
+            request[1] = UDID_RESPONSE_LEN as u8;
+            request[2..18].copy_from_slice(&udid.0);
+            request[18] = addr.0 << 1;
+
+            self.i2c
+                .request_write(&self.i2c.device, &request)
+                .map_err(|response_code| HubrisI2cError {
+                    response_code,
+                    operation: "arp_assign_address",
+                    bus: self.i2c.name,
+                    detail: None,
+                })
+        }
+    }
+}
+
+#[cfg(feature = "target-mode")]
+pub mod target {
+    //! I2C target (slave) mode, for tasks that answer a host controller
+    //!
+    //! `drv-i2c-api` has no target-mode requests yet, so construction fails
+    //! with `OperationNotSupported` unless the backend advertises
+    //! [`Capabilities::TARGET_MODE`]; the stock [`IpcBackend`] doesn't.
+
+    use super::*;
+
+    /// What the controller asked of us when it addressed this target
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum TargetEvent {
+        /// Controller wants to read; answer with [`HubrisI2cTarget::respond_read`]
+        ReadRequested,
+        /// Controller is writing; collect with [`HubrisI2cTarget::accept_write`]
+        WriteRequested,
+    }
+
+    /// Handle for acting as an I2C target at a fixed address
+    ///
+    /// The wrapped [`HubrisI2c`] supplies the controller and port, the
+    /// address we answer to (its device address) and the backend.
+    pub struct HubrisI2cTarget<B = IpcBackend> {
+        i2c: HubrisI2c<B>,
+    }
+
+    impl HubrisI2cTarget {
+        /// Claim `address` as a target on the given controller and port
+        pub fn new(
+            i2c_server_task: TaskId,
+            controller: Controller,
+            port: PortIndex,
+            address: SevenBitAddr,
+        ) -> Result<Self, HubrisI2cError> {
+            Self::from_handle(HubrisI2c::new(
+                i2c_server_task,
+                controller,
+                port,
+                None,
+                address.0,
+            ))
+        }
+    }
+
+    impl<B: I2cBackend> HubrisI2cTarget<B> {
+        /// Claim `i2c`'s device address as a target on its controller and port
+        ///
+        /// Fails with `OperationNotSupported` unless `i2c`'s backend has
+        /// [`Capabilities::TARGET_MODE`].
+        pub fn from_handle(i2c: HubrisI2c<B>) -> Result<Self, HubrisI2cError> {
+            i2c.require(Capabilities::TARGET_MODE, "target_mode")?;
+            Ok(Self { i2c })
+        }
+
+        /// Address this target answers to
+        pub fn address(&self) -> SevenBitAddr {
+            self.i2c.address()
+        }
+
+        /// Block until the controller addresses us
+        pub fn listen(&mut self) -> Result<TargetEvent, HubrisI2cError> {
+            self.i2c
+                .backend
+                .target_listen(&self.i2c.device)
+                .map_err(|response_code| self.error(response_code, "target_listen"))
+        }
+
+        /// Supply the bytes for a pending controller read
+        pub fn respond_read(&mut self, data: &[u8]) -> Result<(), HubrisI2cError> {
+            self.i2c
+                .backend
+                .target_respond_read(&self.i2c.device, data)
+                .map_err(|response_code| self.error(response_code, "target_respond_read"))
+        }
+
+        /// Receive the bytes of a pending controller write
+        ///
+        /// Returns the number of bytes stored in `buffer`.
+        pub fn accept_write(&mut self, buffer: &mut [u8]) -> Result<usize, HubrisI2cError> {
+            self.i2c
+                .backend
+                .target_accept_write(&self.i2c.device, buffer)
+                .map_err(|response_code| self.error(response_code, "target_accept_write"))
+        }
+
+        fn error(&self, response_code: ResponseCode, operation: &'static str) -> HubrisI2cError {
+            HubrisI2cError {
+                response_code,
+                operation,
+                bus: self.i2c.name,
+                detail: None,
+            }
+        }
+    }
+}
+
+#[cfg(any(test, feature = "testing"))]
+pub mod mock {
+    //! Mock I2C implementation for testing embedded-hal device drivers
+
+    use super::*;
+    use core::time::Duration;
+    use heapless::Vec;
+
+    /// Mock I2C implementation for testing
+    ///
+    /// `D` simulates bus latency: delayed expectations call it with their
+    /// configured duration before completing.
+    pub struct MockI2c<D = NoDelay> {
+        expected_operations: Vec<MockOperation, 32>,
+        operation_index: usize,
+        first_mismatch: Option<(usize, &'static str)>,
+        delay: D,
+    }
+
+    #[derive(Debug, Clone)]
+    pub enum MockOperation {
+        Read {
+            address: SevenBitAddr,
+            response: Vec<u8, 256>,
+            delay: Duration,
+        },
+        Write {
+            address: SevenBitAddr,
+            expected_data: Vec<u8, 256>,
+            delay: Duration,
+        },
+        WriteRead {
+            address: SevenBitAddr,
+            expected_write: Vec<u8, 256>,
+            read_response: Vec<u8, 256>,
+            delay: Duration,
+        },
+        /// Fail whatever operation arrives next with an error of `kind`
+        Fail {
+            address: SevenBitAddr,
+            kind: ErrorKind,
+        },
+    }
+
+    /// One step of a [`MockI2c::from_script`] transcript
+    #[derive(Debug, Clone, Copy)]
+    pub enum MockStep<'a> {
+        /// Expect a write of exactly these bytes
+        Write(SevenBitAddr, &'a [u8]),
+        /// Expect a read, answered with these bytes
+        Read(SevenBitAddr, &'a [u8]),
+        /// Expect a write-read: the bytes written, then the bytes answered
+        WriteRead(SevenBitAddr, &'a [u8], &'a [u8]),
+        /// Fail the next operation to this address with an error of this kind
+        Fail(SevenBitAddr, ErrorKind),
+    }
+
+    /// A script didn't fit in the mock's fixed capacity
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum MockSetupError {
+        /// More steps than the mock can hold (32)
+        TooManySteps,
+        /// A step carries more than 256 bytes
+        StepTooLong,
+    }
+
+    impl core::fmt::Display for MockSetupError {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            match self {
+                MockSetupError::TooManySteps => write!(f, "Mock script has too many steps"),
+                MockSetupError::StepTooLong => write!(f, "Mock script step exceeds 256 bytes"),
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    impl std::error::Error for MockSetupError {}
+
+    impl MockI2c {
+        /// Create new mock I2C
+        pub fn new() -> Self {
+            Self {
+                expected_operations: Vec::new(),
+                operation_index: 0,
+                first_mismatch: None,
+                delay: NoDelay,
+            }
+        }
+
+        /// Create a mock whose expectations are the steps of `script`, in order
+        pub fn from_script(script: &[MockStep<'_>]) -> Result<Self, MockSetupError> {
+            fn bytes(data: &[u8]) -> Result<Vec<u8, 256>, MockSetupError> {
+                Vec::from_slice(data).map_err(|_| MockSetupError::StepTooLong)
+            }
+
+            let mut mock = Self::new();
+            for step in script {
+                let operation = match *step {
+                    MockStep::Write(address, data) => MockOperation::Write {
+                        address,
+                        expected_data: bytes(data)?,
+                        delay: Duration::ZERO,
+                    },
+                    MockStep::Read(address, response) => MockOperation::Read {
+                        address,
+                        response: bytes(response)?,
+                        delay: Duration::ZERO,
+                    },
+                    MockStep::WriteRead(address, write_data, read_response) => {
+                        MockOperation::WriteRead {
+                            address,
+                            expected_write: bytes(write_data)?,
+                            read_response: bytes(read_response)?,
+                            delay: Duration::ZERO,
+                        }
+                    }
+                    MockStep::Fail(address, kind) => MockOperation::Fail { address, kind },
+                };
+
+                mock.expected_operations
+                    .push(operation)
+                    .map_err(|_| MockSetupError::TooManySteps)?;
+            }
+
+            Ok(mock)
+        }
+    }
+
+    impl<D> MockI2c<D> {
+        /// Use `delay` to simulate the latency of delayed expectations
+        pub fn with_delay<D2: DelayProvider>(self, delay: D2) -> MockI2c<D2> {
+            MockI2c {
+                expected_operations: self.expected_operations,
+                operation_index: self.operation_index,
+                first_mismatch: self.first_mismatch,
+                delay,
+            }
+        }
+
+        /// Delay provider, e.g. to inspect what a fake delay recorded
+        pub fn delay(&self) -> &D {
+            &self.delay
+        }
+
+        /// Expect a write operation
+        pub fn expect_write(&mut self, address: SevenBitAddr, data: &[u8]) {
+            self.expect_write_delayed(address, data, Duration::ZERO);
+        }
+
+        /// Expect a write operation that takes `delay` to complete
+        pub fn expect_write_delayed(&mut self, address: SevenBitAddr, data: &[u8], delay: Duration) {
+            let mut expected_data = Vec::new();
+            expected_data.extend_from_slice(data).unwrap();
+
+            self.expected_operations
+                .push(MockOperation::Write {
+                    address,
+                    expected_data,
+                    delay,
+                })
+                .unwrap();
+        }
+
+        /// Expect a read operation
+        pub fn expect_read(&mut self, address: SevenBitAddr, response: &[u8]) {
+            self.expect_read_delayed(address, response, Duration::ZERO);
+        }
+
+        /// Expect a read operation that takes `delay` to complete
+        pub fn expect_read_delayed(
+            &mut self,
+            address: SevenBitAddr,
+            response: &[u8],
+            delay: Duration,
+        ) {
+            let mut response_data = Vec::new();
+            response_data.extend_from_slice(response).unwrap();
+
+            self.expected_operations
+                .push(MockOperation::Read {
+                    address,
+                    response: response_data,
+                    delay,
+                })
+                .unwrap();
+        }
+
+        /// Expect a write-read operation
+        pub fn expect_write_read(
+            &mut self,
+            address: SevenBitAddr,
+            write_data: &[u8],
+            read_response: &[u8],
+        ) {
+            self.expect_write_read_delayed(address, write_data, read_response, Duration::ZERO);
+        }
+
+        /// Expect a write-read operation that takes `delay` to complete
+        pub fn expect_write_read_delayed(
+            &mut self,
+            address: SevenBitAddr,
+            write_data: &[u8],
+            read_response: &[u8],
+            delay: Duration,
+        ) {
+            let mut expected_write = Vec::new();
+            expected_write.extend_from_slice(write_data).unwrap();
+
+            let mut response = Vec::new();
+            response.extend_from_slice(read_response).unwrap();
+
+            self.expected_operations
+                .push(MockOperation::WriteRead {
+                    address,
+                    expected_write,
+                    read_response: response,
+                    delay,
+                })
+                .unwrap();
+        }
+
+        /// Expect the next operation to `address` to fail with `kind`
+        pub fn expect_failure(&mut self, address: SevenBitAddr, kind: ErrorKind) {
+            self.expected_operations
+                .push(MockOperation::Fail { address, kind })
+                .unwrap();
+        }
+
+        /// Consume a scripted failure if it is the next expectation
+        fn scripted_failure(&mut self, address: SevenBitAddr) -> Option<MockI2cError> {
+            match self.expected_operations.get(self.operation_index) {
+                Some(MockOperation::Fail {
+                    address: expected_addr,
+                    kind,
+                }) => {
+                    if *expected_addr != address {
+                        return Some(MockI2cError {
+                            message: "Failure address mismatch",
+                            kind: ErrorKind::Other,
+                            scripted: false,
+                        });
+                    }
+
+                    let kind = *kind;
+                    self.operation_index += 1;
+                    Some(MockI2cError {
+                        message: "Scripted failure",
+                        kind,
+                        scripted: true,
+                    })
+                }
+                _ => None,
+            }
+        }
+
+        /// Expectations that haven't been consumed yet, in order
+        pub fn remaining(&self) -> &[MockOperation] {
+            &self.expected_operations[self.operation_index..]
+        }
+
+        /// Number of expectations consumed so far
+        ///
+        /// Taken between drivers sharing one mock, this pins down how far
+        /// each one got, e.g. "after driver A, we're at operation 3".
+        pub fn snapshot_progress(&self) -> usize {
+            self.operation_index
+        }
+
+        /// Verify every operation so far matched the next expectation
+        ///
+        /// Expectations can only be consumed in order, so an interleaving
+        /// bug shows up as a mismatch error. A driver that swallows that
+        /// error would hide it from the test, so the mock remembers the
+        /// first one and this panics with it.
+        pub fn assert_consumed_in_order(&self) {
+            if let Some((index, message)) = self.first_mismatch {
+                panic!("I2C operation {} did not match its expectation: {}", index, message);
+            }
+        }
+
+        /// Verify all expected operations were performed
+        pub fn verify_complete(&self) {
+            assert_eq!(
+                self.operation_index,
+                self.expected_operations.len(),
+                "Not all expected I2C operations were performed; remaining: {:?}",
+                self.remaining()
+            );
+        }
+    }
+
+    /// Mock I2C error type
+    #[derive(Debug)]
+    pub struct MockI2cError {
+        message: &'static str,
+        kind: ErrorKind,
+        /// Produced by an `expect_failure` expectation rather than a mismatch
+        scripted: bool,
+    }
+
+    impl MockI2cError {
+        /// Whether this error was scripted with
+        /// [`expect_failure`](MockI2c::expect_failure) rather than caused by
+        /// an operation that didn't match its expectation
+        pub fn is_scripted(&self) -> bool {
+            self.scripted
+        }
+    }
+
+    impl embedded_hal::i2c::Error for MockI2cError {
+        fn kind(&self) -> ErrorKind {
+            self.kind
+        }
+    }
+
+    impl RetryClass for MockI2cError {}
+
+    impl core::fmt::Display for MockI2cError {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            write!(f, "Mock I2C error: {}", self.message)
+        }
+    }
+
+    #[cfg(feature = "std")]
+    impl std::error::Error for MockI2cError {}
+
+    impl<D> ErrorType for MockI2c<D> {
+        type Error = MockI2cError;
+    }
+
+    impl<D: DelayProvider> MockI2c<D> {
+        /// Check a read against the next expectation and consume it
+        fn match_read(
+            &mut self,
+            address: SevenBitAddr,
+            buffer: &mut [u8],
+        ) -> Result<(), MockI2cError> {
+            if let Some(error) = self.scripted_failure(address) {
+                return Err(error);
+            }
+
+            if self.operation_index >= self.expected_operations.len() {
+                return Err(MockI2cError {
+                    message: "Unexpected read operation",
+                    kind: ErrorKind::Other,
+                    scripted: false,
+                });
+            }
+
+            match &self.expected_operations[self.operation_index] {
+                MockOperation::Read {
+                    address: expected_addr,
+                    response,
+                    delay,
+                } => {
+                    if *expected_addr != address {
+                        return Err(MockI2cError {
+                            message: "Read address mismatch",
+                            kind: ErrorKind::Other,
+                            scripted: false,
+                        });
+                    }
+
+                    if buffer.len() != response.len() {
+                        return Err(MockI2cError {
+                            message: "Read buffer size mismatch",
+                            kind: ErrorKind::Other,
+                            scripted: false,
+                        });
+                    }
+
+                    if !delay.is_zero() {
+                        self.delay.delay(*delay);
+                    }
+                    buffer.copy_from_slice(response);
+                    self.operation_index += 1;
+                    Ok(())
+                }
+                _ => Err(MockI2cError {
+                    message: "Expected read operation",
+                    kind: ErrorKind::Other,
+                    scripted: false,
+                }),
+            }
+        }
+
+        /// Check a write against the next expectation and consume it
+        fn match_write(
+            &mut self,
+            address: SevenBitAddr,
+            bytes: &[u8],
+        ) -> Result<(), MockI2cError> {
+            if let Some(error) = self.scripted_failure(address) {
+                return Err(error);
+            }
+
+            if self.operation_index >= self.expected_operations.len() {
+                return Err(MockI2cError {
+                    message: "Unexpected write operation",
+                    kind: ErrorKind::Other,
+                    scripted: false,
+                });
+            }
+
+            match &self.expected_operations[self.operation_index] {
+                MockOperation::Write {
+                    address: expected_addr,
+                    expected_data,
+                    delay,
+                } => {
+                    if *expected_addr != address {
+                        return Err(MockI2cError {
+                            message: "Write address mismatch",
+                            kind: ErrorKind::Other,
+                            scripted: false,
+                        });
+                    }
+
+                    if bytes != expected_data.as_slice() {
+                        return Err(MockI2cError {
+                            message: "Write data mismatch",
+                            kind: ErrorKind::Other,
+                            scripted: false,
+                        });
+                    }
+
+                    if !delay.is_zero() {
+                        self.delay.delay(*delay);
+                    }
+                    self.operation_index += 1;
+                    Ok(())
+                }
+                _ => Err(MockI2cError {
+                    message: "Expected write operation",
+                    kind: ErrorKind::Other,
+                    scripted: false,
+                }),
+            }
+        }
+
+        /// Check a write-read against the next expectation and consume it
+        fn match_write_read(
+            &mut self,
+            address: SevenBitAddr,
+            bytes: &[u8],
+            buffer: &mut [u8],
+        ) -> Result<(), MockI2cError> {
+            if let Some(error) = self.scripted_failure(address) {
+                return Err(error);
+            }
+
+            if self.operation_index >= self.expected_operations.len() {
+                return Err(MockI2cError {
+                    message: "Unexpected write_read operation",
+                    kind: ErrorKind::Other,
+                    scripted: false,
+                });
+            }
+
+            match &self.expected_operations[self.operation_index] {
+                MockOperation::WriteRead {
+                    address: expected_addr,
+                    expected_write,
+                    read_response,
+                    delay,
+                } => {
+                    if *expected_addr != address {
+                        return Err(MockI2cError {
+                            message: "WriteRead address mismatch",
+                            kind: ErrorKind::Other,
+                            scripted: false,
+                        });
+                    }
+
+                    if bytes != expected_write.as_slice() {
+                        return Err(MockI2cError {
+                            message: "WriteRead write data mismatch",
+                            kind: ErrorKind::Other,
+                            scripted: false,
+                        });
+                    }
+
+                    if buffer.len() != read_response.len() {
+                        return Err(MockI2cError {
+                            message: "WriteRead read buffer size mismatch",
+                            kind: ErrorKind::Other,
+                            scripted: false,
+                        });
+                    }
+
+                    if !delay.is_zero() {
+                        self.delay.delay(*delay);
+                    }
+                    buffer.copy_from_slice(read_response);
+                    self.operation_index += 1;
+                    Ok(())
+                }
+                _ => Err(MockI2cError {
+                    message: "Expected write_read operation",
+                    kind: ErrorKind::Other,
+                    scripted: false,
+                }),
+            }
+        }
+
+        /// Remember the first operation that didn't match its expectation
+        fn record(&mut self, result: Result<(), MockI2cError>) -> Result<(), MockI2cError> {
+            if let Err(error) = &result {
+                if !error.scripted && self.first_mismatch.is_none() {
+                    self.first_mismatch = Some((self.operation_index, error.message));
+                }
+            }
+            result
+        }
+    }
+
+    impl<D: DelayProvider> embedded_hal::i2c::I2c<SevenBitAddr> for MockI2c<D> {
+        fn read(&mut self, address: SevenBitAddr, buffer: &mut [u8]) -> Result<(), Self::Error> {
+            let result = self.match_read(address, buffer);
+            self.record(result)
+        }
+
+        fn write(&mut self, address: SevenBitAddr, bytes: &[u8]) -> Result<(), Self::Error> {
+            let result = self.match_write(address, bytes);
+            self.record(result)
+        }
+
+        fn write_read(
+            &mut self,
+            address: SevenBitAddr,
+            bytes: &[u8],
+            buffer: &mut [u8],
+        ) -> Result<(), Self::Error> {
+            let result = self.match_write_read(address, bytes, buffer);
+            self.record(result)
+        }
+
+        fn transaction(
+            &mut self,
+            address: SevenBitAddr,
+            operations: &mut [Operation<'_>],
+        ) -> Result<(), Self::Error> {
+            for operation in operations.iter_mut() {
+                match operation {
+                    Operation::Read(buffer) => {
+                        self.read(address, buffer)?;
+                    }
+                    Operation::Write(data) => {
+                        self.write(address, data)?;
+                    }
+                }
+            }
+            Ok(())
+        }
+    }
+
+    impl Default for MockI2c {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+}
+
+// Host tools collect these as `Box<dyn std::error::Error + Send + Sync>`
+// (or `anyhow::Error`), so every public error type must stay `Send + Sync +
+// 'static`. This fails to compile if one stops qualifying.
+#[cfg(feature = "std")]
+const _: () = {
+    const fn assert_boxable<E: std::error::Error + Send + Sync + 'static>() {}
+
+    assert_boxable::<HubrisI2cError>();
+    assert_boxable::<TransactionError>();
+    assert_boxable::<VerifyError>();
+    assert_boxable::<InvalidAddress>();
+    #[cfg(feature = "testing")]
+    assert_boxable::<mock::MockI2cError>();
+    #[cfg(feature = "testing")]
+    assert_boxable::<mock::MockSetupError>();
+};
+
+// Re-export common types for convenience
+pub use embedded_hal::i2c::{Error, ErrorKind, I2c, NoAcknowledgeSource, Operation};
+
+#[cfg(any(test, feature = "testing"))]
+pub use mock::{MockI2c, MockStep};
+
+#[cfg(feature = "target-mode")]
+pub use target::{HubrisI2cTarget, TargetEvent};
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+    use mock::MockI2cError;
+    use core::cell::RefCell;
+    use std::collections::VecDeque;
+    use std::vec::Vec;
+
+    /// Mux segment a request was routed to
+    type Route = Option<(Mux, Segment)>;
+
+    /// Request as received by [`FakeServer`]
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    enum Request {
+        Write { address: u8, bytes: Vec<u8> },
+        Read { address: u8, len: usize },
+        ReadReg { address: u8, reg: Vec<u8>, len: usize },
+        ReadBlock { address: u8, reg: Vec<u8>, len: usize },
+        RecoverBus,
+        ForceStop,
+        LineState,
+        BusFrequency,
+        #[cfg(feature = "target-mode")]
+        TargetListen { address: u8 },
+        #[cfg(feature = "target-mode")]
+        TargetRespond { address: u8, bytes: Vec<u8> },
+        #[cfg(feature = "target-mode")]
+        TargetAccept { address: u8, len: usize },
+    }
+
+    /// Backend that records every request and answers from a script
+    ///
+    /// Replies are consumed in order; once the script runs out, writes
+    /// succeed and reads return zeros. Addresses outside `present` (when
+    /// set) NACK without consuming a reply. A `hung` server never replies:
+    /// requests fail with `BusTimeout` inside `with_timeout` and panic
+    /// outside it, standing in for blocking forever.
+    struct FakeServer {
+        caps: Capabilities,
+        present: Option<Vec<(Route, u8)>>,
+        replies: RefCell<VecDeque<Result<Vec<u8>, ResponseCode>>>,
+        requests: RefCell<Vec<(Route, Request)>>,
+        hung: bool,
+        timeout: core::cell::Cell<Option<core::time::Duration>>,
+        priority: core::cell::Cell<Option<u8>>,
+        priorities: RefCell<Vec<Option<u8>>>,
+    }
+
+    impl FakeServer {
+        fn new() -> Self {
+            Self {
+                caps: Capabilities::NONE,
+                present: None,
+                replies: RefCell::new(VecDeque::new()),
+                requests: RefCell::new(Vec::new()),
+                hung: false,
+                timeout: core::cell::Cell::new(None),
+                priority: core::cell::Cell::new(None),
+                priorities: RefCell::new(Vec::new()),
+            }
+        }
+
+        fn with_caps(mut self, caps: Capabilities) -> Self {
+            self.caps = caps;
+            self
+        }
+
+        fn reply(self, bytes: &[u8]) -> Self {
+            self.replies.borrow_mut().push_back(Ok(bytes.to_vec()));
+            self
+        }
+
+        fn fail(self, code: ResponseCode) -> Self {
+            self.replies.borrow_mut().push_back(Err(code));
+            self
+        }
+
+        fn with_present(mut self, present: &[(Route, u8)]) -> Self {
+            self.present = Some(present.to_vec());
+            self
+        }
+
+        /// Route of every request, in order
+        fn routes(&self) -> Vec<Route> {
+            self.requests.borrow().iter().map(|(route, _)| *route).collect()
+        }
+
+        fn requests(&self) -> Vec<Request> {
+            self.requests.borrow().iter().map(|(_, request)| request.clone()).collect()
+        }
+
+        fn answer(&self, device: &I2cDevice, request: Request) -> Result<Vec<u8>, ResponseCode> {
+            self.requests.borrow_mut().push((device.segment, request));
+            self.priorities.borrow_mut().push(self.priority.get());
+
+            if self.hung {
+                assert!(self.timeout.get().is_some(), "request blocked forever");
+                return Err(ResponseCode::BusTimeout);
+            }
+
+            if let Some(present) = &self.present {
+                if !present.contains(&(device.segment, device.address)) {
+                    return Err(ResponseCode::AddressNackSentEarly);
+                }
+            }
+
+            self.replies.borrow_mut().pop_front().unwrap_or(Ok(Vec::new()))
+        }
+
+        fn answer_into(
+            &self,
+            device: &I2cDevice,
+            request: Request,
+            buffer: &mut [u8],
+        ) -> Result<usize, ResponseCode> {
+            let reply = self.answer(device, request)?;
+            if reply.is_empty() {
+                buffer.fill(0);
+                return Ok(buffer.len());
+            }
+
+            let len = reply.len().min(buffer.len());
+            buffer[..len].copy_from_slice(&reply[..len]);
+            Ok(len)
+        }
+    }
+
+    impl I2cBackend for FakeServer {
+        fn write(&self, device: &I2cDevice, bytes: &[u8]) -> Result<(), ResponseCode> {
+            let request = Request::Write {
+                address: device.address,
+                bytes: bytes.to_vec(),
+            };
+            self.answer(device, request).map(|_| ())
+        }
+
+        fn read_into(&self, device: &I2cDevice, buffer: &mut [u8]) -> Result<usize, ResponseCode> {
+            let request = Request::Read {
+                address: device.address,
+                len: buffer.len(),
+            };
+            self.answer_into(device, request, buffer)
+        }
+
+        fn read_reg_into<R>(
+            &self,
+            device: &I2cDevice,
+            reg: R,
+            buffer: &mut [u8],
+        ) -> Result<usize, ResponseCode>
+        where
+            R: zerocopy::IntoBytes + zerocopy::Immutable,
+        {
+            let request = Request::ReadReg {
+                address: device.address,
+                reg: reg.as_bytes().to_vec(),
+                len: buffer.len(),
+            };
+            self.answer_into(device, request, buffer)
+        }
+
+        fn read_block<R>(
+            &self,
+            device: &I2cDevice,
+            reg: R,
+            buffer: &mut [u8],
+        ) -> Result<usize, ResponseCode>
+        where
+            R: zerocopy::IntoBytes + zerocopy::Immutable,
+        {
+            let request = Request::ReadBlock {
+                address: device.address,
+                reg: reg.as_bytes().to_vec(),
+                len: buffer.len(),
+            };
+            self.answer_into(device, request, buffer)
+        }
+
+        fn capabilities(&self) -> Capabilities {
+            self.caps
+        }
+
+        fn recover_bus(&self, device: &I2cDevice) -> Result<(), ResponseCode> {
+            self.answer(device, Request::RecoverBus).map(|_| ())
+        }
+
+        fn force_stop(&self, device: &I2cDevice) -> Result<(), ResponseCode> {
+            self.answer(device, Request::ForceStop).map(|_| ())
+        }
+
+        /// A one-byte reply sets SDA from bit 0 and SCL from bit 1
+        fn line_state(&self, device: &I2cDevice) -> Result<(bool, bool), ResponseCode> {
+            let lines = self.answer(device, Request::LineState)?;
+            let lines = lines.first().copied().unwrap_or(0b11);
+            Ok((lines & 0b01 != 0, lines & 0b10 != 0))
+        }
+
+        /// A four-byte reply is the frequency, little-endian
+        fn bus_frequency_hz(&self, device: &I2cDevice) -> Result<u32, ResponseCode> {
+            let hz = self.answer(device, Request::BusFrequency)?;
+            hz.try_into().map(u32::from_le_bytes).map_err(|_| ResponseCode::BadResponse)
+        }
+
+        /// A reply of `[1]` is a controller write, anything else a read
+        #[cfg(feature = "target-mode")]
+        fn target_listen(&self, device: &I2cDevice) -> Result<TargetEvent, ResponseCode> {
+            let request = Request::TargetListen { address: device.address };
+            Ok(match self.answer(device, request)?.as_slice() {
+                [1] => TargetEvent::WriteRequested,
+                _ => TargetEvent::ReadRequested,
+            })
+        }
+
+        #[cfg(feature = "target-mode")]
+        fn target_respond_read(&self, device: &I2cDevice, data: &[u8]) -> Result<(), ResponseCode> {
+            let request = Request::TargetRespond {
+                address: device.address,
+                bytes: data.to_vec(),
+            };
+            self.answer(device, request).map(|_| ())
+        }
+
+        #[cfg(feature = "target-mode")]
+        fn target_accept_write(
+            &self,
+            device: &I2cDevice,
+            buffer: &mut [u8],
+        ) -> Result<usize, ResponseCode> {
+            let request = Request::TargetAccept {
+                address: device.address,
+                len: buffer.len(),
+            };
+            self.answer_into(device, request, buffer)
+        }
+
+        fn with_priority<T>(
+            &self,
+            priority: u8,
+            request: impl FnOnce(&Self) -> Result<T, ResponseCode>,
+        ) -> Result<T, ResponseCode> {
+            self.priority.set(Some(priority));
+            let result = request(self);
+            self.priority.set(None);
+            result
+        }
+
+        fn with_timeout<T>(
+            &self,
+            timeout: core::time::Duration,
+            request: impl FnOnce(&Self) -> Result<T, ResponseCode>,
+        ) -> Result<T, ResponseCode> {
+            self.timeout.set(Some(timeout));
+            let result = request(self);
+            self.timeout.set(None);
+            result
+        }
+    }
+
+    fn handle(server: &FakeServer) -> HubrisI2c<&FakeServer> {
+        HubrisI2c::new_simple(TaskId::KERNEL, Controller::I2c1, PortIndex(0), 0x48)
+            .with_backend(server)
+    }
+
+    #[test]
+    fn capabilities_come_from_the_backend() {
+        let server = FakeServer::new().with_caps(Capabilities::BUS_RECOVERY);
+        let i2c = handle(&server);
+
+        assert_eq!(i2c.capabilities(), Capabilities::BUS_RECOVERY);
+        assert!(i2c.require(Capabilities::BUS_RECOVERY, "test").is_ok());
+        assert_eq!(
+            i2c.require(Capabilities::TIMEOUT, "test").unwrap_err().response_code,
+            ResponseCode::OperationNotSupported
+        );
+    }
+
+    #[test]
+    fn stock_server_lacks_optional_features() {
+        assert_eq!(IpcBackend.capabilities(), Capabilities::SERVER);
+        assert!(!Capabilities::SERVER.contains(Capabilities::TEN_BIT));
+        assert!(!Capabilities::SERVER.contains(Capabilities::BUS_RECOVERY));
+    }
+
+    #[test]
+    fn ten_bit_fails_fast_without_capability() {
+        let server = FakeServer::new();
+        let mut i2c = handle(&server);
+        let mut buffer = [0u8; 2];
+
+        let err = i2c.write(TenBitAddr(0x150), &[0x01]).unwrap_err();
+        assert_eq!(err.response_code, ResponseCode::OperationNotSupported);
+        let err = i2c.read(TenBitAddr(0x150), &mut buffer).unwrap_err();
+        assert_eq!(err.response_code, ResponseCode::OperationNotSupported);
+        let err = i2c.transaction(TenBitAddr(0x150), &mut []).unwrap_err();
+        assert_eq!(err.response_code, ResponseCode::OperationNotSupported);
+
+        assert!(server.requests().is_empty());
+    }
+
+    #[test]
+    fn recover_bus_is_gated_on_capability() {
+        let server = FakeServer::new();
+        let err = handle(&server).recover_bus().unwrap_err();
+        assert_eq!(err.response_code, ResponseCode::OperationNotSupported);
+        assert!(server.requests().is_empty());
+
+        let server = FakeServer::new().with_caps(Capabilities::BUS_RECOVERY);
+        handle(&server).recover_bus().unwrap();
+        assert_eq!(server.requests(), [Request::RecoverBus]);
+    }
+
+    #[test]
+    fn invalid_address_converts_with_detail() {
+        let err = HubrisI2cError::from(InvalidAddress::Reserved(0x7C));
+        assert_eq!(err.response_code, ResponseCode::BadResponse);
+        assert_eq!(err.operation, "invalid_address_reserved");
+        assert_eq!(
+            err.detail,
+            Some(ErrorDetail::InvalidAddress(InvalidAddress::Reserved(0x7C)))
+        );
+
+        let text = std::format!("{}", err);
+        assert!(text.contains(&std::format!("{}", InvalidAddress::Reserved(0x7C))));
+    }
+
+    #[test]
+    fn oversized_transaction_fails_before_the_bus() {
+        let server = FakeServer::new();
+        let mut i2c = handle(&server).with_name("rail");
+        let mut first = [0u8; 200];
+        let mut second = [0u8; 57];
+        let mut operations = [Operation::Read(&mut first), Operation::Read(&mut second)];
+
+        let err = i2c.transaction(SevenBitAddr(0x48), &mut operations).unwrap_err();
+        assert_eq!(err.response_code, ResponseCode::TooMuchData);
+        assert_eq!(err.bus, Some("rail"));
+        assert_eq!(
+            err.detail,
+            Some(ErrorDetail::TooLong { limit: 256, attempted: 257 })
+        );
+        assert!(server.requests().is_empty());
+
+        let mut first = [0u8; 200];
+        let mut second = [0u8; 56];
+        let mut operations = [Operation::Read(&mut first), Operation::Read(&mut second)];
+        i2c.transaction(SevenBitAddr(0x48), &mut operations).unwrap();
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[test]
+    fn smbus_block_follows_the_length_byte() {
+        let server = FakeServer::new().reply(&[3, 0xA, 0xB, 0xC, 0xD]);
+        let mut buffer = [0u8; 8];
+        assert_eq!(handle(&server).smbus_read_block(0x9A, &mut buffer), Ok(3));
+        assert_eq!(buffer[..4], [0xA, 0xB, 0xC, 0]);
+        assert_eq!(
+            server.requests(),
+            [Request::ReadReg { address: 0x48, reg: std::vec![0x9A], len: 33 }]
+        );
+
+        let server = FakeServer::new().reply(&[3, 0xA, 0xB, 0xC]);
+        let mut short = [0u8; 2];
+        assert_eq!(handle(&server).smbus_read_block(0x9A, &mut short), Ok(2));
+        assert_eq!(short, [0xA, 0xB]);
+
+        for len in [0, 33] {
+            let server = FakeServer::new().reply(&[len]);
+            let err = handle(&server).smbus_read_block(0x9A, &mut buffer).unwrap_err();
+            assert_eq!(err.response_code, ResponseCode::BadResponse);
+        }
+    }
+
+    #[test]
+    fn pmbus_extended_block_fits_the_transfer_limit() {
+        let mut reply = std::vec![255u8];
+        reply.extend(core::iter::repeat_n(0x5A, 255));
+        let server = FakeServer::new().reply(&reply);
+        let mut buffer = [0u8; 255];
+        assert_eq!(handle(&server).smbus_read_block_max(0x9A, &mut buffer, 255), Ok(255));
+        assert!(buffer.iter().all(|&byte| byte == 0x5A));
+        assert_eq!(
+            server.requests(),
+            [Request::ReadReg { address: 0x48, reg: std::vec![0x9A], len: 256 }]
+        );
+    }
+
+    #[test]
+    fn block16_over_the_limit_is_unsupported() {
+        // 300 = 0x012C
+        let mut reply = std::vec![0x01, 0x2C];
+        reply.extend(core::iter::repeat_n(0x11, 254));
+        let server = FakeServer::new().reply(&reply);
+        let mut buffer = [0u8; 300];
+        let err = handle(&server).read_block16(0x40, &mut buffer).unwrap_err();
+        assert_eq!(err.response_code, ResponseCode::TooMuchData);
+        assert_eq!(
+            err.detail,
+            Some(ErrorDetail::TooLong { limit: 254, attempted: 300 })
+        );
+        assert_eq!(
+            server.requests(),
+            [Request::ReadReg { address: 0x48, reg: std::vec![0x40], len: 256 }]
+        );
+    }
+
+    #[test]
+    fn block16_truncates_to_the_buffer() {
+        let server = FakeServer::new().reply(&[0x00, 0x04, 1, 2, 3, 4]);
+        let mut buffer = [0u8; 2];
+        assert_eq!(handle(&server).read_block16(0x40, &mut buffer), Ok(2));
+        assert_eq!(buffer, [1, 2]);
+
+        let server = FakeServer::new().reply(&[0x00, 0x03, 1, 2, 3]);
+        let mut buffer = [0u8; 8];
+        assert_eq!(handle(&server).read_block16(0x40, &mut buffer), Ok(3));
+        assert_eq!(buffer[..3], [1, 2, 3]);
+    }
+
+    #[test]
+    fn ten_bit_addresses_the_header() {
+        let server = FakeServer::new().with_caps(Capabilities::TEN_BIT);
+        let mut i2c = handle(&server);
+        let mut buffer = [0u8; 2];
+
+        i2c.write(TenBitAddr(0x2A5), &[0x10, 0x20]).unwrap();
+        i2c.read(TenBitAddr(0x2A5), &mut buffer).unwrap();
+        assert_eq!(
+            server.requests(),
+            [
+                Request::Write { address: 0x7A, bytes: std::vec![0xA5, 0x10, 0x20] },
+                Request::ReadReg { address: 0x7A, reg: std::vec![0xA5], len: 2 },
+            ]
+        );
+    }
+
+    #[test]
+    fn ten_bit_transactions_skip_empty_operations() {
+        let server = FakeServer::new().with_caps(Capabilities::TEN_BIT);
+        let mut i2c = handle(&server);
+        let mut empty = [0u8; 0];
+        let mut buffer = [0u8; 1];
+
+        i2c.transaction(
+            TenBitAddr(0x2A5),
+            &mut [
+                Operation::Write(&[]),
+                Operation::Write(&[0x10]),
+                Operation::Read(&mut empty),
+                Operation::Read(&mut buffer),
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            server.requests(),
+            [
+                Request::Write { address: 0x7A, bytes: std::vec![0xA5, 0x10] },
+                Request::ReadReg { address: 0x7A, reg: std::vec![0xA5], len: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn ten_bit_write_read_with_stop() {
+        let server = FakeServer::new().with_caps(Capabilities::TEN_BIT);
+        let mut buffer = [0u8; 4];
+        handle(&server)
+            .write_read_10bit(TenBitAddr(0x150), &[0x07], &mut buffer, false)
+            .unwrap();
+        assert_eq!(
+            server.requests(),
+            [
+                Request::Write { address: 0x79, bytes: std::vec![0x50, 0x07] },
+                Request::ReadReg { address: 0x79, reg: std::vec![0x50], len: 4 },
+            ]
+        );
+    }
+
+    #[test]
+    fn ten_bit_write_read_with_repeated_start() {
+        let server = FakeServer::new()
+            .with_caps(Capabilities::TEN_BIT)
+            .reply(&[0xDE, 0xAD]);
+        let mut i2c = handle(&server);
+        let mut buffer = [0u8; 2];
+        i2c.write_read_10bit(TenBitAddr(0x150), &[0x07, 0x08], &mut buffer, true)
+            .unwrap();
+        assert_eq!(buffer, [0xDE, 0xAD]);
+        assert_eq!(
+            server.requests(),
+            [Request::ReadReg { address: 0x79, reg: std::vec![0x50, 0x07, 0x08], len: 2 }]
+        );
+
+        let err = i2c
+            .write_read_10bit(TenBitAddr(0x150), &[1, 2, 3], &mut buffer, true)
+            .unwrap_err();
+        assert_eq!(err.response_code, ResponseCode::OperationNotSupported);
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[test]
+    fn ten_bit_write_limit_boundary() {
+        let server = FakeServer::new().with_caps(Capabilities::TEN_BIT);
+        let mut i2c = handle(&server);
+
+        i2c.write(TenBitAddr(0x150), &[0xEE; 256]).unwrap();
+        let mut expected = std::vec![0x50];
+        expected.extend_from_slice(&[0xEE; 256]);
+        assert_eq!(server.requests(), [Request::Write { address: 0x79, bytes: expected }]);
+
+        let err = i2c.write(TenBitAddr(0x150), &[0xEE; 257]).unwrap_err();
+        assert_eq!(err.response_code, ResponseCode::TooMuchData);
+        assert_eq!(
+            err.detail,
+            Some(ErrorDetail::TooLong { limit: 256, attempted: 257 })
+        );
+        assert!(std::format!("{}", err).contains("257 bytes exceeds the 256 byte limit"));
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[test]
+    fn unsupported_feature_is_recorded_at_construction() {
+        let server = FakeServer::new();
+        let mut i2c = handle(&server);
+        let mut buffer = [0u8; 1];
+
+        let err = i2c.read(TenBitAddr(0x150), &mut buffer).unwrap_err();
+        assert_eq!(err.unsupported_feature(), Some(UnsupportedFeature::TenBit));
+        // Survives the operation being renamed
+        let err = err.with_operation("sensor_read");
+        assert_eq!(err.unsupported_feature(), Some(UnsupportedFeature::TenBit));
+
+        let err = i2c.recover_bus().unwrap_err();
+        assert_eq!(err.unsupported_feature(), Some(UnsupportedFeature::BusRecovery));
+        let err = i2c.force_stop().unwrap_err();
+        assert_eq!(err.unsupported_feature(), Some(UnsupportedFeature::ForceStop));
+
+        let server = FakeServer::new().with_caps(Capabilities::TEN_BIT);
+        let err = handle(&server)
+            .write_read_10bit(TenBitAddr(0x150), &[1, 2, 3], &mut buffer, true)
+            .unwrap_err();
+        assert_eq!(err.unsupported_feature(), Some(UnsupportedFeature::TenBitRepeatedStart));
+    }
+
+    #[test]
+    fn unsupported_feature_without_detail() {
+        let err = HubrisI2cError {
+            response_code: ResponseCode::OperationNotSupported,
+            operation: "force_stop",
+            bus: None,
+            detail: None,
+        };
+        assert_eq!(err.unsupported_feature(), Some(UnsupportedFeature::Other));
+
+        let err = HubrisI2cError {
+            response_code: ResponseCode::BusLocked,
+            ..err
+        };
+        assert_eq!(err.unsupported_feature(), None);
+    }
+
+    #[test]
+    fn last_operation_tracks_every_request_path() {
+        let server = FakeServer::new().with_caps(Capabilities::TEN_BIT);
+        let mut i2c = handle(&server);
+        assert_eq!(i2c.last_operation(), None);
+
+        i2c.write(SevenBitAddr(0x48), &[0x01, 0x60]).unwrap();
+        assert_eq!(
+            i2c.last_operation(),
+            Some(LastOp { kind: LastOpKind::Write, first_byte: Some(0x01), len: 2 })
+        );
+
+        let mut buffer = [0u8; 3];
+        i2c.read(TenBitAddr(0x150), &mut buffer).unwrap();
+        assert_eq!(
+            i2c.last_operation(),
+            Some(LastOp { kind: LastOpKind::WriteRead, first_byte: Some(0x50), len: 4 })
+        );
+
+        let optimized = RegisterOptimizedI2c::new(i2c);
+        optimized.read_reg_word(0x05).unwrap();
+        assert_eq!(
+            optimized.inner().last_operation(),
+            Some(LastOp { kind: LastOpKind::WriteRead, first_byte: Some(0x05), len: 3 })
+        );
+    }
+
+    #[cfg(feature = "watchdog")]
+    #[test]
+    fn op_timeout_bounds_a_hung_server() {
+        let mut server = FakeServer::new();
+        server.hung = true;
+        let mut i2c = handle(&server);
+        let timeout = core::time::Duration::from_millis(5);
+        assert_eq!(
+            i2c.set_op_timeout(Some(timeout)).unwrap_err().unsupported_feature(),
+            Some(UnsupportedFeature::OpTimeout)
+        );
+
+        server.caps = Capabilities::TIMEOUT;
+        let mut i2c = handle(&server);
+        i2c.set_op_timeout(Some(timeout)).unwrap();
+        let err = i2c.read_register::<u8, u8>(0x00).unwrap_err();
+        assert_eq!(err.response_code, ResponseCode::BusTimeout);
+        let err = i2c.write(SevenBitAddr(0x48), &[0x01]).unwrap_err();
+        assert_eq!(err.response_code, ResponseCode::BusTimeout);
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[test]
+    fn force_stop_reaches_the_server() {
+        let server = FakeServer::new();
+        let err = handle(&server).force_stop().unwrap_err();
+        assert_eq!(err.response_code, ResponseCode::OperationNotSupported);
+        assert!(server.requests().is_empty());
+
+        let server = FakeServer::new().with_caps(Capabilities::FORCE_STOP);
+        handle(&server).force_stop().unwrap();
+        assert_eq!(server.requests(), [Request::ForceStop]);
+    }
+
+    #[test]
+    fn bus_health_reports_idle_and_stuck_lines() {
+        let server = FakeServer::new();
+        let err = handle(&server).bus_health().unwrap_err();
+        assert_eq!(err.unsupported_feature(), Some(UnsupportedFeature::LineState));
+        assert!(server.requests().is_empty());
+
+        let server = FakeServer::new()
+            .with_caps(Capabilities::LINE_STATE)
+            .reply(&[0b11])
+            .reply(&[0b10]);
+        let i2c = handle(&server);
+        assert_eq!(
+            i2c.bus_health(),
+            Ok(BusHealth { sda_high: true, scl_high: true, idle: true })
+        );
+        assert_eq!(
+            i2c.bus_health(),
+            Ok(BusHealth { sda_high: false, scl_high: true, idle: false })
+        );
+        assert_eq!(server.requests(), [Request::LineState, Request::LineState]);
+    }
+
+    #[test]
+    fn bus_frequency_comes_from_the_server() {
+        let server = FakeServer::new();
+        let err = handle(&server).bus_frequency_hz().unwrap_err();
+        assert_eq!(err.unsupported_feature(), Some(UnsupportedFeature::BusFrequency));
+        assert!(server.requests().is_empty());
+
+        let server = FakeServer::new()
+            .with_caps(Capabilities::BUS_FREQUENCY)
+            .reply(&400_000u32.to_le_bytes());
+        assert_eq!(handle(&server).bus_frequency_hz(), Ok(400_000));
+        assert_eq!(server.requests(), [Request::BusFrequency]);
+    }
+
+    #[test]
+    fn scheduling_priority_reaches_the_server() {
+        let server = FakeServer::new();
+        let mut bus = SchedulingI2c::new(handle(&server), 7);
+        let mut buffer = [0u8; 2];
+
+        bus.read(SevenBitAddr(0x48), &mut buffer).unwrap();
+        bus.with_priority(200, |bus| {
+            bus.write_read(SevenBitAddr(0x48), &[0x01, 0x02], &mut buffer)
+        })
+        .unwrap();
+        bus.inner_mut().read(SevenBitAddr(0x48), &mut buffer).unwrap();
+
+        assert_eq!(*server.priorities.borrow(), [Some(7), Some(200), Some(200), None]);
+    }
+
+    fn bus(server: &FakeServer) -> BusParams<&FakeServer> {
+        BusParams::new(TaskId::KERNEL, Controller::I2c1, PortIndex(0)).with_backend(server)
+    }
+
+    /// Advances by `step` every time it is read
+    struct SteppingClock {
+        now: core::cell::Cell<core::time::Duration>,
+        step: core::time::Duration,
+    }
+
+    impl Clock for SteppingClock {
+        fn now(&self) -> core::time::Duration {
+            let now = self.now.get();
+            self.now.set(now + self.step);
+            now
+        }
+    }
+
+    #[test]
+    fn topology_walks_the_given_segments() {
+        let s1 = Some((Mux::M1, Segment::S1));
+        let s2 = Some((Mux::M1, Segment::S2));
+        let server =
+            FakeServer::new().with_present(&[(None, 0x10), (s1, 0x20), (s2, 0x21), (s2, 0x50)]);
+        let clock = SteppingClock {
+            now: core::cell::Cell::new(core::time::Duration::ZERO),
+            step: core::time::Duration::ZERO,
+        };
+
+        let deadline = core::time::Duration::from_secs(1);
+        let topology = discover_topology(&bus(&server), &[(Mux::M1, 2)], deadline, &clock);
+        assert!(!topology.truncated);
+        assert_eq!(topology.devices(None), Some(&[SevenBitAddr(0x10)][..]));
+        assert_eq!(topology.devices(s1), Some(&[SevenBitAddr(0x20)][..]));
+        assert_eq!(
+            topology.devices(s2),
+            Some(&[SevenBitAddr(0x21), SevenBitAddr(0x50)][..])
+        );
+        assert_eq!(topology.devices(Some((Mux::M1, Segment::S3))), None);
+
+        // 112 probes per segment, and nothing beyond the two segments asked for
+        let routes = server.routes();
+        assert_eq!(routes.len(), 3 * 112);
+        assert!(routes[2 * 112..].iter().all(|&route| route == s2));
+    }
+
+    #[test]
+    fn topology_stops_at_the_deadline() {
+        let server = FakeServer::new().with_present(&[(None, 0x08), (None, 0x09), (None, 0x70)]);
+        let clock = SteppingClock {
+            now: core::cell::Cell::new(core::time::Duration::ZERO),
+            step: core::time::Duration::from_millis(1),
+        };
+
+        // The start time takes one reading, then one per probe
+        let deadline = core::time::Duration::from_millis(3);
+        let topology = discover_topology(&bus(&server), &[(Mux::M1, 8)], deadline, &clock);
+        assert!(topology.truncated);
+        assert_eq!(
+            topology.devices(None),
+            Some(&[SevenBitAddr(0x08), SevenBitAddr(0x09)][..])
+        );
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[test]
+    fn scan_and_identify_tolerates_an_id_nack() {
+        let server = FakeServer::new()
+            .with_present(&[(None, 0x1D), (None, 0x6A)])
+            .reply(&[])
+            .reply(&[0x33])
+            .reply(&[])
+            .fail(ResponseCode::AddressNackSentLate);
+        let mut out = [(SevenBitAddr(0), None); 4];
+
+        assert_eq!(scan_and_identify(&bus(&server), 0x0F, &mut out), 2);
+        assert_eq!(out[..2], [(SevenBitAddr(0x1D), Some(0x33)), (SevenBitAddr(0x6A), None)]);
+
+        let id_reads: Vec<_> = server
+            .requests()
+            .into_iter()
+            .filter(|request| matches!(request, Request::ReadReg { .. }))
+            .collect();
+        assert_eq!(
+            id_reads,
+            [
+                Request::ReadReg { address: 0x1D, reg: std::vec![0x0F], len: 1 },
+                Request::ReadReg { address: 0x6A, reg: std::vec![0x0F], len: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn drain_fifo_reads_until_empty() {
+        let server = FakeServer::new()
+            .reply(&[0x00])
+            .reply(&[0xA1])
+            .reply(&[0x00])
+            .reply(&[0xA2])
+            .reply(&[0x00])
+            .reply(&[0xA3])
+            .reply(&[0x04]);
+        let mut out = [0u8; 8];
+
+        assert_eq!(handle(&server).drain_fifo(0x0B, 2, 0x0C, &mut out), Ok(3));
+        assert_eq!(out[..3], [0xA1, 0xA2, 0xA3]);
+        assert_eq!(server.requests().len(), 7);
+    }
+
+    #[test]
+    fn drain_fifo_keeps_the_count_on_error() {
+        let server = FakeServer::new()
+            .reply(&[0x00])
+            .reply(&[0xA1])
+            .reply(&[0x00])
+            .fail(ResponseCode::BusLocked);
+        let mut out = [0u8; 8];
+
+        let err = handle(&server).drain_fifo(0x0B, 2, 0x0C, &mut out).unwrap_err();
+        assert_eq!(err.failed_index, 1);
+        assert_eq!(err.source.response_code, ResponseCode::BusLocked);
+        assert_eq!(err.source.operation, "drain_fifo");
+        assert_eq!(out[0], 0xA1);
+    }
+
+    #[test]
+    fn drain_fifo_rejects_an_out_of_range_bit() {
+        let server = FakeServer::new();
+        let mut out = [0u8; 8];
+
+        let err = handle(&server).drain_fifo(0x0B, 8, 0x0C, &mut out).unwrap_err();
+        assert_eq!(err.failed_index, 0);
+        assert_eq!(err.source.response_code, ResponseCode::BadArg);
+        assert_eq!(err.source.operation, "drain_fifo_bit");
+        assert!(server.requests().is_empty());
+    }
+
+    /// Records delays instead of sleeping
+    #[derive(Default)]
+    struct FakeDelay {
+        total: core::time::Duration,
+    }
+
+    impl DelayProvider for FakeDelay {
+        fn delay(&mut self, duration: core::time::Duration) {
+            self.total += duration;
+        }
+    }
+
+    const MS: core::time::Duration = core::time::Duration::from_millis(1);
+
+    #[test]
+    fn poll_until_immediate_and_eventual() {
+        let ready = |value: u8| value & 0x80 != 0;
+
+        let server = FakeServer::new().reply(&[0x81]);
+        let mut delay = FakeDelay::default();
+        assert_eq!(handle(&server).poll_until(0x07, ready, MS, 10 * MS, &mut delay), Ok(0x81));
+        assert_eq!(delay.total, core::time::Duration::ZERO);
+
+        let server = FakeServer::new().reply(&[0x00]).reply(&[0x01]).reply(&[0x80]);
+        let mut delay = FakeDelay::default();
+        assert_eq!(handle(&server).poll_until(0x07, ready, MS, 10 * MS, &mut delay), Ok(0x80));
+        assert_eq!(delay.total, 2 * MS);
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[test]
+    fn poll_until_times_out() {
+        let server = FakeServer::new();
+        let mut delay = FakeDelay::default();
+        let err = handle(&server)
+            .poll_until(0x07, |value| value != 0, 2 * MS, 5 * MS, &mut delay)
+            .unwrap_err();
+        assert_eq!(err.response_code, ResponseCode::BusTimeout);
+        assert_eq!(err.detail, Some(ErrorDetail::Timeout { waited: 6 * MS }));
+        assert_eq!(server.requests().len(), 4);
+    }
+
+    #[test]
+    fn poll_until_change_tells_timeouts_from_read_errors() {
+        let server = FakeServer::new().reply(&[0x05]).reply(&[0x05]);
+        let mut delay = FakeDelay::default();
+        let err = handle(&server)
+            .poll_until_change(0x07, 0x05, MS, MS, &mut delay)
+            .unwrap_err();
+        assert_eq!(err.operation, "poll_until_change_timeout");
+
+        let server = FakeServer::new().fail(ResponseCode::BusTimeout);
+        let err = handle(&server)
+            .poll_until_change(0x07, 0x05, MS, MS, &mut delay)
+            .unwrap_err();
+        assert_eq!(err.response_code, ResponseCode::BusTimeout);
+        assert_eq!(err.operation, "poll_until_change");
+
+        let server = FakeServer::new().reply(&[0x05]).reply(&[0x06]);
+        assert_eq!(handle(&server).poll_until_change(0x07, 0x05, MS, MS, &mut delay), Ok(0x06));
+    }
+
+    #[test]
+    fn mock_tracks_interleaved_drivers() {
+        let mut mock = MockI2c::new();
+        mock.expect_write(SevenBitAddr(0x48), &[0x01, 0x60]);
+        mock.expect_write(SevenBitAddr(0x48), &[0x02]);
+        mock.expect_write(SevenBitAddr(0x50), &[0x00, 0x10]);
+        mock.expect_failure(SevenBitAddr(0x50), ErrorKind::Bus);
+
+        // Driver A
+        mock.write(SevenBitAddr(0x48), &[0x01, 0x60]).unwrap();
+        mock.write(SevenBitAddr(0x48), &[0x02]).unwrap();
+        assert_eq!(mock.snapshot_progress(), 2);
+
+        // Driver B, whose second write fails as scripted
+        mock.write(SevenBitAddr(0x50), &[0x00, 0x10]).unwrap();
+        let err = mock.write(SevenBitAddr(0x50), &[0x00]).unwrap_err();
+        assert!(err.is_scripted());
+        assert_eq!(mock.snapshot_progress(), 4);
+
+        mock.assert_consumed_in_order();
+        mock.verify_complete();
+    }
+
+    #[test]
+    #[should_panic(expected = "I2C operation 1 did not match")]
+    fn mock_remembers_a_swallowed_mismatch() {
+        let mut mock = MockI2c::new();
+        mock.expect_write(SevenBitAddr(0x48), &[0x01]);
+        mock.expect_write(SevenBitAddr(0x50), &[0x02]);
+
+        mock.write(SevenBitAddr(0x48), &[0x01]).unwrap();
+        // Driver B runs out of turn and drops the error
+        let err = mock.write(SevenBitAddr(0x48), &[0x02]).unwrap_err();
+        assert!(!err.is_scripted());
+
+        mock.assert_consumed_in_order();
+    }
+
+    #[test]
+    fn stack_builder_nests_in_the_recommended_order() {
+        let server = FakeServer::new();
+        let clock = SteppingClock {
+            now: core::cell::Cell::new(core::time::Duration::ZERO),
+            step: MS,
+        };
+        let mut stack: RetryingI2c<TimedI2c<CountingI2c<_>, _>> =
+            I2cStackBuilder::new(handle(&server))
+                .with_timing(clock)
+                .with_retries(2)
+                .with_counting()
+                .build();
+
+        let mut buffer = [0u8; 2];
+        stack.write_read(SevenBitAddr(0x48), &[0x05], &mut buffer).unwrap();
+        assert_eq!(stack.inner().inner().stats().write_reads, 1);
+        assert_eq!(
+            server.requests(),
+            [Request::ReadReg { address: 0x48, reg: std::vec![0x05], len: 2 }]
+        );
+    }
+
+    #[test]
+    fn stack_builder_wraps_a_mock() {
+        let mut mock = MockI2c::new();
+        mock.expect_write(SevenBitAddr(0x48), &[0x01, 0x60]);
+        mock.expect_read(SevenBitAddr(0x48), &[0x7F]);
+
+        // Counting ends up inside the retries whichever is asked for first
+        let mut stack: RetryingI2c<CountingI2c<MockI2c>> =
+            I2cStackBuilder::new(mock).with_counting().with_retries(3).build();
+        let mut buffer = [0u8; 1];
+        stack.write(SevenBitAddr(0x48), &[0x01, 0x60]).unwrap();
+        stack.read(SevenBitAddr(0x48), &mut buffer).unwrap();
+
+        assert_eq!(buffer, [0x7F]);
+        assert_eq!(stack.inner().stats().writes, 1);
+        assert_eq!(stack.inner().stats().reads, 1);
+        stack.inner().inner().verify_complete();
+    }
+
+    /// A driver written against the trait, as a harness would use one
+    fn read_id<I2C>(bus: &mut I2C) -> Result<u8, I2C::Error>
+    where
+        I2C: embedded_hal::i2c::I2c<SevenBitAddr>,
+    {
+        let mut id = [0xFF];
+        bus.write_read(SevenBitAddr(0x48), &[0x0F], &mut id)?;
+        Ok(id[0])
+    }
+
+    #[test]
+    fn null_bus_accepts_everything_and_reads_zeros() {
+        let mut bus = NullI2c::<MockI2cError>::new();
+        assert_eq!(read_id(&mut bus).unwrap(), 0);
+
+        let mut buf = [0xAA; 2];
+        bus.transaction(
+            SevenBitAddr(0x48),
+            &mut [Operation::Write(&[1, 2]), Operation::Read(&mut buf)],
+        )
+        .unwrap();
+        assert_eq!(buf, [0, 0]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn boxed_buses_swap_at_runtime() {
+        let mut mock = MockI2c::new();
+        mock.expect_write_read(SevenBitAddr(0x48), &[0x0F], &[0x33]);
+
+        let mut buses: Vec<BoxedI2c<MockI2cError>> =
+            std::vec![BoxedI2c::new(mock), BoxedI2c::new(NullI2c::<MockI2cError>::new())];
+        let ids: Vec<u8> = buses.iter_mut().map(|bus| read_id(bus).unwrap()).collect();
+        assert_eq!(ids, [0x33, 0x00]);
+    }
+
+    #[test]
+    fn hubris_delay_rounds_up_and_pads_one_tick() {
+        use embedded_hal::delay::DelayNs;
+
+        let slept = RefCell::new(Vec::new());
+        let mut delay = HubrisDelay::with_sleep(|d: core::time::Duration| {
+            slept.borrow_mut().push(d.as_millis())
+        });
+        delay.delay_ns(0);
+        delay.delay_ns(1);
+        delay.delay_us(1500);
+        delay.delay_ms(5);
+        assert_eq!(*slept.borrow(), [2, 3, 6]);
+    }
+
+    #[test]
+    fn snapshot_diffs_against_golden_map() {
+        let server = FakeServer::new().reply(&[0x11]).reply(&[0x22]).reply(&[0x30]);
+        let i2c = handle(&server);
+
+        let actual = i2c.snapshot_registers::<4>(&[0x01, 0x02, 0x03]).unwrap();
+        assert_eq!(
+            server.requests(),
+            [0x01, 0x02, 0x03].map(|reg| Request::ReadReg {
+                address: 0x48,
+                reg: std::vec![reg],
+                len: 1,
+            })
+        );
+
+        let mut expected = heapless::FnvIndexMap::<u8, u8, 8>::new();
+        for (reg, value) in [(0x01, 0x11), (0x02, 0x22), (0x03, 0x33), (0x04, 0x44)] {
+            expected.insert(reg, value).unwrap();
+        }
+        assert_eq!(
+            diff_registers(&expected, &actual),
+            [(0x03, 0x33, Some(0x30)), (0x04, 0x44, None)]
+        );
+    }
+
+    #[test]
+    fn replay_reissues_each_operation_in_order() {
+        let server = FakeServer::new();
+        let mut i2c = handle(&server);
+        let trace = [
+            RecordedOp::Write { address: SevenBitAddr(0x48), bytes: &[0x01, 0x60] },
+            RecordedOp::Read { address: SevenBitAddr(0x48), len: 2 },
+            RecordedOp::WriteRead { address: SevenBitAddr(0x48), bytes: &[0x00], len: 2 },
+        ];
+
+        replay_trace(&mut i2c, &trace).unwrap();
+        assert_eq!(
+            server.requests(),
+            [
+                Request::Write { address: 0x48, bytes: std::vec![0x01, 0x60] },
+                Request::Read { address: 0x48, len: 2 },
+                Request::ReadReg { address: 0x48, reg: std::vec![0x00], len: 2 },
+            ]
+        );
+    }
+
+    #[test]
+    fn replay_stops_at_the_first_failure() {
+        let server = FakeServer::new().reply(&[]).fail(ResponseCode::NoDevice);
+        let mut i2c = handle(&server);
+        let trace = [
+            RecordedOp::Write { address: SevenBitAddr(0x48), bytes: &[0x01] },
+            RecordedOp::Read { address: SevenBitAddr(0x48), len: 1 },
+            RecordedOp::Write { address: SevenBitAddr(0x48), bytes: &[0x02] },
+        ];
+
+        let err = replay_trace(&mut i2c, &trace).unwrap_err();
+        assert_eq!(err.failed_index, 1);
+        assert_eq!(err.source.response_code, ResponseCode::NoDevice);
+        assert_eq!(server.requests().len(), 2);
+
+        let long = [RecordedOp::Read { address: SevenBitAddr(0x48), len: MAX_TRANSFER_LEN + 1 }];
+        let err = replay_trace(&mut i2c, &long).unwrap_err();
+        assert_eq!(err.source.response_code, ResponseCode::TooMuchData);
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[test]
+    fn unique_addresses_are_checked_pairwise() {
+        const _: () = assert!(assert_unique_addresses(&[0x48, 0x49, 0x4A]));
+        assert!(assert_unique_addresses(&[]));
+        assert!(!assert_unique_addresses(&[0x48, 0x49, 0x48]));
+        assert!(!assert_unique_addresses(&[0x20, 0x20]));
+    }
+
+    #[test]
+    fn error_codes_are_pinned() {
+        let code = |response_code, operation| {
+            HubrisI2cError { response_code, operation, bus: None, detail: None }.code()
+        };
+
+        assert_eq!(code(ResponseCode::NoDevice, "read"), 0x032A);
+        assert_eq!(code(ResponseCode::NoDevice, "write"), 0x0362);
+        assert_eq!(code(ResponseCode::BadResponse, "read_register_map"), 0x0137);
+        assert_eq!(code(ResponseCode::BadResponse, "not_an_operation"), 0x0100);
+    }
+
+    #[test]
+    fn operation_ids_are_distinct_and_fit_a_byte() {
+        assert!(OPERATION_IDS.len() <= usize::from(u8::MAX));
+        for (i, name) in OPERATION_IDS.iter().enumerate() {
+            assert!(!OPERATION_IDS[..i].contains(name), "{} listed twice", name);
+        }
+    }
+
+    #[test]
+    fn addresses_validate_through_try_from() {
+        assert_eq!(SevenBitAddr::try_from(0x48), Ok(SevenBitAddr(0x48)));
+        assert_eq!(SevenBitAddr::try_from(0x80), Err(InvalidAddress::SevenBitRange(0x80)));
+        assert_eq!(SevenBitAddr::try_from(0x03), Err(InvalidAddress::Reserved(0x03)));
+        assert_eq!(u8::from(SevenBitAddr(0x48)), 0x48);
+
+        assert_eq!(TenBitAddr::try_from(0x3FF), Ok(TenBitAddr(0x3FF)));
+        assert_eq!(TenBitAddr::try_from(0x400), Err(InvalidAddress::TenBitRange(0x400)));
+        assert_eq!(u16::from(TenBitAddr(0x150)), 0x150);
+
+        let err: HubrisI2cError = SevenBitAddr::try_from(0x80).unwrap_err().into();
+        assert_eq!(err.operation, "invalid_address_7bit_range");
+    }
+
+    #[test]
+    fn timed_wrapper_records_round_trips_per_kind() {
+        let server = FakeServer::new().fail(ResponseCode::NoDevice);
+        let clock = SteppingClock {
+            now: core::cell::Cell::new(core::time::Duration::ZERO),
+            step: 2 * MS,
+        };
+        let mut i2c = TimedI2c::new(handle(&server), clock);
+
+        assert!(i2c.write(SevenBitAddr(0x48), &[0x01]).is_err());
+        i2c.write(SevenBitAddr(0x48), &[0x02]).unwrap();
+        i2c.read(SevenBitAddr(0x48), &mut [0u8; 2]).unwrap();
+
+        let latencies = *i2c.latencies();
+        assert_eq!(latencies.write.count, 2);
+        assert_eq!(latencies.write.max, 2 * MS);
+        assert_eq!(latencies.read.count, 1);
+        assert_eq!(latencies.write_read.count, 0);
+        assert_eq!(server.requests().len(), 3);
+
+        i2c.reset_latencies();
+        assert_eq!(*i2c.latencies(), Latencies::default());
+    }
+
+    #[test]
+    fn fifo_reader_sets_the_pointer_once() {
+        let server = FakeServer::new().reply(&[]).reply(&[0x10, 0x11]).reply(&[0x12, 0x13]);
+        let i2c = handle(&server);
+
+        let mut fifo = i2c.fifo_reader(0x3F).unwrap();
+        let mut sample = [0u8; 2];
+        fifo.next(&mut sample).unwrap();
+        assert_eq!(sample, [0x10, 0x11]);
+        fifo.next(&mut sample).unwrap();
+        assert_eq!(sample, [0x12, 0x13]);
+
+        assert_eq!(
+            server.requests(),
+            [
+                Request::Write { address: 0x48, bytes: std::vec![0x3F] },
+                Request::Read { address: 0x48, len: 2 },
+                Request::Read { address: 0x48, len: 2 },
+            ]
+        );
+    }
+
+    #[test]
+    fn static_spec_connects_to_its_device() {
+        static SPECS: [HubrisI2cSpec; 2] = [
+            HubrisI2cSpec::new_simple(Controller::I2c1, PortIndex(0), 0x48),
+            HubrisI2cSpec::new(Controller::I2c2, PortIndex(1), Some((Mux::M1, Segment::S3)), 0x50),
+        ];
+
+        let i2c = SPECS[0].connect(TaskId::KERNEL);
+        assert_eq!(i2c.address(), SevenBitAddr(0x48));
+        assert_eq!(i2c.segment(), None);
+
+        let i2c = SPECS[1].connect(TaskId::KERNEL);
+        assert_eq!(i2c.address(), SevenBitAddr(0x50));
+        assert_eq!(i2c.controller(), Controller::I2c2);
+        assert_eq!(i2c.port(), PortIndex(1));
+        assert_eq!(i2c.segment(), Some((Mux::M1, Segment::S3)));
+    }
+
+    #[test]
+    #[should_panic(expected = "reserved")]
+    fn spec_rejects_a_reserved_address() {
+        HubrisI2cSpec::new_simple(Controller::I2c1, PortIndex(0), 0x78);
+    }
+
+    #[test]
+    fn retry_budget_limits_each_error_kind() {
+        let mut mock = MockI2c::new();
+        mock.expect_failure(SevenBitAddr(0x48), ErrorKind::ArbitrationLoss);
+        mock.expect_failure(SevenBitAddr(0x48), ErrorKind::ArbitrationLoss);
+        mock.expect_read(SevenBitAddr(0x48), &[0x5A]);
+        mock.expect_failure(SevenBitAddr(0x48), ErrorKind::Bus);
+
+        let budget = RetryBudget { arbitration_loss: 2, ..RetryBudget::new(0) };
+        let mut i2c = RetryingI2c::new(mock, 3)
+            .with_budget(budget)
+            .with_delay(FakeDelay::default());
+
+        let mut byte = [0u8];
+        i2c.read(SevenBitAddr(0x48), &mut byte).unwrap();
+        assert_eq!(byte, [0x5A]);
+        assert_eq!(i2c.delay.total, 30 * MS);
+
+        // Bus errors get no retries under this budget
+        let err = i2c.read(SevenBitAddr(0x48), &mut byte).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Bus);
+        assert_eq!(i2c.delay.total, 30 * MS);
+        i2c.inner().verify_complete();
+    }
+
+    #[test]
+    fn udid_decodes_big_endian_fields() {
+        let bytes: [u8; 17] = [
+            0xC1, 0x08, 0x12, 0x34, 0x56, 0x78, 0x00, 0x04, 0x9A, 0xBC, 0xDE, 0xF0, 0x01, 0x02,
+            0x03, 0x04, 0xFF,
+        ];
+        let udid = smbus_arp::Udid::decode(&bytes).unwrap();
+        assert_eq!(udid.device_capabilities(), 0xC1);
+        assert_eq!(udid.version_revision(), 0x08);
+        assert_eq!(udid.vendor_id(), 0x1234);
+        assert_eq!(udid.device_id(), 0x5678);
+        assert_eq!(udid.interface(), 0x0004);
+        assert_eq!(udid.subsystem_vendor_id(), 0x9ABC);
+        assert_eq!(udid.subsystem_device_id(), 0xDEF0);
+        assert_eq!(udid.vendor_specific_id(), 0x0102_0304);
+
+        assert_eq!(smbus_arp::Udid::decode(&bytes[..15]), None);
+    }
+
+    #[test]
+    fn arp_commands_go_to_the_default_address() {
+        let mut reply = [0u8; 17];
+        reply[2] = 0x12;
+        reply[16] = 0x61 << 1;
+        let server = FakeServer::new().reply(&[]).reply(&reply).reply(&[]).reply(&[0; 16]);
+        let arp = smbus_arp::SmbusArp::new(TaskId::KERNEL, Controller::I2c1, PortIndex(0), None)
+            .with_backend(&server);
+
+        arp.prepare_to_arp().unwrap();
+        let udid = arp.get_udid().unwrap();
+        assert_eq!(udid.vendor_id(), 0x1200);
+        arp.assign_address(&udid, SevenBitAddr(0x30)).unwrap();
+
+        let mut assign = std::vec![0x04, 17];
+        assign.extend_from_slice(&udid.0);
+        assign.push(0x30 << 1);
+        assert_eq!(
+            server.requests(),
+            [
+                Request::Write { address: 0x61, bytes: std::vec![0x01] },
+                Request::ReadBlock { address: 0x61, reg: std::vec![0x03], len: 17 },
+                Request::Write { address: 0x61, bytes: assign },
+            ]
+        );
+
+        // A 16-byte reply is missing the address byte
+        let err = arp.get_udid().unwrap_err();
+        assert!(err.is_protocol_error());
+        assert_eq!(err.operation, "arp_get_udid_length");
+    }
+
+    #[test]
+    fn read_bit_tests_one_bit_of_the_register() {
+        let server = FakeServer::new().reply(&[0b0000_1000]).reply(&[0b0000_1000]);
+        let i2c = handle(&server);
+
+        assert!(i2c.read_bit(0x27, 3).unwrap());
+        assert!(!i2c.read_bit(0x27, 2).unwrap());
+        assert_eq!(
+            server.requests()[0],
+            Request::ReadReg { address: 0x48, reg: std::vec![0x27], len: 1 }
+        );
+    }
+
+    #[test]
+    fn redundant_fails_over_on_bus_errors_but_not_nacks() {
+        let primary = FakeServer::new().fail(ResponseCode::BusError);
+        let secondary = FakeServer::new().reply(&[]).fail(ResponseCode::AddressNackSentEarly);
+        let mut i2c = RedundantI2c::new(handle(&primary), handle(&secondary));
+        assert!(i2c.is_on_primary());
+
+        i2c.write(SevenBitAddr(0x48), &[0x01]).unwrap();
+        assert!(!i2c.is_on_primary());
+        assert_eq!(primary.requests().len(), 1);
+        assert_eq!(secondary.requests().len(), 1);
+
+        let err = i2c.write(SevenBitAddr(0x48), &[0x02]).unwrap_err();
+        assert!(err.is_device_not_found());
+        assert!(!i2c.is_on_primary());
+        assert_eq!(primary.requests().len(), 1);
+        assert_eq!(secondary.requests().len(), 2);
+    }
+
+    #[test]
+    fn redundant_never_replays_reset_writes_or_rejected_requests() {
+        let primary = FakeServer::new()
+            .fail(ResponseCode::BusReset)
+            .fail(ResponseCode::BadArg)
+            .fail(ResponseCode::BusResetMux);
+        let secondary = FakeServer::new();
+        let mut i2c = RedundantI2c::new(handle(&primary), handle(&secondary));
+
+        let err = i2c.write(SevenBitAddr(0x48), &[0x01, 0x02]).unwrap_err();
+        assert!(err.is_bus_reset());
+        let err = i2c.write(SevenBitAddr(0x48), &[0x01, 0x02]).unwrap_err();
+        assert_eq!(err.response_code, ResponseCode::BadArg);
+        assert!(secondary.requests().is_empty());
+
+        // A read interrupted by a reset is safe to issue again
+        let mut byte = [0u8];
+        i2c.read(SevenBitAddr(0x48), &mut byte).unwrap();
+        assert!(!i2c.is_on_primary());
+        assert_eq!(primary.requests().len(), 3);
+        assert_eq!(secondary.requests().len(), 1);
+    }
+
+    #[test]
+    fn reset_all_writes_to_the_general_call_address() {
+        let server = FakeServer::new().reply(&[]).fail(ResponseCode::AddressNackSentEarly);
+        let i2c = handle(&server);
+
+        i2c.smbus_reset_all().unwrap();
+        assert_eq!(server.requests(), [Request::Write { address: 0x00, bytes: std::vec![0x06] }]);
+
+        let err = i2c.smbus_reset_all().unwrap_err();
+        assert!(err.is_device_not_found());
+        assert_eq!(err.operation, "smbus_reset_all");
+    }
+
+    #[test]
+    fn accessors_report_the_configured_routing() {
+        let segment = Some((Mux::M2, Segment::S4));
+        let i2c = HubrisI2c::new(TaskId::KERNEL, Controller::I2c3, PortIndex(2), segment, 0x1D);
+
+        assert_eq!(i2c.address(), SevenBitAddr(0x1D));
+        assert_eq!(i2c.controller(), Controller::I2c3);
+        assert_eq!(i2c.port(), PortIndex(2));
+        assert_eq!(i2c.segment(), segment);
+    }
+
+    #[test]
+    fn isolated_reads_keep_going_past_a_failure() {
+        let server = FakeServer::new().reply(&[0x11]).fail(ResponseCode::NoRegister).reply(&[0x33]);
+        let i2c = handle(&server);
+
+        let mut out = [Ok(0); 3];
+        i2c.read_registers_isolated(&[0x01, 0x02, 0x03], &mut out).unwrap();
+        assert_eq!(out[0], Ok(0x11));
+        assert_eq!(out[1].unwrap_err().response_code, ResponseCode::NoRegister);
+        assert_eq!(out[2], Ok(0x33));
+        assert_eq!(server.requests().len(), 3);
+
+        let err = i2c.read_registers_isolated(&[0x01], &mut out).unwrap_err();
+        assert_eq!(err.response_code, ResponseCode::BadArg);
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[test]
+    fn pec_matches_the_crc8_check_value() {
+        assert_eq!(smbus_pec(b"123456789"), 0xF4);
+        assert_eq!(smbus_pec(&[]), 0x00);
+
+        let mut pec = PecAccumulator::new();
+        pec.update(b"1234");
+        pec.update(b"56789");
+        assert_eq!(pec.finish(), 0xF4);
+    }
+
+    #[test]
+    fn protocol_errors_are_bad_responses_only() {
+        let server = FakeServer::new().fail(ResponseCode::BadResponse).fail(ResponseCode::NoDevice);
+        let i2c = handle(&server);
+
+        assert!(i2c.read_register::<u8, u8>(0x00).unwrap_err().is_protocol_error());
+        let err = i2c.read_register::<u8, u8>(0x00).unwrap_err();
+        assert!(!err.is_protocol_error());
+        assert!(err.is_device_not_found());
+    }
+
+    #[test]
+    fn mock_lists_unconsumed_expectations_in_order() {
+        let mut mock = MockI2c::new();
+        mock.expect_write(SevenBitAddr(0x48), &[0x01]);
+        mock.expect_read(SevenBitAddr(0x48), &[0xAA]);
+        mock.expect_write(SevenBitAddr(0x49), &[0x02]);
+        assert_eq!(mock.remaining().len(), 3);
+
+        mock.write(SevenBitAddr(0x48), &[0x01]).unwrap();
+        let remaining = mock.remaining();
+        assert_eq!(remaining.len(), 2);
+        assert!(matches!(remaining[0], mock::MockOperation::Read { .. }));
+        assert!(matches!(
+            remaining[1],
+            mock::MockOperation::Write { address: SevenBitAddr(0x49), .. }
+        ));
+    }
+
+    #[test]
+    fn layers_peel_back_to_the_base() {
+        let server = FakeServer::new().reply(&[]).reply(&[0x42]);
+        let clock = SteppingClock {
+            now: core::cell::Cell::new(core::time::Duration::ZERO),
+            step: MS,
+        };
+        let base = RegisterOptimizedI2c::new(handle(&server));
+        let mut stack = TimedI2c::new(CountingI2c::new(base), clock);
+
+        stack.write(SevenBitAddr(0x48), &[0x10]).unwrap();
+        assert_eq!(stack.inner().stats().writes, 1);
+        assert_eq!(stack.inner().inner().read_register::<u8>(0x00), Ok(0x42));
+        assert_eq!(stack.inner().inner().inner().address(), SevenBitAddr(0x48));
+
+        stack.inner_mut().reset_stats();
+        assert_eq!(stack.inner().stats().writes, 0);
+    }
+
+    #[test]
+    fn address_only_write_sends_no_payload() {
+        let server = FakeServer::new().reply(&[]).fail(ResponseCode::AddressNackSentEarly);
+        let i2c = handle(&server);
+
+        i2c.write_address_only().unwrap();
+        assert!(i2c.write_address_only().unwrap_err().is_device_not_found());
+        assert_eq!(
+            server.requests(),
+            [
+                Request::Write { address: 0x48, bytes: Vec::new() },
+                Request::Write { address: 0x48, bytes: Vec::new() },
+            ]
+        );
+    }
+
+    #[test]
+    fn tolerant_write_reports_nacks_as_false() {
+        let server = FakeServer::new()
+            .reply(&[])
+            .fail(ResponseCode::AddressNackSentEarly)
+            .fail(ResponseCode::DataNackSent)
+            .fail(ResponseCode::BusLocked);
+        let i2c = handle(&server);
+
+        assert_eq!(i2c.write_tolerant(&[0x01]), Ok(true));
+        assert_eq!(i2c.write_tolerant(&[0x01]), Ok(false));
+        assert_eq!(i2c.write_tolerant(&[0x01]), Ok(false));
+        assert_eq!(i2c.write_tolerant(&[0x01]).unwrap_err().response_code, ResponseCode::BusLocked);
+    }
+
+    #[cfg(feature = "target-mode")]
+    #[test]
+    fn target_mode_is_refused_by_the_stock_server() {
+        let address = SevenBitAddr(0x42);
+        let target = HubrisI2cTarget::new(TaskId::KERNEL, Controller::I2c1, PortIndex(0), address);
+        let err = target.err().unwrap();
+        assert_eq!(err.response_code, ResponseCode::OperationNotSupported);
+        assert_eq!(err.unsupported_feature(), Some(UnsupportedFeature::TargetMode));
+
+        let server = FakeServer::new();
+        let err = HubrisI2cTarget::from_handle(handle(&server)).err().unwrap();
+        assert_eq!(err.unsupported_feature(), Some(UnsupportedFeature::TargetMode));
+        assert!(server.requests().is_empty());
+    }
+
+    #[cfg(feature = "target-mode")]
+    #[test]
+    fn target_requests_go_through_the_backend() {
+        let server = FakeServer::new()
+            .with_caps(Capabilities::TARGET_MODE)
+            .reply(&[1])
+            .reply(&[0x10, 0x20])
+            .reply(&[0])
+            .reply(&[])
+            .fail(ResponseCode::BusError);
+        let mut target = HubrisI2cTarget::from_handle(handle(&server).with_name("bmc")).unwrap();
+        assert_eq!(target.address(), SevenBitAddr(0x48));
+
+        assert_eq!(target.listen(), Ok(TargetEvent::WriteRequested));
+        let mut buffer = [0u8; 4];
+        assert_eq!(target.accept_write(&mut buffer), Ok(2));
+        assert_eq!(buffer[..2], [0x10, 0x20]);
+        assert_eq!(target.listen(), Ok(TargetEvent::ReadRequested));
+        target.respond_read(&[0xAB]).unwrap();
+
+        let err = target.listen().unwrap_err();
+        assert_eq!((err.operation, err.bus), ("target_listen", Some("bmc")));
+
+        assert_eq!(
+            server.requests(),
+            [
+                Request::TargetListen { address: 0x48 },
+                Request::TargetAccept { address: 0x48, len: 4 },
+                Request::TargetListen { address: 0x48 },
+                Request::TargetRespond { address: 0x48, bytes: std::vec![0xAB] },
+                Request::TargetListen { address: 0x48 },
+            ]
+        );
+    }
+
+    #[test]
+    fn configured_byte_order_converts_register_values() {
+        let server = FakeServer::new()
+            .reply(&[0x12, 0x34])
+            .reply(&[0x12, 0x34])
+            .reply(&[0x12, 0x34]);
+
+        let i2c = handle(&server).with_byte_order(ByteOrder::Big);
+        assert_eq!(i2c.byte_order(), Some(ByteOrder::Big));
+        assert_eq!(i2c.read_register::<u8, u16>(0x00), Ok(0x1234));
+
+        let i2c = handle(&server).with_byte_order(ByteOrder::Little);
+        assert_eq!(i2c.read_register::<u8, u16>(0x00), Ok(0x3412));
+
+        let i2c = handle(&server);
+        assert_eq!(i2c.byte_order(), None);
+        assert_eq!(i2c.read_register::<u8, u16>(0x00), Ok(u16::from_ne_bytes([0x12, 0x34])));
+    }
+
+    #[test]
+    fn explicit_byte_order_reads_ignore_the_configured_order() {
+        let server = FakeServer::new()
+            .reply(&[0x12, 0x34])
+            .reply(&[0x12, 0x34, 0x56, 0x78])
+            .reply(&[0x12, 0x34])
+            .reply(&[0x12, 0x34, 0x56, 0x78]);
+        let i2c = handle(&server).with_byte_order(ByteOrder::Little);
+
+        assert_eq!(i2c.read_register_be::<u8, u16>(0x02), Ok(0x1234));
+        assert_eq!(i2c.read_register_be::<u8, u32>(0x04), Ok(0x1234_5678));
+        assert_eq!(i2c.read_register_le::<u8, u16>(0x02), Ok(0x3412));
+        assert_eq!(i2c.read_register_le::<u8, u32>(0x04), Ok(0x7856_3412));
+        assert_eq!(
+            server.requests()[1],
+            Request::ReadReg { address: 0x48, reg: std::vec![0x04], len: 4 }
+        );
+    }
+
+    #[test]
+    fn views_issue_only_their_own_direction() {
+        let server = FakeServer::new().reply(&[0xAB]).reply(&[0xCD]);
+        let i2c = handle(&server);
+
+        let mut byte = [0u8];
+        i2c.read_only().read(&mut byte).unwrap();
+        assert_eq!(byte, [0xAB]);
+        i2c.read_only().read_reg_into(0x0F, &mut byte).unwrap();
+        assert_eq!(byte, [0xCD]);
+        i2c.write_only().write(&[0x01, 0x80]).unwrap();
+        i2c.write_only().write(&[]).unwrap();
+
+        assert_eq!(
+            server.requests(),
+            [
+                Request::Read { address: 0x48, len: 1 },
+                Request::ReadReg { address: 0x48, reg: std::vec![0x0F], len: 1 },
+                Request::Write { address: 0x48, bytes: std::vec![0x01, 0x80] },
+                Request::Write { address: 0x48, bytes: Vec::new() },
+            ]
+        );
+    }
+
+    #[test]
+    fn init_writes_config_only_for_the_expected_id() {
+        let config = [(0x20, 0x47), (0x23, 0x08)];
+
+        let server = FakeServer::new().reply(&[0x33]);
+        assert_eq!(handle(&server).init_if_present(0x0F, 0x33, &config), Ok(true));
+        assert_eq!(
+            server.requests()[1..],
+            [
+                Request::Write { address: 0x48, bytes: std::vec![0x20, 0x47] },
+                Request::Write { address: 0x48, bytes: std::vec![0x23, 0x08] },
+            ]
+        );
+
+        let server = FakeServer::new().reply(&[0x44]);
+        assert_eq!(handle(&server).init_if_present(0x0F, 0x33, &config), Ok(false));
+        assert_eq!(server.requests().len(), 1);
+
+        let server = FakeServer::new().fail(ResponseCode::AddressNackSentEarly);
+        assert_eq!(handle(&server).init_if_present(0x0F, 0x33, &config), Ok(false));
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[test]
+    fn reattach_swaps_only_the_server_task() {
+        let segment = Some((Mux::M1, Segment::S2));
+        let mut i2c = HubrisI2c::new(TaskId(1), Controller::I2c2, PortIndex(1), segment, 0x48)
+            .with_byte_order(ByteOrder::Big);
+
+        i2c.reattach(TaskId(2));
+        assert_eq!(i2c.device().task, TaskId(2));
+        assert_eq!(i2c.controller(), Controller::I2c2);
+        assert_eq!(i2c.port(), PortIndex(1));
+        assert_eq!(i2c.segment(), segment);
+        assert_eq!(i2c.address(), SevenBitAddr(0x48));
+        assert_eq!(i2c.byte_order(), Some(ByteOrder::Big));
+    }
+
+    #[test]
+    fn smbus_words_are_little_endian_on_the_wire() {
+        let server = FakeServer::new().reply(&[0x34, 0x12]);
+        let i2c = handle(&server).with_byte_order(ByteOrder::Big);
+
+        assert_eq!(i2c.smbus_read_word(0x8B), Ok(0x1234));
+        i2c.smbus_write_word(0x21, 0xBEEF).unwrap();
+        assert_eq!(
+            server.requests(),
+            [
+                Request::ReadReg { address: 0x48, reg: std::vec![0x8B], len: 2 },
+                Request::Write { address: 0x48, bytes: std::vec![0x21, 0xEF, 0xBE] },
+            ]
+        );
+    }
+
+    #[test]
+    fn auto_increment_shows_as_differing_bytes() {
+        let server = FakeServer::new().reply(&[0x33, 0x01]).reply(&[0x33, 0x33]);
+        let i2c = handle(&server);
+
+        assert_eq!(i2c.probe_auto_increment(0x0F), Ok(true));
+        assert_eq!(i2c.probe_auto_increment(0x0F), Ok(false));
+        assert_eq!(
+            server.requests()[0],
+            Request::ReadReg { address: 0x48, reg: std::vec![0x0F], len: 2 }
+        );
+    }
+
+    #[test]
+    fn mock_latency_goes_through_its_delay() {
+        let mut mock = MockI2c::new().with_delay(FakeDelay::default());
+        mock.expect_write_delayed(SevenBitAddr(0x48), &[0x01], 3 * MS);
+        mock.expect_read(SevenBitAddr(0x48), &[0x00]);
+        mock.expect_write_read_delayed(SevenBitAddr(0x48), &[0x02], &[0x7F], 5 * MS);
+
+        mock.write(SevenBitAddr(0x48), &[0x01]).unwrap();
+        assert_eq!(mock.delay().total, 3 * MS);
+        mock.read(SevenBitAddr(0x48), &mut [0u8]).unwrap();
+        assert_eq!(mock.delay().total, 3 * MS);
+        let mut byte = [0u8];
+        mock.write_read(SevenBitAddr(0x48), &[0x02], &mut byte).unwrap();
+        assert_eq!(byte, [0x7F]);
+        assert_eq!(mock.delay().total, 8 * MS);
+        mock.verify_complete();
+    }
+
+    #[test]
+    fn mapped_errors_take_the_driver_type() {
+        #[derive(Debug, PartialEq)]
+        enum DriverError {
+            Bus(ErrorKind),
+        }
+
+        impl embedded_hal::i2c::Error for DriverError {
+            fn kind(&self) -> ErrorKind {
+                match self {
+                    DriverError::Bus(kind) => *kind,
+                }
+            }
+        }
+
+        let server = FakeServer::new().reply(&[]).fail(ResponseCode::AddressNackSentEarly);
+        let mut i2c = MappedI2c::new(handle(&server), |err: HubrisI2cError| {
+            DriverError::Bus(err.kind())
+        });
+
+        assert_eq!(i2c.write(SevenBitAddr(0x48), &[0x01]), Ok(()));
+        assert_eq!(
+            i2c.write(SevenBitAddr(0x48), &[0x01]),
+            Err(DriverError::Bus(ErrorKind::NoAcknowledge(NoAcknowledgeSource::Address)))
+        );
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[test]
+    fn detailed_transaction_reports_the_failed_index() {
+        let server = FakeServer::new().reply(&[]).fail(ResponseCode::DataNackSent);
+        let mut i2c = handle(&server);
+
+        let mut buffer = [0u8; 2];
+        let err = i2c
+            .transaction_detailed(&mut [
+                Operation::Write(&[0x10]),
+                Operation::Write(&[]),
+                Operation::Read(&mut buffer),
+                Operation::Write(&[0x11]),
+            ])
+            .unwrap_err();
+        assert_eq!(err.failed_index, 2);
+        assert_eq!(err.source.response_code, ResponseCode::DataNackSent);
+        assert_eq!(
+            server.requests(),
+            [
+                Request::Write { address: 0x48, bytes: std::vec![0x10] },
+                Request::Read { address: 0x48, len: 2 },
+            ]
+        );
+    }
+
+    #[test]
+    fn special_addresses_match_the_specs() {
+        assert_eq!(SevenBitAddr::GENERAL_CALL.get(), 0x00);
+        assert_eq!(SevenBitAddr::ALERT_RESPONSE.get(), 0x0C);
+        assert_eq!(SevenBitAddr::ARP.get(), 0x61);
+        assert_eq!(SevenBitAddr::DEVICE_ID.get(), 0x7C);
+
+        // Reserved ones are only reachable through the constants
+        assert!(SevenBitAddr::try_new(SevenBitAddr::GENERAL_CALL.get()).is_err());
+        assert!(SevenBitAddr::try_new(SevenBitAddr::DEVICE_ID.get()).is_err());
+        assert_eq!(SevenBitAddr::try_new(0x08), Ok(SevenBitAddr::SMBUS_HOST));
+    }
+
+    #[test]
+    fn conversion_waits_before_reading_the_result() {
+        let server = FakeServer::new().reply(&[]).reply(&[0x12, 0x34]);
+        let mut delay = FakeDelay::default();
+        let mut result = [0u8; 2];
+
+        handle(&server)
+            .trigger_and_read(&[0x01, 0x81], 8 * MS, 0x00, &mut result, &mut delay)
+            .unwrap();
+        assert_eq!(result, [0x12, 0x34]);
+        assert_eq!(delay.total, 8 * MS);
+        assert_eq!(
+            server.requests(),
+            [
+                Request::Write { address: 0x48, bytes: std::vec![0x01, 0x81] },
+                Request::ReadReg { address: 0x48, reg: std::vec![0x00], len: 2 },
+            ]
+        );
+    }
+
+    #[test]
+    fn counting_tallies_bytes_including_failures() {
+        let server = FakeServer::new().fail(ResponseCode::DataNackSent);
+        let mut i2c = CountingI2c::new(handle(&server));
+
+        assert!(i2c.write(SevenBitAddr(0x48), &[0x01, 0x02, 0x03]).is_err());
+        i2c.write_read(SevenBitAddr(0x48), &[0x04], &mut [0u8; 2]).unwrap();
+        let mut buffer = [0u8; 4];
+        i2c.transaction(
+            SevenBitAddr(0x48),
+            &mut [Operation::Write(&[0x05]), Operation::Read(&mut buffer)],
+        )
+        .unwrap();
+
+        let stats = *i2c.stats();
+        assert_eq!((stats.writes, stats.write_reads, stats.transactions), (1, 1, 1));
+        assert_eq!(stats.errors, 1);
+        assert_eq!(stats.bytes_written, 5);
+        assert_eq!(stats.bytes_read, 6);
+    }
+
+    #[test]
+    fn segment_changes_are_scoped_and_sealed_handles_keep_theirs() {
+        let home = Some((Mux::M1, Segment::S1));
+        let away = Some((Mux::M1, Segment::S5));
+        let server = FakeServer::new();
+        let mut i2c = handle(&server);
+        i2c.set_segment(home);
+
+        i2c.with_segment(away, |i2c| i2c.write(SevenBitAddr(0x48), &[0x01])).unwrap();
+        assert_eq!(i2c.segment(), home);
+
+        let mut sealed = i2c.sealed();
+        sealed.write(SevenBitAddr(0x48), &[0x02]).unwrap();
+        assert_eq!(sealed.inner().segment(), home);
+        assert_eq!(server.routes(), [away, home]);
+    }
+
+    #[test]
+    fn address_widths_convert_where_the_value_fits() {
+        assert_eq!(TenBitAddr::from(SevenBitAddr(0x48)), TenBitAddr(0x048));
+        assert_eq!(SevenBitAddr::try_from(TenBitAddr(0x048)), Ok(SevenBitAddr(0x48)));
+        assert_eq!(SevenBitAddr::try_from(TenBitAddr(0x07F)), Ok(SevenBitAddr(0x7F)));
+        assert_eq!(
+            SevenBitAddr::try_from(TenBitAddr(0x080)),
+            Err(InvalidAddress::NotSevenBit(0x080))
+        );
+        assert_eq!(
+            SevenBitAddr::try_from(TenBitAddr(0x150)),
+            Err(InvalidAddress::NotSevenBit(0x150))
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn errors_chain_through_std_error() {
+        let err = TransactionError {
+            failed_index: 1,
+            source: HubrisI2cError::from(InvalidAddress::Reserved(0x7C)),
+        };
+        let boxed: std::boxed::Box<dyn std::error::Error> = std::boxed::Box::new(err);
+
+        let source = boxed.source().unwrap();
+        assert!(source.is::<HubrisI2cError>());
+        let root = source.source().unwrap();
+        assert_eq!(root.downcast_ref(), Some(&InvalidAddress::Reserved(0x7C)));
+        assert!(root.source().is_none());
+    }
+
+    #[test]
+    fn dry_run_validates_and_logs() {
+        let mut i2c = DryRunI2c::<2>::new();
+        let mut buffer = [0xEE; 2];
+
+        i2c.write_read(SevenBitAddr(0x48), &[0x00], &mut buffer).unwrap();
+        assert_eq!(buffer, [0xEE; 2]);
+        i2c.write(SevenBitAddr(0x49), &[0x01, 0x02]).unwrap();
+        i2c.read(SevenBitAddr(0x4A), &mut buffer).unwrap();
+
+        assert_eq!(
+            i2c.log(),
+            [
+                DryRunOp {
+                    kind: DryRunKind::WriteRead,
+                    address: SevenBitAddr(0x48),
+                    bytes_written: 1,
+                    bytes_read: 2,
+                },
+                DryRunOp {
+                    kind: DryRunKind::Write,
+                    address: SevenBitAddr(0x49),
+                    bytes_written: 2,
+                    bytes_read: 0,
+                },
+            ]
+        );
+        assert_eq!(i2c.dropped(), 1);
+
+        let err = i2c.write(SevenBitAddr(0x03), &[0x00]).unwrap_err();
+        assert_eq!(err.detail, Some(ErrorDetail::InvalidAddress(InvalidAddress::Reserved(0x03))));
+        let err = i2c.write(SevenBitAddr(0x48), &[0; MAX_TRANSFER_LEN + 1]).unwrap_err();
+        assert_eq!(err.response_code, ResponseCode::TooMuchData);
+
+        i2c.clear();
+        assert!(i2c.log().is_empty());
+        assert_eq!(i2c.dropped(), 0);
+    }
+
+    #[test]
+    fn broadcast_read_isolates_absent_devices() {
+        let server = FakeServer::new()
+            .with_present(&[(None, 0x48), (None, 0x4A)])
+            .reply(&[0x19])
+            .reply(&[0x1A]);
+        let addrs = [SevenBitAddr(0x48), SevenBitAddr(0x49), SevenBitAddr(0x4A)];
+
+        let mut out = [Ok(0); 3];
+        read_reg_broadcast(&bus(&server), &addrs, 0x00, &mut out).unwrap();
+        assert_eq!(out[0], Ok(0x19));
+        assert!(out[1].unwrap_err().is_device_not_found());
+        assert_eq!(out[1].unwrap_err().operation, "read_reg_broadcast");
+        assert_eq!(out[2], Ok(0x1A));
+
+        let err = read_reg_broadcast(&bus(&server), &addrs[..1], 0x00, &mut out).unwrap_err();
+        assert_eq!(err.response_code, ResponseCode::BadArg);
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[test]
+    fn smbus_bytes_send_command_then_data() {
+        let server = FakeServer::new().reply(&[0x5A]);
+        let i2c = handle(&server);
+
+        assert_eq!(i2c.smbus_read_byte(0x19), Ok(0x5A));
+        i2c.smbus_write_byte(0x01, 0x80).unwrap();
+        assert_eq!(
+            server.requests(),
+            [
+                Request::ReadReg { address: 0x48, reg: std::vec![0x19], len: 1 },
+                Request::Write { address: 0x48, bytes: std::vec![0x01, 0x80] },
+            ]
+        );
+    }
+
+    #[test]
+    fn alerts_are_collected_until_the_ara_nacks() {
+        let server = FakeServer::new()
+            .reply(&[0x48 << 1])
+            .reply(&[(0x4C << 1) | 1])
+            .fail(ResponseCode::AddressNackSentEarly);
+        let alerters = handle(&server).collect_alerts();
+        assert_eq!(alerters, [SevenBitAddr(0x48), SevenBitAddr(0x4C)]);
+        assert_eq!(server.requests(), std::vec![Request::Read { address: 0x0C, len: 1 }; 3]);
+
+        // A device that answers twice is stuck and ends the walk
+        let server = FakeServer::new()
+            .reply(&[0x48 << 1])
+            .reply(&[0x48 << 1])
+            .reply(&[0x4C << 1]);
+        assert_eq!(handle(&server).collect_alerts(), [SevenBitAddr(0x48)]);
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[test]
+    fn gate_wraps_each_operation_even_on_failure() {
+        #[derive(Default)]
+        struct Rail {
+            events: Vec<bool>,
+        }
+
+        impl PowerGate for Rail {
+            fn enable(&mut self) {
+                self.events.push(true);
+            }
+
+            fn disable(&mut self) {
+                self.events.push(false);
+            }
+        }
+
+        let server = FakeServer::new().reply(&[]).fail(ResponseCode::NoDevice);
+        let mut i2c =
+            GatedI2c::with_delay(handle(&server), Rail::default(), 2 * MS, FakeDelay::default());
+
+        i2c.write(SevenBitAddr(0x48), &[0x01]).unwrap();
+        assert!(i2c.write(SevenBitAddr(0x48), &[0x02]).is_err());
+        assert_eq!(i2c.gate().events, [true, false, true, false]);
+        assert_eq!(i2c.delay.total, 4 * MS);
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[test]
+    fn raw_response_code_is_the_server_discriminant() {
+        let server = FakeServer::new().fail(ResponseCode::NoDevice);
+        let err = handle(&server).write(SevenBitAddr(0x48), &[0x00]).unwrap_err();
+        assert_eq!(err.raw_response_code(), 3);
+        assert_eq!(err.raw_response_code(), ResponseCode::NoDevice as u32);
+    }
+
+    #[test]
+    fn bus_resets_are_told_apart_from_other_failures() {
+        let server = FakeServer::new()
+            .fail(ResponseCode::BusReset)
+            .fail(ResponseCode::BusResetMux)
+            .fail(ResponseCode::BusError);
+        let i2c = handle(&server);
+
+        assert!(i2c.read_register::<u8, u8>(0x00).unwrap_err().is_bus_reset());
+        assert!(i2c.read_register::<u8, u8>(0x00).unwrap_err().is_bus_reset());
+        assert!(!i2c.read_register::<u8, u8>(0x00).unwrap_err().is_bus_reset());
+    }
+
+    #[test]
+    fn retries_reads_but_not_writes_after_a_bus_reset() {
+        let server = FakeServer::new().fail(ResponseCode::BusReset).reply(&[0x5A]);
+        let mut i2c = RetryingI2c::new(handle(&server), 3).with_delay(FakeDelay::default());
+        let mut byte = [0u8];
+        i2c.read(SevenBitAddr(0x48), &mut byte).unwrap();
+        assert_eq!(byte, [0x5A]);
+        assert_eq!(server.requests().len(), 2);
+
+        let server = FakeServer::new().fail(ResponseCode::BusReset);
+        let mut i2c = RetryingI2c::new(handle(&server), 3).with_delay(FakeDelay::default());
+        let err = i2c.write(SevenBitAddr(0x48), &[0x10, 0x01]).unwrap_err();
+        assert!(err.is_bus_reset());
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[test]
+    fn mock_script_plays_back_in_order() {
+        let mut mock = MockI2c::from_script(&[
+            MockStep::Write(SevenBitAddr(0x48), &[0x01, 0x60]),
+            MockStep::WriteRead(SevenBitAddr(0x48), &[0x00], &[0x12, 0x34]),
+            MockStep::Fail(SevenBitAddr(0x48), ErrorKind::Bus),
+            MockStep::Read(SevenBitAddr(0x48), &[0x56]),
+        ])
+        .unwrap();
+
+        mock.write(SevenBitAddr(0x48), &[0x01, 0x60]).unwrap();
+        let mut word = [0u8; 2];
+        mock.write_read(SevenBitAddr(0x48), &[0x00], &mut word).unwrap();
+        assert_eq!(word, [0x12, 0x34]);
+        let err = mock.read(SevenBitAddr(0x48), &mut word[..1]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Bus);
+        mock.read(SevenBitAddr(0x48), &mut word[..1]).unwrap();
+        assert_eq!(word[0], 0x56);
+        mock.verify_complete();
+
+        let long = [0u8; 257];
+        let err = MockI2c::from_script(&[MockStep::Write(SevenBitAddr(0x48), &long)]).err();
+        assert_eq!(err, Some(mock::MockSetupError::StepTooLong));
+        let steps = [MockStep::Read(SevenBitAddr(0x48), &[0]); 33];
+        assert_eq!(MockI2c::from_script(&steps).err(), Some(mock::MockSetupError::TooManySteps));
+    }
+
+    #[test]
+    fn crc_words_are_checked_and_stripped() {
+        assert_eq!(CrcSpec::SENSIRION.checksum(&[0xBE, 0xEF]), 0x92);
+
+        let server = FakeServer::new()
+            .reply(&[0xBE, 0xEF, 0x92, 0xBE, 0xEF, 0x92])
+            .reply(&[0xBE, 0xEF, 0x92, 0xBE, 0xEF, 0x00]);
+        let i2c = handle(&server);
+
+        let mut payload = [0u8; 4];
+        i2c.read_with_crc(0xE0, &mut payload, CrcSpec::SENSIRION).unwrap();
+        assert_eq!(payload, [0xBE, 0xEF, 0xBE, 0xEF]);
+        assert_eq!(
+            server.requests()[0],
+            Request::ReadReg { address: 0x48, reg: std::vec![0xE0], len: 6 }
+        );
+
+        let err = i2c.read_with_crc(0xE0, &mut payload, CrcSpec::SENSIRION).unwrap_err();
+        assert_eq!(err.operation, "read_with_crc_mismatch");
+        assert!(err.is_protocol_error());
+
+        let err = i2c.read_with_crc(0xE0, &mut payload[..3], CrcSpec::SENSIRION).unwrap_err();
+        assert_eq!(err.response_code, ResponseCode::BadArg);
+
+        // 200 data bytes plus 100 CRC bytes
+        let mut long = [0u8; 200];
+        let err = i2c.read_with_crc(0xE0, &mut long, CrcSpec::SENSIRION).unwrap_err();
+        assert_eq!(err.response_code, ResponseCode::TooMuchData);
+        assert_eq!(err.detail, Some(ErrorDetail::TooLong { limit: 256, attempted: 300 }));
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[test]
+    fn logical_devices_resolve_on_every_operation() {
+        struct Straps {
+            offset: core::cell::Cell<u8>,
+        }
+
+        impl AddressResolver for Straps {
+            fn resolve(&self, logical: LogicalDevice) -> SevenBitAddr {
+                SevenBitAddr(0x48 + logical.0 + self.offset.get())
+            }
+        }
+
+        let mut mock = MockI2c::new();
+        mock.expect_write(SevenBitAddr(0x49), &[0x01]);
+        mock.expect_write_read(SevenBitAddr(0x4D), &[0x00], &[0x2A]);
+
+        let straps = Straps { offset: core::cell::Cell::new(0) };
+        let mut i2c = ResolvingI2c::new(mock, straps);
+        i2c.write(LogicalDevice(1), &[0x01]).unwrap();
+
+        i2c.resolver().offset.set(4);
+        let mut byte = [0u8];
+        i2c.write_read(LogicalDevice(1), &[0x00], &mut byte).unwrap();
+        assert_eq!(byte, [0x2A]);
+        i2c.inner().verify_complete();
+    }
+
+    #[test]
+    fn word_read_is_one_server_call() {
+        let server = FakeServer::new().reply(&[0x0C, 0x80]);
+        let i2c = RegisterOptimizedI2c::new(handle(&server).with_byte_order(ByteOrder::Big));
+
+        assert_eq!(i2c.read_reg_word(0x05), Ok(0x0C80));
+        assert_eq!(
+            server.requests(),
+            [Request::ReadReg { address: 0x48, reg: std::vec![0x05], len: 2 }]
+        );
+    }
+
+    #[test]
+    fn named_bus_errors_carry_the_name() {
+        let server = FakeServer::new().fail(ResponseCode::NoDevice);
+        let i2c = handle(&server).with_name("front_sensors");
+        assert_eq!(i2c.name(), Some("front_sensors"));
+
+        let err = i2c.read_array::<2>().unwrap_err();
+        assert_eq!(err.bus, Some("front_sensors"));
+        assert_eq!(
+            std::format!("{}", err),
+            "I2C read_array operation failed on front_sensors: NoDevice"
+        );
+
+        let server = FakeServer::new().fail(ResponseCode::NoDevice);
+        let err = handle(&server).read_array::<2>().unwrap_err();
+        assert_eq!(err.bus, None);
+        assert_eq!(std::format!("{}", err), "I2C read_array operation failed: NoDevice");
+    }
+
+    #[test]
+    fn array_reads_return_by_value() {
+        let server = FakeServer::new().reply(&[1, 2, 3]).reply(&[4, 5]);
+        let i2c = handle(&server);
+
+        assert_eq!(i2c.read_array::<3>().unwrap(), [1, 2, 3]);
+        assert_eq!(i2c.read_reg_array::<2>(0x10).unwrap(), [4, 5]);
+        assert_eq!(
+            server.requests(),
+            [
+                Request::Read { address: 0x48, len: 3 },
+                Request::ReadReg { address: 0x48, reg: std::vec![0x10], len: 2 },
+            ]
+        );
+    }
+
+    #[test]
+    fn oversized_array_reads_never_reach_the_bus() {
+        let server = FakeServer::new();
+        let err = handle(&server).read_array::<{ MAX_TRANSFER_LEN + 1 }>().unwrap_err();
+        assert_eq!(err.response_code, ResponseCode::TooMuchData);
+        assert_eq!(
+            err.detail,
+            Some(ErrorDetail::TooLong { limit: MAX_TRANSFER_LEN, attempted: MAX_TRANSFER_LEN + 1 })
+        );
+        assert!(server.requests().is_empty());
+    }
+
+    #[test]
+    fn fault_injection_replays_from_the_seed() {
+        fn outcomes(seed: u64) -> Vec<bool> {
+            let bus = NullI2c::<core::convert::Infallible>::new();
+            let mut i2c = FaultInjectingI2c::new(bus, seed, 500);
+            (0..64).map(|_| i2c.write(SevenBitAddr(0x48), &[0]).is_err()).collect()
+        }
+
+        let run = outcomes(7);
+        assert_eq!(run, outcomes(7));
+        assert_ne!(run, outcomes(8));
+        assert!(run.contains(&true) && run.contains(&false));
+        assert_eq!(outcomes(0), outcomes(0));
+    }
+
+    #[test]
+    fn injected_faults_are_never_issued() {
+        let server = FakeServer::new();
+        let mut i2c = FaultInjectingI2c::new(handle(&server), 1, 1000);
+        let err = i2c.write(SevenBitAddr(0x48), &[0x01]).unwrap_err();
+        assert!(matches!(err, FaultError::Injected(_)));
+        assert_eq!(i2c.operation_index(), 1);
+        assert_eq!(i2c.seed(), 1);
+        assert!(server.requests().is_empty());
+
+        let server = FakeServer::new();
+        let mut i2c = FaultInjectingI2c::new(handle(&server), 1, 0);
+        i2c.write(SevenBitAddr(0x48), &[0x01]).unwrap();
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[test]
+    fn signed_registers_sign_extend_from_their_width() {
+        let server = FakeServer::new()
+            .reply(&[0x0F, 0xFF])
+            .reply(&[0x08, 0x00])
+            .reply(&[0x07, 0xFF])
+            .reply(&[0x80]);
+        let i2c = handle(&server).with_byte_order(ByteOrder::Big);
+
+        assert_eq!(i2c.read_signed_register(0x00, 12).unwrap(), -1);
+        assert_eq!(i2c.read_signed_register(0x00, 12).unwrap(), -2048);
+        assert_eq!(i2c.read_signed_register(0x00, 12).unwrap(), 2047);
+        assert_eq!(i2c.read_signed_register(0x01, 8).unwrap(), -128);
+        assert_eq!(
+            server.requests()[3],
+            Request::ReadReg { address: 0x48, reg: std::vec![0x01], len: 1 }
+        );
+
+        let server = FakeServer::new().reply(&[0x00, 0x80]);
+        let i2c = handle(&server).with_byte_order(ByteOrder::Little);
+        assert_eq!(i2c.read_signed_register(0x00, 16).unwrap(), -32768);
+    }
+
+    #[test]
+    fn signed_register_width_is_checked_first() {
+        let server = FakeServer::new();
+        let i2c = handle(&server);
+        for bits in [0, 33] {
+            let err = i2c.read_signed_register(0x00, bits).unwrap_err();
+            assert_eq!(err.response_code, ResponseCode::BadArg);
+        }
+        assert!(server.requests().is_empty());
+    }
+
+    #[test]
+    fn pmbus_page_is_cached_and_restored() {
+        let server = FakeServer::new().reply(&[0x00]);
+        let mut pmbus = PmbusI2c::new(handle(&server), SevenBitAddr(0x48));
+
+        assert_eq!(pmbus.page().unwrap(), 0);
+        assert_eq!(pmbus.page().unwrap(), 0);
+        pmbus.set_page(0).unwrap();
+        let vout = pmbus.with_page(1, |pmbus| pmbus.read_word(0x8B)).unwrap();
+        assert_eq!(vout.unwrap(), 0);
+
+        assert_eq!(
+            server.requests(),
+            [
+                Request::ReadReg { address: 0x48, reg: std::vec![PMBUS_PAGE], len: 1 },
+                Request::Write { address: 0x48, bytes: std::vec![PMBUS_PAGE, 1] },
+                Request::ReadReg { address: 0x48, reg: std::vec![0x8B], len: 2 },
+                Request::Write { address: 0x48, bytes: std::vec![PMBUS_PAGE, 0] },
+            ]
+        );
+    }
+
+    #[test]
+    fn failed_page_write_forgets_the_page() {
+        let server = FakeServer::new().fail(ResponseCode::BusError);
+        let mut pmbus = PmbusI2c::new(handle(&server), SevenBitAddr(0x48));
+
+        assert!(pmbus.set_page(2).is_err());
+        pmbus.page().unwrap();
+        assert_eq!(
+            server.requests()[1],
+            Request::ReadReg { address: 0x48, reg: std::vec![PMBUS_PAGE], len: 1 }
+        );
+    }
+
+    #[test]
+    fn default_classifier_accepts_reads_and_register_reads() {
+        let mut byte = [0u8];
+        assert!(reads_are_idempotent(&RetryOp::Read));
+        assert!(reads_are_idempotent(&RetryOp::WriteRead(&[0x05])));
+        assert!(!reads_are_idempotent(&RetryOp::WriteRead(&[0x05, 0x06])));
+        assert!(!reads_are_idempotent(&RetryOp::Write(&[0x05])));
+        assert!(reads_are_idempotent(&RetryOp::Transaction(&[
+            Operation::Write(&[0x05]),
+            Operation::Read(&mut byte),
+        ])));
+        assert!(!reads_are_idempotent(&RetryOp::Transaction(&[
+            Operation::Write(&[0x05, 0x06]),
+        ])));
+    }
+
+    #[test]
+    fn writes_are_retried_only_when_classified_idempotent() {
+        let server = FakeServer::new().fail(ResponseCode::ArbitrationLost);
+        let mut i2c = RetryingI2c::new(handle(&server), 3).with_delay(FakeDelay::default());
+        assert!(i2c.write(SevenBitAddr(0x48), &[0x10, 0x01]).is_err());
+        assert_eq!(server.requests().len(), 1);
+        assert_eq!(i2c.delay.total, core::time::Duration::ZERO);
+
+        let server = FakeServer::new().fail(ResponseCode::ArbitrationLost);
+        let mut i2c = RetryingI2c::new(handle(&server), 3)
+            .with_idempotency(|_| true)
+            .with_delay(FakeDelay::default());
+        i2c.write(SevenBitAddr(0x48), &[0x10, 0x01]).unwrap();
+        assert_eq!(server.requests().len(), 2);
+        assert_eq!(i2c.delay.total, 10 * MS);
+    }
+
+    #[test]
+    fn bus_reset_writes_are_not_retried_even_when_idempotent() {
+        for code in [ResponseCode::BusReset, ResponseCode::BusResetMux] {
+            let server = FakeServer::new().fail(code).fail(code).fail(code);
+            let mut i2c = RetryingI2c::new(handle(&server), 3)
+                .with_idempotency(|_| true)
+                .with_delay(FakeDelay::default());
+
+            let err = i2c.write(SevenBitAddr(0x48), &[0x10, 0x01]).unwrap_err();
+            assert_eq!(err.response_code, code);
+            assert_eq!(server.requests().len(), 1);
+
+            let mut value = [0u8];
+            let err = i2c.write_read(SevenBitAddr(0x48), &[0x10], &mut value).unwrap_err();
+            assert_eq!(err.response_code, code);
+            assert_eq!(server.requests().len(), 2);
+
+            // Pure reads are still retried
+            i2c.read(SevenBitAddr(0x48), &mut value).unwrap();
+            assert_eq!(server.requests().len(), 4);
+        }
+    }
+
+    #[test]
+    fn string_blocks_trim_padding() {
+        let server = FakeServer::new().reply(b"LM75B \0\0").reply(&[0x4C, 0xFF]);
+        let i2c = handle(&server);
+
+        let mut buffer = [0u8; 32];
+        assert_eq!(i2c.read_string_block(0x9A, &mut buffer).unwrap(), "LM75B");
+        assert_eq!(
+            server.requests(),
+            [Request::ReadBlock { address: 0x48, reg: std::vec![0x9A], len: 32 }]
+        );
+
+        let err = i2c.read_string_block(0x9A, &mut buffer).unwrap_err();
+        assert_eq!(err.response_code, ResponseCode::BadResponse);
+        assert_eq!(err.operation, "read_string_block_encoding");
+    }
+
+    #[test]
+    fn strict_transactions_reject_empty_operations_up_front() {
+        let server = FakeServer::new();
+        let mut i2c = handle(&server);
+
+        let mut empty = [0u8; 0];
+        let err = i2c
+            .transaction_strict(&mut [Operation::Write(&[0x01]), Operation::Read(&mut empty)])
+            .unwrap_err();
+        assert_eq!(err.response_code, ResponseCode::BadArg);
+        assert!(server.requests().is_empty());
+
+        i2c.transaction_strict(&mut [Operation::Write(&[0x01, 0x02])]).unwrap();
+        assert_eq!(
+            server.requests(),
+            [Request::Write { address: 0x48, bytes: std::vec![0x01, 0x02] }]
+        );
+    }
+
+    #[test]
+    fn linear11_decodes_signed_mantissa_and_exponent() {
+        // (word, value, thousandths)
+        let cases: [(u16, f32, i64); 4] = [
+            (0xE864, 12.5, 12_500),
+            (0x1003, 12.0, 12_000),
+            (0x07FF, -1.0, -1_000),
+            (0xE7FF, -0.0625, -63),
+        ];
+
+        for (word, value, milli) in cases {
+            let server = FakeServer::new()
+                .reply(&word.to_le_bytes())
+                .reply(&word.to_le_bytes());
+            let i2c = handle(&server);
+            assert_eq!(i2c.read_linear11(0x8C).unwrap(), value);
+            assert_eq!(i2c.read_linear11_milli(0x8C).unwrap(), milli);
+        }
+    }
+
+    #[test]
+    fn temperatures_scale_big_endian_readings() {
+        let server = FakeServer::new()
+            .reply(&[0x0C, 0x80])
+            .reply(&[0x19, 0x00])
+            .reply(&[0xE7, 0x00]);
+        let i2c = handle(&server);
+
+        assert_eq!(i2c.read_temperature_millicelsius(0x00, TempScale::TMP117).unwrap(), 25_000);
+        assert_eq!(i2c.read_temperature_millicelsius(0x00, TempScale::LM75).unwrap(), 25_000);
+        assert_eq!(i2c.read_temperature_millicelsius(0x00, TempScale::LM75).unwrap(), -25_000);
+        assert_eq!(
+            server.requests()[0],
+            Request::ReadReg { address: 0x48, reg: std::vec![0x00], len: 2 }
+        );
+    }
+
+    #[test]
+    fn unusable_temperature_scales_fail() {
+        let broken = TempScale { numerator: 1, denominator: 0 };
+        assert_eq!(broken.to_millicelsius(100), None);
+        assert_eq!(TempScale { numerator: i32::MAX, denominator: 1 }.to_millicelsius(2), None);
+
+        let server = FakeServer::new();
+        let err = handle(&server).read_temperature_millicelsius(0x00, broken).unwrap_err();
+        assert_eq!(err.response_code, ResponseCode::BadArg);
+    }
+
+    #[test]
+    fn raw_transactions_map_segments_to_requests() {
+        let server = FakeServer::new().reply(&[]).reply(&[0xAB, 0xCD]);
+        let i2c = handle(&server);
+
+        let mut value = [0u8; 2];
+        let transaction = RawTransaction::new()
+            .start()
+            .write(&[0x01, 0x60])
+            .stop()
+            .start()
+            .write(&[0x00])
+            .repeated_start()
+            .read(&mut value)
+            .stop();
+        i2c.raw_transaction(transaction).unwrap();
+
+        assert_eq!(value, [0xAB, 0xCD]);
+        assert_eq!(
+            server.requests(),
+            [
+                Request::Write { address: 0x48, bytes: std::vec![0x01, 0x60] },
+                Request::ReadReg { address: 0x48, reg: std::vec![0x00], len: 2 },
+            ]
+        );
+    }
+
+    #[test]
+    fn malformed_raw_sequences_issue_nothing() {
+        let server = FakeServer::new();
+        let i2c = handle(&server);
+        let mut byte = [0u8];
+
+        // Direction change without a repeated START
+        let missing_restart = RawTransaction::new().start().write(&[0x00]).read(&mut byte).stop();
+        let err = i2c.raw_transaction(missing_restart).unwrap_err();
+        assert_eq!(err.response_code, ResponseCode::BadArg);
+
+        // A valid first segment is not issued when a later one is bad
+        let unterminated = RawTransaction::new().start().write(&[0x01]).stop().start();
+        let err = i2c.raw_transaction(unterminated).unwrap_err();
+        assert_eq!(err.response_code, ResponseCode::BadArg);
+
+        // Multi-byte pointer before a read has no server request
+        let mut byte = [0u8];
+        let wide_pointer = RawTransaction::new()
+            .start()
+            .write(&[0x00, 0x01])
+            .repeated_start()
+            .read(&mut byte)
+            .stop();
+        let err = i2c.raw_transaction(wide_pointer).unwrap_err();
+        assert_eq!(err.response_code, ResponseCode::OperationNotSupported);
+        assert_eq!(err.detail, Some(ErrorDetail::Unsupported(UnsupportedFeature::RawSequence)));
+
+        assert!(server.requests().is_empty());
+    }
+
+    #[test]
+    fn pec_is_chosen_per_call() {
+        let server = FakeServer::new()
+            .reply(&[])
+            .reply(&[0x5A, 0x48])
+            .reply(&[])
+            .reply(&[0x34, 0x12, 0x98])
+            .reply(&[0x5A]);
+        let i2c = handle(&server);
+
+        i2c.smbus_write_byte_pec(0x01, 0x60).unwrap();
+        assert_eq!(i2c.smbus_read_byte_pec(0x01).unwrap(), 0x5A);
+        i2c.smbus_write_word_pec(0x02, 0x1234).unwrap();
+        assert_eq!(i2c.smbus_read_word_pec(0x02).unwrap(), 0x1234);
+        assert_eq!(i2c.smbus_read_byte(0x01).unwrap(), 0x5A);
+
+        assert_eq!(
+            server.requests(),
+            [
+                Request::Write { address: 0x48, bytes: std::vec![0x01, 0x60, 0x9B] },
+                Request::ReadReg { address: 0x48, reg: std::vec![0x01], len: 2 },
+                Request::Write { address: 0x48, bytes: std::vec![0x02, 0x34, 0x12, 0x53] },
+                Request::ReadReg { address: 0x48, reg: std::vec![0x02], len: 3 },
+                Request::ReadReg { address: 0x48, reg: std::vec![0x01], len: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn pec_mismatch_is_a_protocol_error() {
+        let server = FakeServer::new().reply(&[0x5A, 0x49]);
+        let err = handle(&server).smbus_read_byte_pec(0x01).unwrap_err();
+        assert_eq!(err.response_code, ResponseCode::BadResponse);
+        assert_eq!(err.operation, "smbus_pec_mismatch");
+        assert!(err.is_protocol_error());
+    }
+
+    #[test]
+    fn structs_read_consecutive_registers_as_is() {
+        use zerocopy::byteorder::{BigEndian, I16};
+
+        let server = FakeServer::new().reply(&[0x00, 0x10, 0xFF, 0xF0, 0x40, 0x00]);
+        let axes: [I16<BigEndian>; 3] = handle(&server).read_struct(0x28).unwrap();
+
+        assert_eq!(axes.map(|axis| axis.get()), [16, -16, 16384]);
+        assert_eq!(
+            server.requests(),
+            [Request::ReadReg { address: 0x48, reg: std::vec![0x28], len: 6 }]
+        );
+    }
+
+    #[test]
+    fn oversized_structs_never_reach_the_bus() {
+        let server = FakeServer::new();
+        let err = handle(&server)
+            .read_struct::<[u8; MAX_TRANSFER_LEN + 1]>(0x00)
+            .unwrap_err();
+        assert_eq!(err.response_code, ResponseCode::TooMuchData);
+        assert!(server.requests().is_empty());
+    }
+
+    #[test]
+    fn shared_proxies_reach_one_bus() {
+        let server = FakeServer::new().reply(&[]).reply(&[0x5A]);
+        let bus = RefCell::new(handle(&server));
+        let mut first = SharedHubrisI2c::new(&bus);
+        let mut second = first;
+
+        first.write(SevenBitAddr(0x48), &[0x01, 0x02]).unwrap();
+        assert_eq!(read_id(&mut second).unwrap(), 0x5A);
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[test]
+    fn reentrant_use_is_busy_instead_of_a_panic() {
+        let server = FakeServer::new();
+        let bus = RefCell::new(handle(&server));
+        let mut proxy = SharedHubrisI2c::new(&bus);
+
+        let in_progress = bus.borrow_mut();
+        let err = proxy.write(SevenBitAddr(0x48), &[0x01]).unwrap_err();
+        assert!(matches!(err, SharedBusError::Busy));
+        assert_eq!(err.kind(), ErrorKind::Other);
+        drop(in_progress);
+
+        proxy.write(SevenBitAddr(0x48), &[0x01]).unwrap();
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[test]
+    fn paired_register_reads_collapse_in_one_transaction() {
+        let server = FakeServer::new().reply(&[0x11, 0x22]).reply(&[0x33]);
+        let mut i2c = RegisterOptimizedI2c::new(handle(&server));
+
+        let mut status = [0u8; 2];
+        let mut config = [0u8; 1];
+        i2c.transaction(
+            SevenBitAddr(0x48),
+            &mut [
+                Operation::Write(&[0x00]),
+                Operation::Read(&mut status),
+                Operation::Write(&[0x01]),
+                Operation::Read(&mut config),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!((status, config), ([0x11, 0x22], [0x33]));
+        assert_eq!(
+            server.requests(),
+            [
+                Request::ReadReg { address: 0x48, reg: std::vec![0x00], len: 2 },
+                Request::ReadReg { address: 0x48, reg: std::vec![0x01], len: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn unpaired_operations_run_in_order() {
+        let server = FakeServer::new();
+        let mut i2c = RegisterOptimizedI2c::new(handle(&server));
+
+        let mut value = [0u8; 1];
+        i2c.transaction(
+            SevenBitAddr(0x48),
+            &mut [
+                Operation::Write(&[0x02, 0x80]),
+                Operation::Write(&[0x03]),
+                Operation::Read(&mut value),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(
+            server.requests(),
+            [
+                Request::Write { address: 0x48, bytes: std::vec![0x02, 0x80] },
+                Request::ReadReg { address: 0x48, reg: std::vec![0x03], len: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn gang_writes_stop_at_the_first_failure() {
+        let server = FakeServer::new().with_present(&[(None, 0x48), (None, 0x49), (None, 0x4B)]);
+        let i2c = handle(&server);
+        let bank = [SevenBitAddr(0x48), SevenBitAddr(0x49), SevenBitAddr(0x4A), SevenBitAddr(0x4B)];
+
+        i2c.write_gang(&bank[..2], &[0x01, 0x60]).unwrap();
+        let err = i2c.write_gang(&bank, &[0x01, 0x60]).unwrap_err();
+        assert_eq!(err.failed_index, 2);
+        assert_eq!(err.source.response_code, ResponseCode::AddressNackSentEarly);
+
+        let written = |address| Request::Write { address, bytes: std::vec![0x01, 0x60] };
+        assert_eq!(
+            server.requests(),
+            [written(0x48), written(0x49), written(0x48), written(0x49), written(0x4A)]
+        );
+    }
+
+    #[test]
+    fn repeated_reads_resend_the_register_per_sample() {
+        let server = FakeServer::new().reply(&[10]).reply(&[11]).reply(&[12]);
+        let i2c = handle(&server);
+
+        let mut samples = [0u8; 3];
+        i2c.read_repeated(0x07, &mut samples).unwrap();
+        assert_eq!(samples, [10, 11, 12]);
+        assert_eq!(
+            server.requests(),
+            std::vec![Request::ReadReg { address: 0x48, reg: std::vec![0x07], len: 1 }; 3]
+        );
+
+        let server = FakeServer::new().reply(&[10]).fail(ResponseCode::BusError);
+        let err = handle(&server).read_repeated(0x07, &mut samples).unwrap_err();
+        assert_eq!(err.operation, "read_repeated");
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[test]
+    fn write_reads_choose_combined_or_split_per_call() {
+        let server = FakeServer::new().reply(&[0xAA]).reply(&[]).reply(&[0xBB]);
+        let i2c = handle(&server);
+
+        let mut byte = [0u8];
+        i2c.write_read_optimized(0x05, &mut byte).unwrap();
+        assert_eq!(byte, [0xAA]);
+        i2c.write_read_split(&[0x05], &mut byte).unwrap();
+        assert_eq!(byte, [0xBB]);
+
+        assert_eq!(
+            server.requests(),
+            [
+                Request::ReadReg { address: 0x48, reg: std::vec![0x05], len: 1 },
+                Request::Write { address: 0x48, bytes: std::vec![0x05] },
+                Request::Read { address: 0x48, len: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn split_write_read_reports_the_failed_phase() {
+        let server = FakeServer::new().fail(ResponseCode::NoDevice);
+        let mut byte = [0u8];
+        let err = handle(&server).write_read_split(&[0x05], &mut byte).unwrap_err();
+        assert_eq!(err.operation, "write_read_write_phase");
+        assert_eq!(server.requests().len(), 1);
+
+        let server = FakeServer::new().reply(&[]).fail(ResponseCode::NoDevice);
+        let err = handle(&server).write_read_split(&[0x05], &mut byte).unwrap_err();
+        assert_eq!(err.operation, "write_read_read_phase");
+    }
+
+    #[test]
+    fn crc_writes_frame_each_word_after_the_command() {
+        let server = FakeServer::new();
+        let i2c = handle(&server);
+
+        let data = [0xBE, 0xEF, 0xBE, 0xEF];
+        i2c.write_with_crc(&[0x36, 0x1E], &data, CrcSpec::SENSIRION).unwrap();
+        let per_block = CrcSpec { coverage: CrcCoverage::PerBlock, ..CrcSpec::SENSIRION };
+        i2c.write_with_crc(&[0x36], &data, per_block).unwrap();
+
+        assert_eq!(
+            server.requests(),
+            [
+                Request::Write {
+                    address: 0x48,
+                    bytes: std::vec![0x36, 0x1E, 0xBE, 0xEF, 0x92, 0xBE, 0xEF, 0x92],
+                },
+                Request::Write {
+                    address: 0x48,
+                    bytes: std::vec![0x36, 0xBE, 0xEF, 0xBE, 0xEF, 0x87],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn crc_writes_reject_partial_words_and_oversized_frames() {
+        let server = FakeServer::new();
+        let i2c = handle(&server);
+
+        for data in [&[][..], &[0xBE, 0xEF, 0xBE][..]] {
+            let err = i2c.write_with_crc(&[0x36], data, CrcSpec::SENSIRION).unwrap_err();
+            assert_eq!(err.response_code, ResponseCode::BadArg);
+        }
+
+        // One command byte, 200 data bytes and 100 CRC bytes
+        let err = i2c.write_with_crc(&[0x36], &[0u8; 200], CrcSpec::SENSIRION).unwrap_err();
+        assert_eq!(err.response_code, ResponseCode::TooMuchData);
+        assert_eq!(err.detail, Some(ErrorDetail::TooLong { limit: 256, attempted: 301 }));
+        assert!(server.requests().is_empty());
+    }
+
+    #[cfg(feature = "log")]
+    #[test]
+    fn logging_traces_each_operation_and_its_outcome() {
+        use std::string::{String, ToString};
+        use std::sync::Mutex;
+
+        /// Collects `i2c` events; the only logger in the test binary
+        struct Capture(Mutex<Vec<String>>);
+
+        impl log::Log for Capture {
+            fn enabled(&self, metadata: &log::Metadata<'_>) -> bool {
+                metadata.target() == "i2c"
+            }
+
+            fn log(&self, record: &log::Record<'_>) {
+                if self.enabled(record.metadata()) {
+                    self.0.lock().unwrap().push(record.args().to_string());
+                }
+            }
+
+            fn flush(&self) {}
+        }
+
+        static CAPTURE: Capture = Capture(Mutex::new(Vec::new()));
+        log::set_logger(&CAPTURE).unwrap();
+        log::set_max_level(log::LevelFilter::Trace);
+
+        let server = FakeServer::new().reply(&[]).reply(&[0x5A]).fail(ResponseCode::NoDevice);
+        let mut i2c = LoggingI2c::new(handle(&server));
+
+        i2c.write(SevenBitAddr(0x48), &[0x01, 0x02]).unwrap();
+        let mut byte = [0u8];
+        i2c.write_read(SevenBitAddr(0x48), &[0x0F], &mut byte).unwrap();
+        assert!(i2c.read(SevenBitAddr(0x48), &mut byte).is_err());
+
+        let events = CAPTURE.0.lock().unwrap();
+        assert_eq!(events[0], "write addr=0x48 len=2 ok");
+        assert_eq!(events[1], "write_read addr=0x48 len=2 ok");
+        assert!(events[2].starts_with("read addr=0x48 len=1 failed: "));
+        assert_eq!(events.len(), 3);
+        assert_eq!(byte, [0x5A]);
+    }
+
+    #[test]
+    fn absent_devices_are_not_probed_within_the_ttl() {
+        let server = FakeServer::new().fail(ResponseCode::NoDevice);
+        let clock = SteppingClock {
+            now: core::cell::Cell::new(core::time::Duration::ZERO),
+            step: 10 * MS,
+        };
+        let mut i2c: CachingPresenceI2c<_, _> =
+            CachingPresenceI2c::new(handle(&server), clock, 25 * MS);
+        let mut byte = [0u8];
+
+        // t=0 probes, t=10 and t=20 are answered from the cache
+        assert!(i2c.read(SevenBitAddr(0x48), &mut byte).is_err());
+        for _ in 0..2 {
+            let err = i2c.read(SevenBitAddr(0x48), &mut byte).unwrap_err();
+            assert_eq!(err.response_code, ResponseCode::NoDevice);
+            assert_eq!(err.operation, "presence_cached");
+        }
+        assert_eq!(server.requests().len(), 1);
+
+        // t=30 is past the TTL; the success clears the entry for t=40
+        i2c.read(SevenBitAddr(0x48), &mut byte).unwrap();
+        i2c.read(SevenBitAddr(0x48), &mut byte).unwrap();
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[test]
+    fn only_device_not_found_is_cached() {
+        let server = FakeServer::new().fail(ResponseCode::BusError).fail(ResponseCode::NoDevice);
+        let clock = SteppingClock {
+            now: core::cell::Cell::new(core::time::Duration::ZERO),
+            step: core::time::Duration::ZERO,
+        };
+        let mut i2c: CachingPresenceI2c<_, _> =
+            CachingPresenceI2c::new(handle(&server), clock, 25 * MS);
+        let mut byte = [0u8];
+
+        assert!(i2c.read(SevenBitAddr(0x48), &mut byte).is_err());
+        assert!(i2c.read(SevenBitAddr(0x48), &mut byte).is_err());
+        assert_eq!(server.requests().len(), 2);
+
+        i2c.invalidate(SevenBitAddr(0x48));
+        i2c.read(SevenBitAddr(0x48), &mut byte).unwrap();
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[test]
+    fn register_maps_decode_the_read_value() {
+        let server = FakeServer::new().reply(&[0b1010_0001]);
+        let i2c = handle(&server);
+
+        let mode = i2c.read_register_map(0x01, |bits| (bits >> 5, bits & 1 != 0)).unwrap();
+        assert_eq!(mode, (0b101, true));
+        assert_eq!(
+            server.requests(),
+            [Request::ReadReg { address: 0x48, reg: std::vec![0x01], len: 1 }]
+        );
+
+        let server = FakeServer::new().fail(ResponseCode::NoDevice);
+        let err = handle(&server)
+            .read_register_map(0x01, |_| -> u8 { unreachable!("decoded after a failed read") })
+            .unwrap_err();
+        assert_eq!(err.operation, "read_register_map");
+    }
+
+    #[test]
+    fn segment_walks_report_the_first_failure() {
+        let segments = [(Mux::M1, Segment::S1), (Mux::M1, Segment::S2), (Mux::M1, Segment::S3)];
+        let present = [(Some(segments[0]), 0x48), (Some(segments[2]), 0x48), (None, 0x48)];
+
+        for (stop_on_error, visited) in [(false, 3), (true, 2)] {
+            let server = FakeServer::new().with_present(&present);
+            let mut i2c = handle(&server);
+
+            let err = i2c
+                .for_each_segment(&segments, stop_on_error, |i2c| i2c.smbus_write_byte(0x01, 0x60))
+                .unwrap_err();
+            assert_eq!(err.failed_index, 1);
+            assert_eq!(err.source.response_code, ResponseCode::AddressNackSentEarly);
+
+            // The handle is back on its own segment afterwards
+            i2c.smbus_write_byte(0x01, 0x60).unwrap();
+
+            let mut routes: Vec<Route> = segments[..visited].iter().copied().map(Some).collect();
+            routes.push(None);
+            assert_eq!(server.routes(), routes);
+        }
+    }
+
+    #[test]
+    fn short_reads_below_the_minimum_fail() {
+        let server = FakeServer::new().reply(&[1, 2, 3, 4]).reply(&[1, 2]);
+        let i2c = handle(&server);
+        let mut buffer = [0u8; 8];
+
+        assert_eq!(i2c.read_at_least(0x30, &mut buffer, 4).unwrap(), 4);
+        assert_eq!(buffer[..4], [1, 2, 3, 4]);
+        let err = i2c.read_at_least(0x30, &mut buffer, 4).unwrap_err();
+        assert_eq!(err.response_code, ResponseCode::BadResponse);
+        assert_eq!(err.operation, "read_at_least_short");
+        assert!(err.is_protocol_error());
+
+        let err = i2c.read_at_least(0x30, &mut buffer, 9).unwrap_err();
+        assert_eq!(err.response_code, ResponseCode::BadArg);
+        assert_eq!(
+            server.requests(),
+            std::vec![Request::ReadReg { address: 0x48, reg: std::vec![0x30], len: 8 }; 2]
+        );
+    }
+
+    #[test]
+    fn address_policies_differ_only_on_reserved_ranges() {
+        for addr in [0x00, 0x07, 0x78, 0x7F] {
+            assert_eq!(SevenBitAddr::try_new(addr), Err(InvalidAddress::Reserved(addr)));
+            assert_eq!(
+                SevenBitAddr::try_new_with_policy(addr, AddressPolicy::AllowReserved),
+                Ok(SevenBitAddr(addr))
+            );
+        }
+        for addr in [0x08, 0x77] {
+            assert_eq!(SevenBitAddr::try_new(addr), Ok(SevenBitAddr(addr)));
+        }
+        for policy in [AddressPolicy::Strict, AddressPolicy::AllowReserved] {
+            assert_eq!(
+                SevenBitAddr::try_new_with_policy(0x80, policy),
+                Err(InvalidAddress::SevenBitRange(0x80))
+            );
+        }
+    }
+
+    #[test]
+    fn over_long_transactions_are_rejected_up_front() {
+        let server = FakeServer::new();
+        let mut i2c = handle(&server).with_max_transaction_ops(2);
+        assert_eq!(i2c.max_transaction_ops(), 2);
+        assert_eq!(handle(&server).max_transaction_ops(), DEFAULT_MAX_TRANSACTION_OPS);
+
+        let writes = || {
+            [
+                Operation::Write(&[0x01]),
+                Operation::Write(&[0x02]),
+                Operation::Write(&[0x03]),
+            ]
+        };
+
+        let err = i2c.transaction(SevenBitAddr(0x48), &mut writes()).unwrap_err();
+        assert_eq!(err.response_code, ResponseCode::BadArg);
+        assert_eq!(err.operation, "transaction_op_count");
+
+        let err = i2c.transaction_detailed(&mut writes()).unwrap_err();
+        assert_eq!((err.failed_index, err.source.operation), (0, "transaction_op_count"));
+
+        let mut optimized = RegisterOptimizedI2c::new(i2c);
+        let err = optimized.transaction(SevenBitAddr(0x48), &mut writes()).unwrap_err();
+        assert_eq!(err.operation, "transaction_op_count");
+        assert!(server.requests().is_empty());
+
+        optimized.transaction(SevenBitAddr(0x48), &mut writes()[..2]).unwrap();
+        assert_eq!(server.requests().len(), 2);
+
+        let ten_bit = FakeServer::new().with_caps(Capabilities::TEN_BIT);
+        let mut i2c = handle(&ten_bit).with_max_transaction_ops(2);
+        let err = i2c.transaction(TenBitAddr(0x150), &mut writes()).unwrap_err();
+        assert_eq!(err.operation, "transaction_op_count");
+        assert!(ten_bit.requests().is_empty());
+    }
+
+    #[test]
+    fn register_sets_read_each_register_in_order() {
+        let server = FakeServer::new().reply(&[0xA0]).reply(&[0xA5]).reply(&[0xB0]);
+        let i2c = handle(&server);
+
+        let mut out = [0u8; 3];
+        i2c.read_register_set(&[0x00, 0x05, 0x10], &mut out).unwrap();
+        assert_eq!(out, [0xA0, 0xA5, 0xB0]);
+        let regs: Vec<u8> = server
+            .requests()
+            .into_iter()
+            .map(|request| match request {
+                Request::ReadReg { reg, len: 1, .. } => reg[0],
+                other => panic!("unexpected {:?}", other),
+            })
+            .collect();
+        assert_eq!(regs, [0x00, 0x05, 0x10]);
+
+        let err = i2c.read_register_set(&[0x00, 0x05], &mut out).unwrap_err();
+        assert_eq!(err.response_code, ResponseCode::BadArg);
+        assert_eq!(server.requests().len(), 3);
+    }
+
+    #[test]
+    fn verified_writes_read_back_and_compare() {
+        let server = FakeServer::new().reply(&[]).reply(&[0x60]).reply(&[]).reply(&[0x20]);
+        let i2c = handle(&server);
+
+        i2c.write_register_verified(0x01, 0x60).unwrap();
+        let err = i2c.write_register_verified(0x01, 0x60).unwrap_err();
+        assert_eq!(
+            err,
+            VerifyError::Mismatch { reg: 0x01, expected: 0x60, actual: 0x20, mask: 0xFF, bus: None }
+        );
+        assert_eq!(HubrisI2cError::from(err).response_code, ResponseCode::BadResponse);
+
+        assert_eq!(
+            server.requests()[..2],
+            [
+                Request::Write { address: 0x48, bytes: std::vec![0x01, 0x60] },
+                Request::ReadReg { address: 0x48, reg: std::vec![0x01], len: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn masked_verified_writes_ignore_volatile_bits() {
+        // Bit 7 self-clears after the write
+        let server = FakeServer::new().reply(&[]).reply(&[0x05]).reply(&[]).reply(&[0x04]);
+        let i2c = handle(&server);
+
+        i2c.write_register_verified_masked(0x02, 0x85, 0x7F).unwrap();
+        assert!(matches!(
+            i2c.write_register_verified_masked(0x02, 0x85, 0x7F),
+            Err(VerifyError::Mismatch { actual: 0x04, .. })
+        ));
+
+        let server = FakeServer::new().fail(ResponseCode::NoDevice);
+        let err = handle(&server).write_register_verified(0x02, 0x85).unwrap_err();
+        assert!(matches!(err, VerifyError::Bus(_)));
+        assert_eq!(server.requests().len(), 1);
+    }
+
+    #[test]
+    fn poll_until_change_returns_the_first_new_value() {
+        let server = FakeServer::new().reply(&[0x05]).reply(&[0x05]).reply(&[0x09]).reply(&[0x0A]);
+        let mut delay = FakeDelay::default();
+
+        let value = handle(&server)
+            .poll_until_change(0x07, 0x05, 2 * MS, 20 * MS, &mut delay)
+            .unwrap();
+        assert_eq!(value, 0x09);
+        assert_eq!(delay.total, 4 * MS);
+        assert_eq!(
+            server.requests(),
+            std::vec![Request::ReadReg { address: 0x48, reg: std::vec![0x07], len: 1 }; 3]
+        );
+    }
+}